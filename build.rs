@@ -0,0 +1,31 @@
+// Generates the protobuf message types from config.proto when the `protobuf` feature is on.
+// The schema is parsed in pure Rust so building doesn't need protoc.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "protobuf")]
+    generate_protobuf_types();
+}
+
+#[cfg(feature = "protobuf")]
+fn generate_protobuf_types() {
+    use prost::Message;
+
+    const PROTO_DIR: &str = "src/protobuf";
+    const PROTO_FILE: &str = "src/protobuf/config.proto";
+    println!("cargo:rerun-if-changed={}", PROTO_FILE);
+
+    let descriptors = protobuf_parse::Parser::new()
+        .pure()
+        .include(PROTO_DIR)
+        .input(PROTO_FILE)
+        .file_descriptor_set()
+        .expect("Failed to parse config.proto");
+    // protobuf-parse and prost-build use different descriptor types, the wire format is shared
+    let bytes = protobuf_descriptor::Message::write_to_bytes(&descriptors)
+        .expect("Failed to encode config.proto descriptors");
+    let descriptors = prost_types::FileDescriptorSet::decode(bytes.as_slice())
+        .expect("Failed to decode config.proto descriptors");
+    prost_build::Config::new()
+        .compile_fds(descriptors)
+        .expect("Failed to generate protobuf types");
+}
@@ -11,6 +11,7 @@ public class DevCycleClient : IDisposable
 {
     private readonly string _sdkKey;
     private bool _disposed = false;
+    private bool _closed = false;
     private static bool _debugMode = true; // Enable debug logging
 
     public DevCycleClient(string sdkKey)
@@ -84,6 +85,52 @@ public class DevCycleClient : IDisposable
         }
     }
 
+    /// <summary>
+    /// Publish queued events now, returning how many were reported
+    /// </summary>
+    public long FlushEvents()
+    {
+        var sdkKeyPtr = Marshal.StringToHGlobalAnsi(_sdkKey);
+        try
+        {
+            var result = NativeMethods.devcycle_flush_events(sdkKeyPtr);
+            if (result < 0)
+            {
+                throw new DevCycleException($"Failed to flush events (error code {result}): {GetLastFFIError()}");
+            }
+            return result;
+        }
+        finally
+        {
+            Marshal.FreeHGlobal(sdkKeyPtr);
+        }
+    }
+
+    /// <summary>
+    /// Publish queued events in a final flush and release the SDK key. Called by Dispose, so
+    /// events queued before shutdown aren't lost.
+    /// </summary>
+    public void Close()
+    {
+        if (_closed)
+            return;
+        _closed = true;
+
+        var sdkKeyPtr = Marshal.StringToHGlobalAnsi(_sdkKey);
+        try
+        {
+            var result = NativeMethods.devcycle_close(sdkKeyPtr);
+            if (result != 0)
+            {
+                throw new DevCycleException($"Failed to close (error code {result}): {GetLastFFIError()}");
+            }
+        }
+        finally
+        {
+            Marshal.FreeHGlobal(sdkKeyPtr);
+        }
+    }
+
     /// <summary>
     /// Set configuration from JSON string
     /// </summary>
@@ -582,7 +629,21 @@ public class DevCycleClient : IDisposable
     {
         if (!_disposed)
         {
-            // Clean up any resources if needed
+            if (disposing)
+            {
+                try
+                {
+                    Close();
+                }
+                catch (DevCycleException ex)
+                {
+                    LogDebug($"Close failed during dispose: {ex.Message}");
+                }
+                catch (DllNotFoundException)
+                {
+                    // The native library never loaded, so there is nothing to close
+                }
+            }
             _disposed = true;
         }
     }
@@ -619,6 +680,9 @@ public class DevCycleUser
     [JsonPropertyName("country")]
     public string Country { get; set; } = string.Empty;
 
+    [JsonPropertyName("ip")]
+    public string Ip { get; set; } = string.Empty;
+
     [JsonPropertyName("language")]
     public string Language { get; set; } = string.Empty;
 
@@ -26,6 +26,20 @@ internal static class NativeMethods
         IntPtr sdk_key,
         IntPtr options);
 
+    /// <summary>
+    /// Publish queued events now
+    /// </summary>
+    /// <returns>Number of events reported on success, negative error code on error</returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern long devcycle_flush_events(IntPtr sdk_key);
+
+    /// <summary>
+    /// Stop the event queue after a final flush and unregister the SDK key
+    /// </summary>
+    /// <returns>0 on success, non-zero on error</returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern int devcycle_close(IntPtr sdk_key);
+
     /// <summary>
     /// Set config from JSON string
     /// </summary>
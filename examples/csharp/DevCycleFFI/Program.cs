@@ -113,6 +113,15 @@ class Program
                 });
             }
 
+            if (NativeAvailable)
+            {
+                // Publishes the events still queued before the process exits
+                Measure("Close client", () =>
+                {
+                    try { client.Close(); } catch (DevCycleException ex) { VerboseException(ex); }
+                });
+            }
+
             Info("Execution complete.");
         }
         catch (DllNotFoundException ex)
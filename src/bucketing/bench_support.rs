@@ -0,0 +1,56 @@
+use crate::config::ConfigBody;
+use crate::configmanager;
+use crate::target::Target;
+use crate::user::PopulatedUser;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Every target audience of an SDK key's config, for benchmarking the compiled filter plans
+/// against the slow path. Only built with the `bench` feature.
+pub struct TargetAudiences {
+    config: Arc<ConfigBody>,
+    client_custom_data: HashMap<String, Value>,
+}
+
+impl TargetAudiences {
+    pub fn for_sdk_key(sdk_key: &str) -> Option<Self> {
+        Some(TargetAudiences {
+            config: configmanager::get_config(sdk_key)?,
+            client_custom_data: HashMap::new(),
+        })
+    }
+
+    // Recompiles every audience on each call through `AudienceOperator::evaluate`, returning
+    // how many matched
+    pub fn evaluate_uncompiled(&self, user: &mut PopulatedUser) -> usize {
+        self.targets()
+            .filter(|target| {
+                target.audience.filters.evaluate(
+                    &self.config.audiences,
+                    user,
+                    &self.client_custom_data,
+                )
+            })
+            .count()
+    }
+
+    // Evaluates the plans `ConfigBody::compile` built, returning how many matched
+    pub fn evaluate_compiled(&self, user: &PopulatedUser) -> usize {
+        self.targets()
+            .filter(|target| {
+                target
+                    .compiled_audience
+                    .as_ref()
+                    .is_some_and(|plan| plan.evaluate(user, &self.client_custom_data, None))
+            })
+            .count()
+    }
+
+    fn targets(&self) -> impl Iterator<Item = &Target> {
+        self.config
+            .features
+            .iter()
+            .flat_map(|feature| feature.configuration.targets.iter())
+    }
+}
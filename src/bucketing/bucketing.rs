@@ -1,21 +1,21 @@
+use crate::bucketing::explain::{self, TargetTrace};
+use crate::client::{ClientState, client_manager};
 use crate::config::*;
 use crate::configmanager;
 use crate::constants;
 use crate::errors;
-use crate::errors::bucket_result_error_to_default_reason;
 use crate::errors::{DevCycleError, missing_config, missing_variable, missing_variation};
 use crate::events::event::{EvalDetails, EvaluationReason};
+use crate::events::event_queue::EventQueue;
 use crate::murmurhash::murmurhash;
+use crate::segmentation::optin::{OptInContext, OptInStore};
 use crate::target::*;
-use crate::target::{Rollout, RolloutStage};
 use crate::user::{BucketedUserConfig, PopulatedUser};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use std::ops::Sub;
-use std::ptr::null_mut;
-use std::sync::Arc;
 
 // Helper function to validate variable types
-fn is_variable_type_valid(actual_type: &str, expected_type: &str) -> bool {
+pub(crate) fn is_variable_type_valid(actual_type: &str, expected_type: &str) -> bool {
     // First check if the variable type is one of the valid types
     if actual_type != constants::VARIABLE_TYPES_STRING
         && actual_type != constants::VARIABLE_TYPES_NUMBER
@@ -31,26 +31,27 @@ fn is_variable_type_valid(actual_type: &str, expected_type: &str) -> bool {
     true
 }
 
+// The variable value a user is bucketed into, along with where it came from
+pub(crate) struct BucketedVariableResult {
+    pub variable_id: String,
+    pub variable_key: String,
+    pub variable_type: String,
+    pub value: serde_json::Value,
+    pub feature_id: String,
+    pub variation_id: String,
+    pub eval: EvalDetails,
+}
+
 // Helper function to generate bucketed variable for user
-async fn generate_bucketed_variable_for_user(
-    sdk_key: &str,
+pub(crate) fn generate_bucketed_variable_for_user(
+    client: &ClientState,
     user: PopulatedUser,
     variable_key: &str,
     client_custom_data: HashMap<String, serde_json::Value>,
-) -> Result<
-    (
-        String,
-        String,
-        String,
-        serde_json::Value,
-        String,
-        String,
-        EvaluationReason,
-    ),
-    (DevCycleError, EvaluationReason),
-> {
-    // Get config (already returns Arc<ConfigBody> from the CONFIGS map)
-    let config = configmanager::get_config(sdk_key);
+    now: DateTime<Utc>,
+) -> Result<BucketedVariableResult, (DevCycleError, EvaluationReason)> {
+    // Get config (already returns Arc<ConfigBody> from the client)
+    let config = client.get_config();
 
     if config.is_none() {
         return Err((missing_config(), EvaluationReason::Error));
@@ -70,25 +71,64 @@ async fn generate_bucketed_variable_for_user(
         return Err((errors::missing_feature(), EvaluationReason::Disabled));
     }
     let feat_for_variable = feat_for_variable.unwrap();
+    let opt_in_store = client.get_opt_in_store();
+
+    let (variation, eval) = match get_forced_variation(feat_for_variable, &user.user_id) {
+        Some(variation) => (
+            variation.clone(),
+            EvalDetails {
+                reason: EvaluationReason::Override,
+                details: None,
+                target_id: None,
+            },
+        ),
+        None => {
+            if !does_user_pass_prerequisites(
+                &config,
+                feat_for_variable,
+                &user,
+                &client_custom_data,
+                opt_in_store.as_ref(),
+                &mut HashMap::new(),
+                now,
+            ) {
+                return Err((
+                    errors::failed_user_does_not_meet_prerequisites(),
+                    EvaluationReason::Default,
+                ));
+            }
 
-    // Check if user qualifies for feature
-    let target_and_hashes = match does_user_qualify_for_feature(
-        &config,
-        feat_for_variable,
-        user.clone(),
-        client_custom_data,
-    ) {
-        Ok(th) => th,
-        Err(e) => return Err((e, EvaluationReason::Default)),
+            // Check if user qualifies for feature
+            let target_and_hashes = match does_user_qualify_for_feature(
+                &config,
+                feat_for_variable,
+                user.clone(),
+                client_custom_data,
+                opt_in_store.as_ref(),
+                now,
+                None,
+            ) {
+                Ok(th) => th,
+                Err(e) => return Err((e, EvaluationReason::Default)),
+            };
+
+            // Bucket user for variation
+            let target_id = target_and_hashes.target._id.clone();
+            match decide_variation_for_target(feat_for_variable, target_and_hashes) {
+                Ok((variation, reason)) => (
+                    variation,
+                    EvalDetails {
+                        reason,
+                        details: get_winning_variation(feat_for_variable)
+                            .map(|_| constants::WINNING_VARIATION_DETAILS.to_string()),
+                        target_id: Some(target_id),
+                    },
+                ),
+                Err(e) => return Err((e, EvaluationReason::Default)),
+            }
+        }
     };
 
-    // Bucket user for variation
-    let (variation, is_random_distrib) =
-        match bucket_user_for_variation(feat_for_variable, target_and_hashes.clone()) {
-            Ok(v) => v,
-            Err(e) => return Err((e, EvaluationReason::Default)),
-        };
-
     // Get variable from variation
     let variation_variable = variation.get_variable_by_id(&variable._id);
     if variation_variable.is_none() {
@@ -99,22 +139,15 @@ async fn generate_bucketed_variable_for_user(
     }
     let variation_variable = variation_variable.unwrap();
 
-    // Determine evaluation reason
-    let eval_reason = if target_and_hashes.is_rollout || is_random_distrib {
-        EvaluationReason::Split
-    } else {
-        EvaluationReason::TargetingMatch
-    };
-
-    Ok((
-        variable._id.clone(),
-        variable.key.clone(),
-        variable._type.clone(),
-        variation_variable.value.clone(),
-        feat_for_variable._id.clone(),
-        variation._id.clone(),
-        eval_reason,
-    ))
+    Ok(BucketedVariableResult {
+        variable_id: variable._id.clone(),
+        variable_key: variable.key.clone(),
+        variable_type: variable._type.clone(),
+        value: variation_variable.value.clone(),
+        feature_id: feat_for_variable._id.clone(),
+        variation_id: variation._id.clone(),
+        eval,
+    })
 }
 
 pub struct VariableForUserResult {
@@ -125,44 +158,93 @@ pub struct VariableForUserResult {
     pub feature_id: String,
     pub variation_id: String,
     pub eval_reason: Result<EvaluationReason, DevCycleError>,
+    // Extra context for the reason, e.g. "Winning Variation"
+    pub eval_details: Option<String>,
+    pub target_id: Option<String>,
     pub default_reason: String,
 }
 
-pub async fn variable_for_user(
+pub fn variable_for_user(
     sdk_key: &str,
     user: PopulatedUser,
     variable_key: &str,
     expected_variable_type: &str,
     client_custom_data: HashMap<String, serde_json::Value>,
 ) -> Result<VariableForUserResult, DevCycleError> {
-    let result =
-        generate_bucketed_variable_for_user(sdk_key, user, variable_key, client_custom_data).await;
-    let event_queue = match crate::events::event_queue_manager::get_event_queue(sdk_key) {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => variable_for_user_with_client(
+            &client.state,
+            user,
+            variable_key,
+            expected_variable_type,
+            client_custom_data,
+        ),
+        None => Err(missing_config()),
+    }
+}
+
+pub(crate) fn variable_for_user_with_client(
+    client: &ClientState,
+    user: PopulatedUser,
+    variable_key: &str,
+    expected_variable_type: &str,
+    client_custom_data: HashMap<String, serde_json::Value>,
+) -> Result<VariableForUserResult, DevCycleError> {
+    let event_queue = match client.get_event_queue() {
         Some(eq) => eq,
         None => {
-            eprintln!("Event queue not initialized for SDK key: {}", sdk_key);
+            eprintln!(
+                "Event queue not initialized for SDK key: {}",
+                client.sdk_key
+            );
             return Err(errors::event_queue_not_initialized());
         }
     };
+    evaluate_variable_for_user(
+        client,
+        user,
+        variable_key,
+        expected_variable_type,
+        client_custom_data,
+        client.now(),
+        Some(&event_queue),
+    )
+}
+
+// Evaluates the variable as of `now`, queueing evaluation events when given an event queue
+pub(crate) fn evaluate_variable_for_user(
+    client: &ClientState,
+    user: PopulatedUser,
+    variable_key: &str,
+    expected_variable_type: &str,
+    client_custom_data: HashMap<String, serde_json::Value>,
+    now: DateTime<Utc>,
+    event_queue: Option<&EventQueue>,
+) -> Result<VariableForUserResult, DevCycleError> {
+    let result =
+        generate_bucketed_variable_for_user(client, user, variable_key, client_custom_data, now);
     match result {
-        Ok((
+        Ok(BucketedVariableResult {
             variable_id,
             variable_key,
             variable_type,
-            variable_value,
+            value: variable_value,
             feature_id,
             variation_id,
-            eval_reason,
-        )) => {
+            eval,
+        }) => {
             // Validate variable type
             if !is_variable_type_valid(&variable_type, expected_variable_type)
                 && !expected_variable_type.is_empty()
             {
                 let err = errors::invalid_variable_type();
 
-                if let Err(event_err) = event_queue
-                    .queue_variable_defaulted_event(variable_key.clone().as_str(), "", "")
-                    .await
+                if let Some(event_queue) = event_queue
+                    && let Err(event_err) = event_queue.queue_variable_defaulted_event(
+                        variable_key.clone().as_str(),
+                        "",
+                        "",
+                    )
                 {
                     eprintln!("Failed to queue variable defaulted event: {}", event_err);
                 }
@@ -171,14 +253,13 @@ pub async fn variable_for_user(
             }
 
             // Queue variable evaluated event
-            if let Err(event_err) = event_queue
-                .queue_variable_evaluated_event(
+            if let Some(event_queue) = event_queue
+                && let Err(event_err) = event_queue.queue_variable_evaluated_event(
                     variable_key.clone().as_str(),
                     &feature_id,
                     &variation_id,
-                    eval_reason.clone(),
+                    eval.reason.clone(),
                 )
-                .await
             {
                 eprintln!("Failed to queue variable evaluated event: {}", event_err);
             }
@@ -190,16 +271,18 @@ pub async fn variable_for_user(
                 variation_id,
                 variable_value,
                 feature_id,
-                eval_reason: Ok(eval_reason),
+                eval_reason: Ok(eval.reason),
+                eval_details: eval.details,
+                target_id: eval.target_id,
                 default_reason: String::new(),
             })
         }
         Err((err, eval_reason)) => {
-            let default_reason = bucket_result_error_to_default_reason(&err);
+            let default_reason = err.default_reason();
 
-            if let Err(event_err) = event_queue
-                .queue_variable_defaulted_event(variable_key, "", "")
-                .await
+            if let Some(event_queue) = event_queue
+                && let Err(event_err) =
+                    event_queue.queue_variable_defaulted_event(variable_key, "", "")
             {
                 eprintln!("Failed to queue variable defaulted event: {}", event_err);
             }
@@ -213,6 +296,8 @@ pub async fn variable_for_user(
                 variable_value: serde_json::Value::Null,
                 feature_id: String::new(),
                 eval_reason: Ok(eval_reason),
+                eval_details: None,
+                target_id: None,
                 default_reason: default_reason.to_string(),
             })
         }
@@ -243,99 +328,23 @@ pub(crate) fn determine_user_bucketing_value_for_target(
     String::from(constants::DEFAULT_BUCKETING_VALUE)
 }
 
-pub(crate) fn get_current_rollout_percentage(
-    rollout: Rollout,
-    current_date: chrono::DateTime<chrono::Utc>,
-) -> f64 {
-    let start = rollout.start_percentage;
-    let rollout_start_date = rollout.start_date;
-    let current_date_time = current_date;
-
-    if rollout._type == constants::ROLLOUT_TYPE_SCHEDULE {
-        if current_date_time.gt(&rollout_start_date) {
-            return 1.0;
-        }
-        return 0.0;
-    }
-
-    let stages = rollout.stages;
-    let mut current_stages: Vec<RolloutStage> = Vec::new();
-    let mut next_stages: Vec<RolloutStage> = Vec::new();
-    for stage in stages {
-        if current_date_time > stage.date {
-            current_stages.push(stage);
-        } else {
-            next_stages.push(stage);
-        }
-    }
-
-    // Note: This function contains unsafe pointer operations that should be refactored
-    // for better Rust idioms, but keeping the logic intact for now
-    let _current_stage: *mut RolloutStage;
-    let next_stage: *mut RolloutStage;
-    if current_stages.len() == 0 {
-        _current_stage = null_mut();
-    } else {
-        let current_stages_len = current_stages.len();
-        _current_stage = &mut current_stages[current_stages_len - 1];
-    }
-
-    if next_stages.len() == 0 {
-        next_stage = null_mut();
-    } else {
-        next_stage = &mut next_stages[0];
-    }
-
-    let mut current_stage = _current_stage;
-    if _current_stage == null_mut() && rollout_start_date.lt(&current_date_time) {
-        current_stage = &mut RolloutStage {
-            _type: constants::ROLLOUT_TYPE_DISCRETE.parse().unwrap(),
-            date: rollout_start_date,
-            percentage: start,
-        }
-    }
-    if current_stage == null_mut() {
-        return 0.0;
-    }
-    unsafe {
-        if next_stage == null_mut() || (*next_stage)._type == constants::ROLLOUT_TYPE_DISCRETE {
-            return (*current_stage).percentage;
-        }
-
-        let current_date_percentage = (current_date_time
-            .sub((*current_stage).date)
-            .num_milliseconds()
-            / (*next_stage)
-                .date
-                .sub((*current_stage).date)
-                .num_milliseconds()) as f64;
-        if current_date_percentage == 0.0 {
-            return 0.0;
-        }
-        return (*current_stage).percentage
-            + (current_date_percentage * ((*next_stage).percentage - (*current_stage).percentage));
-    }
-}
-
-pub(crate) fn does_user_pass_rollout(rollout: Option<Rollout>, bounded_hash: f64) -> bool {
-    match rollout {
-        Some(r) => {
-            let current_rollout_percentage = get_current_rollout_percentage(r, chrono::Utc::now());
-            return current_rollout_percentage != 0.0 && bounded_hash <= current_rollout_percentage;
-        }
-        None => true, // No rollout means user passes by default
-    }
-}
-
 pub(crate) fn evaluate_segmentation_for_feature(
     config: &ConfigBody,
     feature: &ConfigFeature,
     mut user: PopulatedUser,
     client_custom_data: HashMap<String, serde_json::Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+    mut trace: Option<&mut Vec<TargetTrace>>,
 ) -> Result<(Target, bool), DevCycleError> {
     let merged_custom_data = user.combined_custom_data();
+    let opt_in = OptInContext {
+        store: opt_in_store,
+        feature_id: &feature._id,
+        enabled: config.project.settings.optin.enabled,
+    };
     let mut ret: Result<(Target, bool), DevCycleError> =
-        Err(errors::FAILED_USER_DOES_NOT_QUALIFY_FOR_TARGETS);
+        Err(errors::failed_user_does_not_qualify_for_targets());
     for target in feature.configuration.targets.clone() {
         let passthrough_enabled = !config.project.settings.disable_passthrough_rollouts;
         let mut rollout_criteria_met = true;
@@ -348,14 +357,32 @@ pub(crate) fn evaluate_segmentation_for_feature(
             );
             let bounded_hash =
                 murmurhash::generate_bounded_hashes(bucketing_value, target._id.clone());
-            rollout_criteria_met =
-                is_user_in_rollout(target.rollout.clone().unwrap(), bounded_hash.rollout_hash);
+            rollout_criteria_met = is_user_in_rollout(&target, bounded_hash.rollout_hash, now);
             is_rollout = rollout_criteria_met;
         }
-        let operator = &target.audience.filters;
-        if rollout_criteria_met
-            && operator.evaluate(&config.audiences, &mut user, &client_custom_data.clone())
-        {
+        let audience_matches = rollout_criteria_met
+            && does_user_match_target_audience(
+                config,
+                &target,
+                &mut user,
+                &client_custom_data,
+                &opt_in,
+            );
+        if let Some(traces) = trace.as_deref_mut() {
+            traces.push(TargetTrace {
+                matched: audience_matches,
+                ..explain::trace_target(
+                    config,
+                    &target,
+                    &user,
+                    &client_custom_data,
+                    &opt_in,
+                    rollout_criteria_met,
+                    now,
+                )
+            });
+        }
+        if audience_matches {
             ret = Ok((target.clone(), is_rollout.clone()));
             return ret;
         }
@@ -363,21 +390,53 @@ pub(crate) fn evaluate_segmentation_for_feature(
     return ret;
 }
 
-pub(crate) fn is_user_in_rollout(rollout: Rollout, bounded_hash: f64) -> bool {
-    let rollout_percentage = get_current_rollout_percentage(rollout, chrono::Utc::now());
-    return rollout_percentage != 0.0 && (bounded_hash <= rollout_percentage);
+pub(crate) fn does_user_match_target_audience(
+    config: &ConfigBody,
+    target: &Target,
+    user: &mut PopulatedUser,
+    client_custom_data: &HashMap<String, serde_json::Value>,
+    opt_in: &OptInContext,
+) -> bool {
+    // Configs built through ConfigBody::compile carry a precompiled plan for every target
+    match &target.compiled_audience {
+        Some(plan) => plan.evaluate(user, client_custom_data, Some(opt_in)),
+        None => target.audience.filters.evaluate_with_opt_in(
+            &config.audiences,
+            user,
+            client_custom_data,
+            Some(opt_in),
+        ),
+    }
+}
+
+// Targets without a rollout let everyone in
+pub(crate) fn is_user_in_rollout(target: &Target, bounded_hash: f64, now: DateTime<Utc>) -> bool {
+    match target.rollout_percentage(now) {
+        Some(rollout_percentage) => rollout_percentage != 0.0 && bounded_hash <= rollout_percentage,
+        None => true,
+    }
 }
 
 pub(crate) fn does_user_qualify_for_feature(
-    config: &Arc<ConfigBody>,
+    config: &ConfigBody,
     feature: &ConfigFeature,
     user: PopulatedUser,
     client_custom_data: HashMap<String, serde_json::Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+    trace: Option<&mut Vec<TargetTrace>>,
 ) -> Result<TargetAndHashes, DevCycleError> {
-    let target_pair =
-        evaluate_segmentation_for_feature(config, feature, user.clone(), client_custom_data);
+    let target_pair = evaluate_segmentation_for_feature(
+        config,
+        feature,
+        user.clone(),
+        client_custom_data,
+        opt_in_store,
+        now,
+        trace,
+    );
     if !target_pair.is_ok() {
-        return Err(errors::FAILED_USER_DOES_NOT_QUALIFY_FOR_TARGETS);
+        return Err(errors::failed_user_does_not_qualify_for_targets());
     }
     let (target, is_rollout) = target_pair.ok().unwrap();
     let merged_custom_data = user.combined_custom_data();
@@ -391,8 +450,8 @@ pub(crate) fn does_user_qualify_for_feature(
     let rollout_hash = bounded_hashes.rollout_hash;
     let passthrough_enabled = !config.project.settings.disable_passthrough_rollouts;
 
-    if !passthrough_enabled && !does_user_pass_rollout(target.rollout.clone(), rollout_hash) {
-        return Err(errors::FAILED_USER_DOES_NOT_QUALIFY_FOR_ROLLOUTS);
+    if !passthrough_enabled && !is_user_in_rollout(&target, rollout_hash, now) {
+        return Err(errors::failed_user_does_not_qualify_for_rollouts());
     }
     Ok(TargetAndHashes {
         target,
@@ -400,6 +459,130 @@ pub(crate) fn does_user_qualify_for_feature(
         is_rollout,
     })
 }
+
+// Forced users are pinned to their mapped variation ahead of any targeting or rollout logic.
+// Mappings that point at a variation missing from the feature are ignored.
+pub(crate) fn get_forced_variation<'a>(
+    feature: &'a ConfigFeature,
+    user_id: &str,
+) -> Option<&'a Variation> {
+    let variation_id = feature.configuration.forced_users.get(user_id)?;
+    feature
+        .variations
+        .iter()
+        .find(|variation| &variation._id == variation_id)
+}
+
+// Prerequisites compare whether the user is bucketed into another feature: "=" requires the
+// user to be bucketed into it and "!=" requires that they are not. Results are memoized in
+// `bucketed_features` by feature id; `ConfigBody::compile` rejects prerequisite cycles.
+pub(crate) fn does_user_pass_prerequisites(
+    config: &ConfigBody,
+    feature: &ConfigFeature,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, serde_json::Value>,
+    opt_in_store: &dyn OptInStore,
+    bucketed_features: &mut HashMap<String, bool>,
+    now: DateTime<Utc>,
+) -> bool {
+    for prerequisite in &feature.configuration.prerequisites {
+        let is_bucketed = is_user_bucketed_into_feature(
+            config,
+            &prerequisite._feature,
+            user,
+            client_custom_data,
+            opt_in_store,
+            bucketed_features,
+            now,
+        );
+        if !prerequisite_passes(&prerequisite.comparator, is_bucketed) {
+            return false;
+        }
+    }
+    true
+}
+
+pub(crate) fn prerequisite_passes(comparator: &str, is_bucketed: bool) -> bool {
+    match comparator {
+        constants::COMPARATOR_EQUAL => is_bucketed,
+        constants::COMPARATOR_NOT_EQUAL => !is_bucketed,
+        _ => false,
+    }
+}
+
+fn is_user_bucketed_into_feature(
+    config: &ConfigBody,
+    feature_id: &str,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, serde_json::Value>,
+    opt_in_store: &dyn OptInStore,
+    bucketed_features: &mut HashMap<String, bool>,
+    now: DateTime<Utc>,
+) -> bool {
+    if let Some(is_bucketed) = bucketed_features.get(feature_id) {
+        return *is_bucketed;
+    }
+    let is_bucketed = match config.get_feature_for_id(feature_id) {
+        Some(feature) => {
+            get_forced_variation(feature, &user.user_id).is_some()
+                || (does_user_pass_prerequisites(
+                    config,
+                    feature,
+                    user,
+                    client_custom_data,
+                    opt_in_store,
+                    bucketed_features,
+                    now,
+                ) && does_user_qualify_for_feature(
+                    config,
+                    feature,
+                    user.clone(),
+                    client_custom_data.clone(),
+                    opt_in_store,
+                    now,
+                    None,
+                )
+                .and_then(|target_and_hashes| {
+                    decide_variation_for_target(feature, target_and_hashes)
+                })
+                .is_ok())
+        }
+        None => false,
+    };
+    bucketed_features.insert(feature_id.to_string(), is_bucketed);
+    is_bucketed
+}
+
+// Concluded experiments carry a winning variation that replaces the target distribution.
+// A winning variation missing from the feature is ignored.
+pub(crate) fn get_winning_variation(feature: &ConfigFeature) -> Option<&Variation> {
+    let winning_variation = feature.configuration.winning_variation.as_ref()?;
+    feature
+        .variations
+        .iter()
+        .find(|variation| variation._id == winning_variation._variation)
+}
+
+// Picks the variation for a user who qualified for one of the feature's targets, serving the
+// winning variation of a concluded experiment to everyone instead of splitting traffic.
+pub(crate) fn decide_variation_for_target(
+    feature: &ConfigFeature,
+    target_and_hashes: TargetAndHashes,
+) -> Result<(Variation, EvaluationReason), DevCycleError> {
+    if let Some(variation) = get_winning_variation(feature) {
+        return Ok((variation.clone(), EvaluationReason::TargetingMatch));
+    }
+
+    let is_rollout = target_and_hashes.is_rollout;
+    let (variation, is_random_distrib) = bucket_user_for_variation(feature, target_and_hashes)?;
+    let eval_reason = if is_rollout || is_random_distrib {
+        EvaluationReason::Split
+    } else {
+        EvaluationReason::TargetingMatch
+    };
+    Ok((variation, eval_reason))
+}
+
 pub(crate) fn bucket_user_for_variation(
     feature: &ConfigFeature,
     hashes: TargetAndHashes,
@@ -418,48 +601,80 @@ pub(crate) fn bucket_user_for_variation(
     }
     Err(missing_variation())
 }
-pub async fn generate_bucketed_config(
+pub fn generate_bucketed_config(
     sdk_key: String,
     user: PopulatedUser,
     client_custom_data: HashMap<String, serde_json::Value>,
 ) -> Result<BucketedUserConfig, DevCycleError> {
-    let config_result = configmanager::CONFIGS
-        .read()
-        .unwrap()
-        .get(&sdk_key)
-        .cloned()
-        .ok_or(missing_config())?;
+    let config = configmanager::get_config(&sdk_key).ok_or(missing_config())?;
+    let client = client_manager::get_client(&sdk_key).ok_or(missing_config())?;
+    generate_bucketed_config_from_config(
+        &config,
+        user,
+        client_custom_data,
+        client.state.get_opt_in_store().as_ref(),
+        client.state.now(),
+    )
+}
 
+pub(crate) fn generate_bucketed_config_from_config(
+    config_result: &ConfigBody,
+    user: PopulatedUser,
+    client_custom_data: HashMap<String, serde_json::Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> Result<BucketedUserConfig, DevCycleError> {
     let project = config_result.project.clone();
     let environment = config_result.environment.clone();
     let mut variables: HashMap<String, ReadOnlyVariable> = HashMap::new();
     let mut features: HashMap<String, Feature> = HashMap::new();
     let mut feature_variation_map: HashMap<String, String> = HashMap::new();
     let mut variable_variation_map: HashMap<String, FeatureVariation> = HashMap::new();
+    let mut bucketed_features: HashMap<String, bool> = HashMap::new();
 
+    // Features are ordered by `ConfigBody::compile` so prerequisites are evaluated first
     for feature in &config_result.features {
-        let target_hash = does_user_qualify_for_feature(
-            &config_result,
-            feature,
-            user.clone(),
-            client_custom_data.clone(),
-        );
-        if !target_hash.is_ok() {
-            continue;
-        }
-
-        let target_and_hashes = target_hash?;
-        let variation = bucket_user_for_variation(feature, target_and_hashes.clone());
-        if !variation.is_ok() {
-            return Err(variation.err().unwrap());
-        }
-
-        let (variation_instance, is_random_distrib) = variation.ok().unwrap();
-        let eval_reason = if target_and_hashes.is_rollout || is_random_distrib {
-            EvaluationReason::Split
-        } else {
-            EvaluationReason::TargetingMatch
-        };
+        let (variation_instance, eval_reason, target_id, details) =
+            match get_forced_variation(feature, &user.user_id) {
+                Some(variation) => (variation.clone(), EvaluationReason::Override, None, None),
+                None => {
+                    if !does_user_pass_prerequisites(
+                        config_result,
+                        feature,
+                        &user,
+                        &client_custom_data,
+                        opt_in_store,
+                        &mut bucketed_features,
+                        now,
+                    ) {
+                        bucketed_features.insert(feature._id.clone(), false);
+                        continue;
+                    }
+
+                    let target_hash = does_user_qualify_for_feature(
+                        config_result,
+                        feature,
+                        user.clone(),
+                        client_custom_data.clone(),
+                        opt_in_store,
+                        now,
+                        None,
+                    );
+                    if !target_hash.is_ok() {
+                        bucketed_features.insert(feature._id.clone(), false);
+                        continue;
+                    }
+
+                    let target_and_hashes = target_hash?;
+                    let target_id = target_and_hashes.target._id.clone();
+                    let (variation_instance, eval_reason) =
+                        decide_variation_for_target(feature, target_and_hashes)?;
+                    let details = get_winning_variation(feature)
+                        .map(|_| constants::WINNING_VARIATION_DETAILS.to_string());
+                    (variation_instance, eval_reason, Some(target_id), details)
+                }
+            };
+        bucketed_features.insert(feature._id.clone(), true);
 
         features.insert(
             feature.key.clone(),
@@ -490,8 +705,8 @@ pub async fn generate_bucketed_config(
                     value: var.value.clone(),
                     eval: EvalDetails {
                         reason: eval_reason.clone(),
-                        details: None,
-                        target_id: Some(target_and_hashes.target._id.clone()),
+                        details: details.clone(),
+                        target_id: target_id.clone(),
                     },
                 },
             );
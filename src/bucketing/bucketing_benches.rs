@@ -1,18 +1,23 @@
 use chrono::Utc;
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
-use devcycle_bucketing_rs::EventQueueOptions;
-use devcycle_bucketing_rs::init_event_queue;
+use devcycle_bucketing_rs::bench_support::TargetAudiences;
+use devcycle_bucketing_rs::ffi::devcycle_set_config;
 use devcycle_bucketing_rs::user::PopulatedUser;
 use devcycle_bucketing_rs::variable_for_user;
+use devcycle_bucketing_rs::{EventQueueOptions, InMemoryEventSink, init_event_queue};
 use devcycle_bucketing_rs::{PlatformData, get_platform_data, set_platform_data};
 use devcycle_bucketing_rs::{generate_bucketed_config, set_client_custom_data};
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::Arc;
 
 const BENCH_SDK_KEY: &str = "benchmark-sdk-key";
+const FILTERS_SDK_KEY: &str = "benchmark-filters-sdk-key";
+const TEST_CONFIG: &str = include_str!("../../tests/resources/test_config.json");
 
 // Setup functions to initialize test data
-async fn setup_platform_data() {
+fn setup_platform_data() {
     let platform_data = PlatformData {
         sdk_type: "server".to_string(),
         sdk_version: "1.0.0".to_string(),
@@ -21,29 +26,54 @@ async fn setup_platform_data() {
         platform: "benchmark".to_string(),
         hostname: "localhost".to_string(),
     };
-    set_platform_data(BENCH_SDK_KEY, platform_data).await;
+    set_platform_data(BENCH_SDK_KEY, platform_data);
 }
 
-async fn create_test_user(user_id: &str) -> PopulatedUser {
-    setup_platform_data().await;
+fn set_config(sdk_key: &str, config_json: &str) {
+    let sdk_key = CString::new(sdk_key).unwrap();
+    let config_json = CString::new(config_json).unwrap();
+    let rc = unsafe { devcycle_set_config(sdk_key.as_ptr(), config_json.as_ptr()) };
+    assert_eq!(rc, 0, "failed to set benchmark config");
+}
+
+fn setup_bench_sdk_key() {
+    setup_platform_data();
+    set_config(BENCH_SDK_KEY, TEST_CONFIG);
+    set_client_custom_data(BENCH_SDK_KEY, HashMap::new()).unwrap();
+}
+
+fn create_test_user(user_id: &str) -> PopulatedUser {
+    create_user_for_sdk_key(BENCH_SDK_KEY, user_id)
+}
+
+// A user carrying the SDK key's platform data
+fn create_user_for_sdk_key(sdk_key: &str, user_id: &str) -> PopulatedUser {
     PopulatedUser {
         user_id: user_id.to_string(),
         email: format!("{}@benchmark.com", user_id),
         name: format!("Benchmark User {}", user_id),
         language: "en".to_string(),
         country: "US".to_string(),
+        ip: String::new(),
         app_version: "1.0.0".to_string(),
         app_build: "100".to_string(),
         custom_data: HashMap::new(),
         private_custom_data: HashMap::new(),
         device_model: "benchmark-device".to_string(),
         last_seen_date: Utc::now(),
-        platform_data: get_platform_data(BENCH_SDK_KEY).unwrap(),
+        platform_data: get_platform_data(sdk_key).unwrap(),
         created_date: Utc::now(),
     }
 }
 
 fn create_test_user_with_custom_data(user_id: &str) -> PopulatedUser {
+    PopulatedUser {
+        custom_data: bench_custom_data(),
+        ..create_test_user(user_id)
+    }
+}
+
+fn bench_custom_data() -> HashMap<String, Value> {
     let mut custom_data: HashMap<String, Value> = HashMap::new();
     custom_data.insert(
         "age".to_string(),
@@ -51,56 +81,32 @@ fn create_test_user_with_custom_data(user_id: &str) -> PopulatedUser {
     );
     custom_data.insert("tier".to_string(), Value::String("premium".to_string()));
     custom_data.insert("beta_user".to_string(), Value::Bool(true));
-
-    PopulatedUser {
-        user_id: user_id.to_string(),
-        email: format!("{}@benchmark.com", user_id),
-        name: format!("Benchmark User {}", user_id),
-        language: "en".to_string(),
-        country: "US".to_string(),
-        app_version: "1.0.0".to_string(),
-        app_build: "100".to_string(),
-        custom_data,
-        private_custom_data: HashMap::new(),
-        device_model: "benchmark-device".to_string(),
-        last_seen_date: Utc::now(),
-        platform_data: get_platform_data(BENCH_SDK_KEY).unwrap(),
-        created_date: Utc::now(),
-    }
+    custom_data
 }
 
 // Benchmark for generate_bucketed_config with PopulatedUser
 fn bench_generate_bucketed_config(c: &mut Criterion) {
-    setup_platform_data();
-    let runtime = tokio::runtime::Runtime::new().unwrap();
+    setup_bench_sdk_key();
 
     let mut group = c.benchmark_group("generate_bucketed_config");
 
     // Benchmark with basic user (no custom data)
     group.bench_function("basic_user", |b| {
         b.iter(|| {
-            runtime.block_on(async {
-                let user = create_test_user("bench_user").await;
-                let client_custom_data = HashMap::new();
-
-                generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                    .await
-                    .ok();
-            })
+            let user = create_test_user("bench_user");
+            let client_custom_data = HashMap::new();
+
+            generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
         });
     });
 
     // Benchmark with user that has custom data
     group.bench_function("user_with_custom_data", |b| {
         b.iter(|| {
-            runtime.block_on(async {
-                let user = create_test_user_with_custom_data("bench_user_custom");
-                let client_custom_data = HashMap::new();
-
-                generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                    .await
-                    .ok();
-            })
+            let user = create_test_user_with_custom_data("bench_user_custom");
+            let client_custom_data = HashMap::new();
+
+            generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
         });
     });
 
@@ -111,16 +117,12 @@ fn bench_generate_bucketed_config(c: &mut Criterion) {
             user_count,
             |b, &count| {
                 b.iter(|| {
-                    runtime.block_on(async move {
-                        for i in 0..count {
-                            let user = create_test_user(&format!("bench_user_{}", i)).await;
-                            let client_custom_data = HashMap::new();
-
-                            generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                                .await
-                                .ok();
-                        }
-                    })
+                    for i in 0..count {
+                        let user = create_test_user(&format!("bench_user_{}", i));
+                        let client_custom_data = HashMap::new();
+
+                        generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
+                    }
                 });
             },
         );
@@ -131,8 +133,7 @@ fn bench_generate_bucketed_config(c: &mut Criterion) {
 
 // Benchmark comparing different user scenarios
 fn bench_user_scenarios(c: &mut Criterion) {
-    setup_platform_data();
-    let runtime = tokio::runtime::Runtime::new().unwrap();
+    setup_bench_sdk_key();
 
     let mut group = c.benchmark_group("user_scenarios");
 
@@ -143,17 +144,11 @@ fn bench_user_scenarios(c: &mut Criterion) {
             country,
             |b, &country_code| {
                 b.iter(|| {
-                    runtime.block_on(async move {
-                        setup_platform_data().await;
-
-                        let mut user = create_test_user("country_bench").await;
-                        user.country = country_code.to_string();
-                        let client_custom_data = HashMap::new();
+                    let mut user = create_test_user("country_bench");
+                    user.country = country_code.to_string();
+                    let client_custom_data = HashMap::new();
 
-                        generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                            .await
-                            .ok();
-                    })
+                    generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
                 });
             },
         );
@@ -166,22 +161,16 @@ fn bench_user_scenarios(c: &mut Criterion) {
             data_count,
             |b, &count| {
                 b.iter(|| {
-                    runtime.block_on(async move {
-                        setup_platform_data().await;
-
-                        let mut user = create_test_user("custom_data_bench").await;
-                        for i in 0..count {
-                            user.custom_data.insert(
-                                format!("field_{}", i),
-                                Value::String(format!("value_{}", i)),
-                            );
-                        }
-                        let client_custom_data = HashMap::new();
+                    let mut user = create_test_user("custom_data_bench");
+                    for i in 0..count {
+                        user.custom_data.insert(
+                            format!("field_{}", i),
+                            Value::String(format!("value_{}", i)),
+                        );
+                    }
+                    let client_custom_data = HashMap::new();
 
-                        generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                            .await
-                            .ok();
-                    })
+                    generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
                 });
             },
         );
@@ -192,94 +181,57 @@ fn bench_user_scenarios(c: &mut Criterion) {
 
 // Throughput benchmark - how many requests per second
 fn bench_throughput(c: &mut Criterion) {
-    let runtime = tokio::runtime::Runtime::new().unwrap();
+    setup_bench_sdk_key();
 
     let mut group = c.benchmark_group("throughput");
     group.sample_size(50); // Reduce sample size for throughput tests
 
-    group.bench_function("sequential_100_requests", |b| {
-        b.iter(|| {
-            runtime.block_on(async {
-                setup_platform_data().await;
-                for i in 0..100 {
-                    let user = create_test_user(&format!("throughput_user_{}", i)).await;
-                    let client_custom_data = HashMap::new();
-
-                    generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                        .await
-                        .ok();
-                }
-            })
-        });
-    });
-
-    group.bench_function("sequential_1000_requests", |b| {
-        b.iter(|| {
-            runtime.block_on(async {
-                set_platform_data(
-                    BENCH_SDK_KEY,
-                    PlatformData {
-                        sdk_type: "server".to_string(),
-                        sdk_version: "1.0.0".to_string(),
-                        platform_version: "1.0.0".to_string(),
-                        device_model: "benchmark-device".to_string(),
-                        platform: "benchmark".to_string(),
-                        hostname: "localhost".to_string(),
-                    },
-                )
-                .await;
-                for i in 0..1000 {
-                    let user = create_test_user(&format!("throughput_user_{}", i)).await;
+    for request_count in [100, 1000] {
+        group.bench_function(format!("sequential_{}_requests", request_count), |b| {
+            b.iter(|| {
+                for i in 0..request_count {
+                    let user = create_test_user(&format!("throughput_user_{}", i));
                     let client_custom_data = HashMap::new();
 
-                    generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data)
-                        .await
-                        .ok();
+                    generate_bucketed_config(BENCH_SDK_KEY, user, client_custom_data).unwrap();
                 }
-            })
+            });
         });
-    });
+    }
 
     group.finish();
 }
 
 // Benchmark for variable_for_user
 fn bench_variable_for_user(c: &mut Criterion) {
-    setup_platform_data();
+    setup_bench_sdk_key();
+    // Keeps the event queue's background task running while the group is measured
     let runtime = tokio::runtime::Runtime::new().unwrap();
+    let event_queue = runtime
+        .block_on(init_event_queue(
+            BENCH_SDK_KEY,
+            EventQueueOptions {
+                event_sink: Some(Arc::new(InMemoryEventSink::new())),
+                ..Default::default()
+            },
+        ))
+        .unwrap();
 
     let mut group = c.benchmark_group("variable_for_user");
 
     // Benchmark with basic user
     group.bench_function("basic_user", |b| {
         b.iter(|| {
-            runtime.block_on(async {
-                setup_platform_data().await;
-                let user = create_test_user("bench_var_user").await;
-                let client_custom_data = HashMap::new();
-                let _ = set_client_custom_data(BENCH_SDK_KEY, client_custom_data.clone()).await;
-                let _ = init_event_queue(BENCH_SDK_KEY, EventQueueOptions::default()).await;
-
-                variable_for_user(BENCH_SDK_KEY, user, "test_variable", "String")
-                    .await
-                    .ok();
-            })
+            let user = create_test_user("bench_var_user");
+            variable_for_user(BENCH_SDK_KEY, user, "test-string-variable", "String").unwrap();
         });
     });
 
     // Benchmark with user that has custom data
     group.bench_function("user_with_custom_data", |b| {
         b.iter(|| {
-            runtime.block_on(async {
-                setup_platform_data().await;
-                let user = create_test_user_with_custom_data("bench_var_user_custom");
-                let client_custom_data = HashMap::new();
-                let _ = set_client_custom_data(BENCH_SDK_KEY, client_custom_data.clone()).await;
-                let _ = init_event_queue(BENCH_SDK_KEY, EventQueueOptions::default()).await;
-                variable_for_user(BENCH_SDK_KEY, user, "test_variable", "String")
-                    .await
-                    .ok();
-            })
+            let user = create_test_user_with_custom_data("bench_var_user_custom");
+            variable_for_user(BENCH_SDK_KEY, user, "test-string-variable", "String").unwrap();
         });
     });
 
@@ -290,31 +242,96 @@ fn bench_variable_for_user(c: &mut Criterion) {
             var_count,
             |b, &count| {
                 b.iter(|| {
-                    runtime.block_on(async move {
-                        setup_platform_data().await;
-
-                        // Initialize event queue via manager
-                        init_event_queue(BENCH_SDK_KEY, EventQueueOptions::default())
-                            .await
+                    for i in 0..count {
+                        let user = create_test_user(&format!("bench_var_user_{}", i));
+                        variable_for_user(BENCH_SDK_KEY, user, "test-string-variable", "String")
                             .unwrap();
-
-                        for i in 0..count {
-                            let user = create_test_user(&format!("bench_var_user_{}", i)).await;
-                            let client_custom_data = HashMap::new();
-                            let _ =
-                                set_client_custom_data(BENCH_SDK_KEY, client_custom_data.clone())
-                                    .await;
-                            variable_for_user(BENCH_SDK_KEY, user, "test_variable", "String")
-                                .await
-                                .ok();
-                        }
-                    })
+                    }
                 });
             },
         );
     }
 
     group.finish();
+    runtime.block_on(event_queue.close()).unwrap();
+}
+
+// test_config.json with targets that run every filter type before the user falls through to
+// an "all" target. The user matches all but the last filter of each target.
+fn filter_heavy_config() -> String {
+    let mut config: Value = serde_json::from_str(TEST_CONFIG).unwrap();
+    let blocked_users: Vec<String> = (0..500).map(|i| format!("blocked_user_{}", i)).collect();
+    let countries: Vec<String> = (0..50).map(|i| format!("C{}", i)).collect();
+    config["audiences"] = json!({
+        "premium_users": {"filters": {"operator": "or", "filters": [
+            {"type": "user", "subType": "country", "comparator": "=", "values": countries},
+            {"type": "user", "subType": "customData", "comparator": "=", "values": ["tier", "premium"]}
+        ]}}
+    });
+
+    let mut targets = Vec::new();
+    for i in 0..5 {
+        targets.push(json!({
+            "_id": format!("filter_target_{}", i),
+            "_audience": {"filters": {"operator": "and", "filters": [
+                {"type": "user", "subType": "user_id", "comparator": "!=", "values": blocked_users},
+                {"type": "user", "subType": "appVersion", "comparator": ">=", "values": ["1.0.0"]},
+                {"type": "user", "subType": "platformVersion", "comparator": "<", "values": ["2.0"]},
+                {"type": "user", "subType": "email", "comparator": "endWith", "values": ["@benchmark.com"]},
+                {"type": "user", "subType": "customData", "comparator": ">", "values": ["age", 18]},
+                {"type": "audienceMatch", "comparator": "=", "_audiences": ["premium_users"]},
+                {"type": "user", "subType": "country", "comparator": "=", "values": [format!("X{}", i)]}
+            ]}},
+            "distribution": [{"_variation": "6216422850294da359385e8f", "percentage": 1.0}]
+        }));
+    }
+    targets.push(json!({
+        "_id": "filter_target_all",
+        "_audience": {"filters": {"operator": "and", "filters": [{"type": "all"}]}},
+        "distribution": [{"_variation": "6216422850294da359385e90", "percentage": 1.0}]
+    }));
+    config["features"][0]["configuration"]["targets"] = Value::Array(targets);
+    config.to_string()
+}
+
+// Benchmark for segmentation against a config whose targets use many filters. Evaluating the
+// audiences through the recompiling slow path next to their compiled plans shows what
+// ConfigBody::compile saves on every evaluation.
+fn bench_audience_filters(c: &mut Criterion) {
+    let platform_data = PlatformData {
+        sdk_type: "server".to_string(),
+        sdk_version: "1.0.0".to_string(),
+        platform_version: "1.0.0".to_string(),
+        device_model: "benchmark-device".to_string(),
+        platform: "benchmark".to_string(),
+        hostname: "localhost".to_string(),
+    };
+    set_platform_data(FILTERS_SDK_KEY, platform_data);
+    set_config(FILTERS_SDK_KEY, &filter_heavy_config());
+
+    let mut user = PopulatedUser {
+        custom_data: bench_custom_data(),
+        ..create_user_for_sdk_key(FILTERS_SDK_KEY, "filter_bench_user")
+    };
+    let audiences = TargetAudiences::for_sdk_key(FILTERS_SDK_KEY).unwrap();
+    assert_eq!(
+        audiences.evaluate_uncompiled(&mut user),
+        audiences.evaluate_compiled(&user)
+    );
+
+    let mut group = c.benchmark_group("audience_filters");
+    group.bench_function("filter_heavy_targets", |b| {
+        b.iter(|| {
+            generate_bucketed_config(FILTERS_SDK_KEY, user.clone(), HashMap::new()).unwrap();
+        });
+    });
+    group.bench_function("uncompiled_audiences", |b| {
+        b.iter(|| audiences.evaluate_uncompiled(&mut user));
+    });
+    group.bench_function("compiled_audiences", |b| {
+        b.iter(|| audiences.evaluate_compiled(&user));
+    });
+    group.finish();
 }
 
 criterion_group!(
@@ -322,6 +339,7 @@ criterion_group!(
     bench_generate_bucketed_config,
     bench_user_scenarios,
     bench_throughput,
-    bench_variable_for_user
+    bench_variable_for_user,
+    bench_audience_filters
 );
 criterion_main!(benches);
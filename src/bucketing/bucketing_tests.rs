@@ -1,15 +1,18 @@
 #[cfg(test)]
 mod tests {
+    use crate::bucketing::rollout_timeline::RolloutTimeline;
     use crate::config::platform_data::{self, PlatformData};
     use crate::config::*;
     use crate::configmanager;
+    use crate::segmentation::optin::{InMemoryOptInStore, OptInStore};
     use crate::user::*;
+    use crate::util::test_fixtures::{config_body, test_config_json};
     use crate::{EvaluationReason, bucketing};
     use chrono::Utc;
     use serde_json;
     use serde_json::Value;
     use std::collections::HashMap;
-    use std::sync::Once;
+    use std::sync::{Arc, Once};
 
     const TEST_SDK_KEY: &str = "test-sdk-key";
 
@@ -87,7 +90,7 @@ mod tests {
             ray_id: "test-ray-id".to_string(),
             last_modified: Utc::now(),
         };
-        config.compile();
+        config.compile().expect("Failed to compile config");
         return config;
     }
 
@@ -100,6 +103,7 @@ mod tests {
             name: format!("Test User {}", user_id),
             language: "en".to_string(),
             country: "US".to_string(),
+            ip: String::new(),
             app_version: "1.0.0".to_string(),
             app_build: "100".to_string(),
             custom_data: HashMap::new(),
@@ -123,6 +127,7 @@ mod tests {
             name: format!("Test User {}", user_id),
             language: "en".to_string(),
             country: "US".to_string(),
+            ip: String::new(),
             app_version: "1.0.0".to_string(),
             app_build: "100".to_string(),
             custom_data,
@@ -138,18 +143,14 @@ mod tests {
         let full_config = load_test_config();
         let config_body = create_config_body_from_full_config(full_config);
 
-        // Store the config in the global CONFIGS map
-        let mut configs = configmanager::CONFIGS.write().unwrap();
-        configs.insert(sdk_key, config_body.into());
+        configmanager::set_config(&sdk_key, config_body);
     }
 
     fn setup_test_config_v2(sdk_key: &str) {
         let full_config = load_test_config_v2();
         let config_body = create_config_body_from_full_config(full_config);
 
-        // Store the config in the global CONFIGS map
-        let mut configs = configmanager::CONFIGS.write().unwrap();
-        configs.insert(sdk_key.to_string(), config_body.into());
+        configmanager::set_config(sdk_key, config_body);
     }
 
     #[tokio::test]
@@ -166,7 +167,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(
@@ -208,7 +208,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(
@@ -247,7 +246,6 @@ mod tests {
                     user.clone(),
                     client_custom_data,
                 )
-                .await
             };
 
             assert!(
@@ -287,7 +285,6 @@ mod tests {
                     user.clone(),
                     client_custom_data,
                 )
-                .await
             };
 
             assert!(
@@ -313,7 +310,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(result.is_err(), "Expected error for missing SDK key");
@@ -342,7 +338,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(
@@ -438,9 +433,7 @@ mod tests {
         let full_config = load_production_config();
         let config_body = create_config_body_from_full_config(full_config);
 
-        // Store the config in the global CONFIGS map
-        let mut configs = configmanager::CONFIGS.write().unwrap();
-        configs.insert(sdk_key, config_body.into());
+        configmanager::set_config(&sdk_key, config_body);
     }
 
     #[tokio::test]
@@ -452,7 +445,7 @@ mod tests {
         let client_custom_data = HashMap::new();
 
         let result = unsafe {
-            bucketing::generate_bucketed_config(sdk_key, user.clone(), client_custom_data).await
+            bucketing::generate_bucketed_config(sdk_key, user.clone(), client_custom_data)
         };
 
         assert!(
@@ -489,7 +482,7 @@ mod tests {
         );
 
         let result = unsafe {
-            bucketing::generate_bucketed_config(sdk_key, user.clone(), client_custom_data).await
+            bucketing::generate_bucketed_config(sdk_key, user.clone(), client_custom_data)
         };
 
         assert!(
@@ -541,7 +534,6 @@ mod tests {
                     user.clone(),
                     client_custom_data,
                 )
-                .await
             };
 
             assert!(
@@ -577,7 +569,6 @@ mod tests {
                     user.clone(),
                     client_custom_data,
                 )
-                .await
             };
 
             if result.is_ok() {
@@ -654,7 +645,6 @@ mod tests {
                 user.clone(),
                 client_custom_data.clone(),
             )
-            .await
         };
 
         let prod_result = unsafe {
@@ -663,7 +653,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(test_result.is_ok(), "Test config bucketing failed");
@@ -689,7 +678,6 @@ mod tests {
                 user.clone(),
                 HashMap::new(),
             )
-            .await
         };
         assert!(
             bucketing_result.is_ok(),
@@ -739,7 +727,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(result.is_ok(), "Failed to generate bucketed config");
@@ -814,7 +801,6 @@ mod tests {
                 user.clone(),
                 client_custom_data,
             )
-            .await
         };
 
         assert!(result.is_ok());
@@ -902,6 +888,7 @@ mod tests {
             name: "Test User".to_string(),
             language: "en".to_string(),
             country: "US".to_string(),
+            ip: String::new(),
             app_version: "1.0.0".to_string(),
             app_build: "100".to_string(),
             custom_data,
@@ -932,7 +919,6 @@ mod tests {
                 constants::VARIABLE_TYPES_STRING,
                 client_custom_data,
             )
-            .await
         };
 
         assert!(
@@ -981,4 +967,612 @@ mod tests {
             "eval_details should be empty on success"
         );
     }
+
+    fn setup_forced_user_config(sdk_key: &str, user_id: &str, variation_id: &str) {
+        let mut full_config = load_test_config();
+        full_config.features[0]
+            .configuration
+            .forced_users
+            .insert(user_id.to_string(), variation_id.to_string());
+        let config_body = create_config_body_from_full_config(full_config);
+
+        configmanager::set_config(sdk_key, config_body);
+    }
+
+    #[test]
+    fn test_forced_users_deserialization() {
+        let full_config = load_test_config();
+        assert!(
+            full_config.features[0]
+                .configuration
+                .forced_users
+                .is_empty()
+        );
+
+        let configuration: crate::feature::FeatureConfiguration = serde_json::from_str(
+            r#"{"_id": "config", "targets": [], "forcedUsers": {"qa-user": "variation-id"}}"#,
+        )
+        .expect("Failed to parse feature configuration");
+        assert_eq!(
+            configuration
+                .forced_users
+                .get("qa-user")
+                .map(String::as_str),
+            Some("variation-id")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_forced_user() {
+        let sdk_key = "test-sdk-key-forced-user";
+        let off_variation = "6216422850294da359385e90";
+        setup_forced_user_config(sdk_key, "qa-user", off_variation);
+
+        let user = create_test_user("qa-user");
+        let bucketed_config =
+            bucketing::generate_bucketed_config(sdk_key.to_string(), user, HashMap::new())
+                .expect("Failed to generate bucketed config");
+
+        let feature = bucketed_config.features.get("test").unwrap();
+        assert_eq!(feature.variation, off_variation);
+        assert_eq!(feature.evalreason, Some(EvaluationReason::Override));
+
+        let variable = bucketed_config.variables.get("test").unwrap();
+        assert_eq!(variable.value, Value::Bool(false));
+        assert_eq!(variable.eval.reason, EvaluationReason::Override);
+        assert!(variable.eval.target_id.is_none());
+
+        // Users without a forced mapping still go through targeting
+        let other_user = create_test_user("not-forced");
+        let bucketed_config =
+            bucketing::generate_bucketed_config(sdk_key.to_string(), other_user, HashMap::new())
+                .expect("Failed to generate bucketed config");
+        let feature = bucketed_config.features.get("test").unwrap();
+        assert_eq!(feature.evalreason, Some(EvaluationReason::Split));
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_forced_user_unknown_variation() {
+        let sdk_key = "test-sdk-key-forced-user-unknown";
+        setup_forced_user_config(sdk_key, "qa-user", "missing-variation");
+
+        let user = create_test_user("qa-user");
+        let bucketed_config =
+            bucketing::generate_bucketed_config(sdk_key.to_string(), user, HashMap::new())
+                .expect("Failed to generate bucketed config");
+
+        let feature = bucketed_config.features.get("test").unwrap();
+        assert_eq!(feature.evalreason, Some(EvaluationReason::Split));
+    }
+
+    #[tokio::test]
+    async fn test_variable_for_user_forced_user() {
+        use crate::constants;
+        use crate::events::event_queue::EventQueueOptions;
+        let sdk_key = "test-variable-for-user-forced";
+        let on_variation = "6216422850294da359385e8f";
+
+        initialize_test_platform_data();
+        platform_data::set_platform_data(
+            sdk_key.to_string(),
+            (*platform_data::get_platform_data(TEST_SDK_KEY).unwrap()).clone(),
+        );
+        setup_forced_user_config(sdk_key, "qa-user", on_variation);
+        crate::init_event_queue(sdk_key, EventQueueOptions::default())
+            .await
+            .expect("Failed to initialize event queue");
+
+        let result = bucketing::variable_for_user(
+            sdk_key,
+            create_test_user("qa-user"),
+            "test-string-variable",
+            constants::VARIABLE_TYPES_STRING,
+            HashMap::new(),
+        )
+        .expect("variable_for_user should succeed");
+
+        assert_eq!(result.variation_id, on_variation);
+        assert_eq!(result.variable_value, Value::String("on".to_string()));
+        assert_eq!(result.eval_reason.unwrap(), EvaluationReason::Override);
+    }
+
+    // Adds a copy of the "test" feature gated on it with the given comparator,
+    // owning a new "dependent-var" Boolean variable
+    fn setup_prerequisite_config(sdk_key: &str, comparator: &str) {
+        let mut full_config = load_test_config();
+        let parent_id = full_config.features[0]._id.clone();
+
+        let mut feature: serde_json::Value =
+            serde_json::to_value(&full_config.features[0]).unwrap();
+        feature["_id"] = Value::String("dependent-feature".to_string());
+        feature["key"] = Value::String("dependent".to_string());
+        feature["configuration"]["_id"] = Value::String("dependent-config".to_string());
+        feature["configuration"]["targets"][0]["_id"] =
+            Value::String("dependent-target".to_string());
+        feature["configuration"]["prerequisites"] = serde_json::json!([
+            {"_feature": parent_id, "comparator": comparator}
+        ]);
+        for variation in feature["variations"].as_array_mut().unwrap() {
+            variation["variables"] = serde_json::json!([
+                {"_var": "dependent-var-id", "value": true}
+            ]);
+        }
+        // Listed ahead of its prerequisite so compile has to reorder it
+        full_config
+            .features
+            .insert(0, serde_json::from_value(feature).unwrap());
+        full_config.variables.push(Variable {
+            _id: "dependent-var-id".to_string(),
+            _type: "Boolean".to_string(),
+            key: "dependent-var".to_string(),
+        });
+
+        let config_body = create_config_body_from_full_config(full_config);
+        configmanager::set_config(sdk_key, config_body);
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_prerequisite_met() {
+        let sdk_key = "test-sdk-key-prerequisite-met";
+        setup_prerequisite_config(sdk_key, "=");
+
+        let bucketed_config = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("prereq-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+
+        assert!(bucketed_config.features.contains_key("test"));
+        assert!(bucketed_config.features.contains_key("dependent"));
+        assert!(bucketed_config.variables.contains_key("dependent-var"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_prerequisite_not_met() {
+        let sdk_key = "test-sdk-key-prerequisite-not-met";
+        setup_prerequisite_config(sdk_key, "!=");
+
+        let bucketed_config = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("prereq-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+
+        assert!(bucketed_config.features.contains_key("test"));
+        assert!(!bucketed_config.features.contains_key("dependent"));
+        assert!(!bucketed_config.variables.contains_key("dependent-var"));
+    }
+
+    #[tokio::test]
+    async fn test_variable_for_user_prerequisite() {
+        use crate::constants;
+        use crate::events::event_queue::EventQueueOptions;
+
+        initialize_test_platform_data();
+        for (sdk_key, comparator) in [
+            ("test-variable-prerequisite-met", "="),
+            ("test-variable-prerequisite-not-met", "!="),
+        ] {
+            platform_data::set_platform_data(
+                sdk_key.to_string(),
+                (*platform_data::get_platform_data(TEST_SDK_KEY).unwrap()).clone(),
+            );
+            setup_prerequisite_config(sdk_key, comparator);
+            crate::init_event_queue(sdk_key, EventQueueOptions::default())
+                .await
+                .expect("Failed to initialize event queue");
+        }
+
+        let result = bucketing::variable_for_user(
+            "test-variable-prerequisite-met",
+            create_test_user("prereq-user"),
+            "dependent-var",
+            constants::VARIABLE_TYPES_BOOL,
+            HashMap::new(),
+        )
+        .expect("variable_for_user should succeed");
+        assert_eq!(result.variable_value, Value::Bool(true));
+        assert_eq!(result.feature_id, "dependent-feature");
+
+        let result = bucketing::variable_for_user(
+            "test-variable-prerequisite-not-met",
+            create_test_user("prereq-user"),
+            "dependent-var",
+            constants::VARIABLE_TYPES_BOOL,
+            HashMap::new(),
+        )
+        .expect("variable_for_user should succeed");
+        assert_eq!(result.variable_value, Value::Null);
+        assert_eq!(result.eval_reason.unwrap(), EvaluationReason::Default);
+        assert_eq!(result.default_reason, "Prerequisite Not Met");
+    }
+
+    #[tokio::test]
+    async fn test_variable_for_user_missing_variable_default_reason() {
+        use crate::constants;
+        use crate::events::event_queue::EventQueueOptions;
+
+        initialize_test_platform_data();
+        let sdk_key = "test-variable-missing-default-reason";
+        platform_data::set_platform_data(
+            sdk_key.to_string(),
+            (*platform_data::get_platform_data(TEST_SDK_KEY).unwrap()).clone(),
+        );
+        configmanager::set_config(
+            sdk_key,
+            create_config_body_from_full_config(load_test_config()),
+        );
+        crate::init_event_queue(sdk_key, EventQueueOptions::default())
+            .await
+            .expect("Failed to initialize event queue");
+
+        let result = bucketing::variable_for_user(
+            sdk_key,
+            create_test_user("missing-variable-user"),
+            "variable-that-does-not-exist",
+            constants::VARIABLE_TYPES_BOOL,
+            HashMap::new(),
+        )
+        .expect("variable_for_user should succeed");
+        assert_eq!(result.variable_value, Value::Null);
+        assert_eq!(result.default_reason, "Missing Variable");
+    }
+
+    fn setup_winning_variation_config(sdk_key: &str, variation_id: &str) {
+        let mut full_config = load_test_config();
+        let feature_id = full_config.features[0]._id.clone();
+        full_config.features[0].configuration.winning_variation = Some(FeatureVariation {
+            _feature: feature_id,
+            _variation: variation_id.to_string(),
+        });
+        let config_body = create_config_body_from_full_config(full_config);
+
+        configmanager::set_config(sdk_key, config_body);
+    }
+
+    #[test]
+    fn test_winning_variation_deserialization() {
+        let configuration: crate::feature::FeatureConfiguration = serde_json::from_str(
+            r#"{
+                "_id": "config",
+                "targets": [],
+                "winningVariation": {"_feature": "feature-id", "_variation": "variation-id"}
+            }"#,
+        )
+        .expect("Failed to parse feature configuration");
+        let winning_variation = configuration.winning_variation.unwrap();
+        assert_eq!(winning_variation._feature, "feature-id");
+        assert_eq!(winning_variation._variation, "variation-id");
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_winning_variation() {
+        let sdk_key = "test-sdk-key-winning-variation";
+        let on_variation = "6216422850294da359385e8f";
+        setup_winning_variation_config(sdk_key, on_variation);
+
+        // The target splits 50/50, so a spread of users would normally land in both variations
+        for i in 0..20 {
+            let user = create_test_user(&format!("winning-user-{}", i));
+            let bucketed_config =
+                bucketing::generate_bucketed_config(sdk_key.to_string(), user, HashMap::new())
+                    .expect("Failed to generate bucketed config");
+
+            let feature = bucketed_config.features.get("test").unwrap();
+            assert_eq!(feature.variation, on_variation);
+            assert_eq!(feature.evalreason, Some(EvaluationReason::TargetingMatch));
+
+            let variable = bucketed_config.variables.get("test").unwrap();
+            assert_eq!(variable.value, Value::Bool(true));
+            assert_eq!(variable.eval.reason, EvaluationReason::TargetingMatch);
+            assert_eq!(variable.eval.details.as_deref(), Some("Winning Variation"));
+            assert_eq!(
+                variable.eval.target_id.as_deref(),
+                Some("621642332ea68943c8833c4d")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_forced_user_beats_winning_variation() {
+        let sdk_key = "test-sdk-key-winning-variation-forced";
+        let off_variation = "6216422850294da359385e90";
+        let mut full_config = load_test_config();
+        full_config.features[0].configuration.winning_variation = Some(FeatureVariation {
+            _feature: full_config.features[0]._id.clone(),
+            _variation: "6216422850294da359385e8f".to_string(),
+        });
+        full_config.features[0]
+            .configuration
+            .forced_users
+            .insert("qa-user".to_string(), off_variation.to_string());
+        let config_body = create_config_body_from_full_config(full_config);
+        configmanager::set_config(sdk_key, config_body);
+
+        let bucketed_config = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("qa-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+
+        let feature = bucketed_config.features.get("test").unwrap();
+        assert_eq!(feature.variation, off_variation);
+        assert_eq!(feature.evalreason, Some(EvaluationReason::Override));
+    }
+
+    #[tokio::test]
+    async fn test_variable_for_user_winning_variation() {
+        use crate::constants;
+        use crate::events::event_queue::EventQueueOptions;
+        let sdk_key = "test-variable-for-user-winning-variation";
+        let off_variation = "6216422850294da359385e90";
+
+        initialize_test_platform_data();
+        platform_data::set_platform_data(
+            sdk_key.to_string(),
+            (*platform_data::get_platform_data(TEST_SDK_KEY).unwrap()).clone(),
+        );
+        setup_winning_variation_config(sdk_key, off_variation);
+        crate::init_event_queue(sdk_key, EventQueueOptions::default())
+            .await
+            .expect("Failed to initialize event queue");
+
+        for i in 0..20 {
+            let result = bucketing::variable_for_user(
+                sdk_key,
+                create_test_user(&format!("winning-user-{}", i)),
+                "test-number-variable",
+                constants::VARIABLE_TYPES_NUMBER,
+                HashMap::new(),
+            )
+            .expect("variable_for_user should succeed");
+
+            assert_eq!(result.variation_id, off_variation);
+            assert_eq!(result.variable_value, serde_json::json!(0));
+            assert_eq!(
+                result.eval_reason.unwrap(),
+                EvaluationReason::TargetingMatch
+            );
+            assert_eq!(
+                result.eval_details.as_deref(),
+                Some(constants::WINNING_VARIATION_DETAILS)
+            );
+            assert_eq!(
+                result.target_id.as_deref(),
+                Some("621642332ea68943c8833c4d")
+            );
+        }
+    }
+
+    fn setup_opt_in_config(sdk_key: &str, opt_in_enabled: bool) -> String {
+        let mut full_config = load_test_config();
+        full_config.project.settings.optin.enabled = opt_in_enabled;
+
+        let mut feature: serde_json::Value =
+            serde_json::to_value(&full_config.features[0]).unwrap();
+        feature["configuration"]["targets"][0]["_audience"]["filters"]["filters"] =
+            serde_json::json!([{"type": "optIn", "comparator": "=", "values": []}]);
+        full_config.features[0] = serde_json::from_value(feature).unwrap();
+        let feature_id = full_config.features[0]._id.clone();
+
+        let config_body = create_config_body_from_full_config(full_config);
+        configmanager::set_config(sdk_key, config_body);
+        feature_id
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_opt_in_filter() {
+        let sdk_key = "test-sdk-key-opt-in";
+        let feature_id = setup_opt_in_config(sdk_key, true);
+        let opt_in_store = Arc::new(InMemoryOptInStore::new());
+        opt_in_store.opt_in("opted-in-user", &feature_id);
+        crate::set_opt_in_store(sdk_key, opt_in_store);
+
+        let opted_in = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("opted-in-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+        assert!(opted_in.features.contains_key("test"));
+
+        let not_opted_in = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("other-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+        assert!(!not_opted_in.features.contains_key("test"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_bucketed_config_opt_in_disabled_for_project() {
+        let sdk_key = "test-sdk-key-opt-in-disabled";
+        let feature_id = setup_opt_in_config(sdk_key, false);
+        crate::get_opt_in_store(sdk_key)
+            .unwrap()
+            .opt_in("opted-in-user", &feature_id);
+
+        let bucketed_config = bucketing::generate_bucketed_config(
+            sdk_key.to_string(),
+            create_test_user("opted-in-user"),
+            HashMap::new(),
+        )
+        .expect("Failed to generate bucketed config");
+        assert!(!bucketed_config.features.contains_key("test"));
+    }
+
+    fn rollout_percentage(rollout: Value, at: &str) -> f64 {
+        let rollout: crate::target::Rollout = serde_json::from_value(rollout).unwrap();
+        RolloutTimeline::from_rollout(&rollout)
+            .unwrap()
+            .percentage_at(at.parse().unwrap())
+    }
+
+    fn assert_percentage(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_rollout_percentage_schedule() {
+        let rollout = serde_json::json!({"type": "schedule", "startDate": "2030-01-01T00:00:00Z"});
+        assert_percentage(
+            rollout_percentage(rollout.clone(), "2029-12-31T23:59:59Z"),
+            0.0,
+        );
+        assert_percentage(
+            rollout_percentage(rollout.clone(), "2030-01-01T00:00:00Z"),
+            0.0,
+        );
+        assert_percentage(rollout_percentage(rollout, "2030-01-01T00:00:01Z"), 1.0);
+    }
+
+    #[test]
+    fn test_rollout_percentage_discrete_and_linear_stages() {
+        let rollout = serde_json::json!({
+            "type": "gradual",
+            "startPercentage": 0.2,
+            "startDate": "2030-01-01T00:00:00Z",
+            "stages": [
+                {"type": "discrete", "date": "2030-01-05T00:00:00Z", "percentage": 0.4},
+                {"type": "linear", "date": "2030-01-09T00:00:00Z", "percentage": 0.8},
+                {"type": "linear", "date": "2030-01-10T00:00:00Z", "percentage": 0.6},
+                {"type": "discrete", "date": "2030-01-20T00:00:00Z", "percentage": 1.0}
+            ]
+        });
+        let at = |date: &str| rollout_percentage(rollout.clone(), date);
+
+        assert_percentage(at("2029-12-31T00:00:00Z"), 0.0);
+        // Holds the start percentage until the discrete stage
+        assert_percentage(at("2030-01-03T00:00:00Z"), 0.2);
+        assert_percentage(at("2030-01-05T00:00:00Z"), 0.2);
+        // Ramps from 0.4 to 0.8 over four days
+        assert_percentage(at("2030-01-06T00:00:00Z"), 0.5);
+        assert_percentage(at("2030-01-07T12:00:00Z"), 0.65);
+        // Linear stages can ramp down
+        assert_percentage(at("2030-01-09T12:00:00Z"), 0.7);
+        assert_percentage(at("2030-01-15T00:00:00Z"), 0.6);
+        assert_percentage(at("2030-01-21T00:00:00Z"), 1.0);
+    }
+
+    #[test]
+    fn test_rollout_percentage_linear_from_start() {
+        let rollout = serde_json::json!({
+            "type": "gradual",
+            "startPercentage": 0.0,
+            "startDate": "2030-01-01T00:00:00Z",
+            "stages": [{"type": "linear", "date": "2030-01-11T00:00:00Z", "percentage": 1.0}]
+        });
+        assert_percentage(
+            rollout_percentage(rollout.clone(), "2030-01-01T00:00:00Z"),
+            0.0,
+        );
+        assert_percentage(
+            rollout_percentage(rollout.clone(), "2030-01-01T06:00:00Z"),
+            0.025,
+        );
+        assert_percentage(
+            rollout_percentage(rollout.clone(), "2030-01-04T00:00:00Z"),
+            0.3,
+        );
+        assert_percentage(rollout_percentage(rollout, "2030-01-12T00:00:00Z"), 1.0);
+    }
+
+    #[test]
+    fn test_malformed_rollouts_load_and_let_nobody_in() {
+        let malformed = [
+            // Stages out of order
+            serde_json::json!({
+                "type": "gradual",
+                "startPercentage": 0.5,
+                "startDate": "2030-01-01T00:00:00Z",
+                "stages": [
+                    {"type": "discrete", "date": "2030-01-10T00:00:00Z", "percentage": 0.8},
+                    {"type": "discrete", "date": "2030-01-05T00:00:00Z", "percentage": 1.0}
+                ]
+            }),
+            // Two stages on the same date
+            serde_json::json!({
+                "type": "gradual",
+                "startPercentage": 0.5,
+                "startDate": "2030-01-01T00:00:00Z",
+                "stages": [
+                    {"type": "discrete", "date": "2030-01-05T00:00:00Z", "percentage": 0.8},
+                    {"type": "linear", "date": "2030-01-05T00:00:00Z", "percentage": 1.0}
+                ]
+            }),
+            serde_json::json!({
+                "type": "gradual",
+                "startPercentage": 0.5,
+                "startDate": "2030-01-01T00:00:00Z",
+                "stages": [{"type": "linear", "date": "2030-01-10T00:00:00Z", "percentage": 1.5}]
+            }),
+            serde_json::json!({
+                "type": "gradual",
+                "startPercentage": 0.5,
+                "startDate": "2030-01-10T00:00:00Z",
+                "stages": [{"type": "discrete", "date": "2030-01-05T00:00:00Z", "percentage": 1.0}]
+            }),
+            serde_json::json!({
+                "type": "sometimes",
+                "startPercentage": 0.5,
+                "startDate": "2030-01-01T00:00:00Z",
+                "stages": []
+            }),
+        ];
+        for rollout in malformed {
+            let parsed: crate::target::Rollout = serde_json::from_value(rollout.clone()).unwrap();
+            assert!(
+                RolloutTimeline::from_rollout(&parsed).is_err(),
+                "{}",
+                rollout
+            );
+
+            // The config still loads, the target's rollout lets nobody in and the lint reports it
+            let mut json = test_config_json();
+            json["features"][0]["configuration"]["targets"][0]["rollout"] = rollout.clone();
+            let config = config_body(json);
+            let target = &config.features[0].configuration.targets[0];
+            assert!(target.compiled_rollout.is_none());
+            assert_eq!(
+                target.rollout_percentage("2030-02-01T00:00:00Z".parse().unwrap()),
+                Some(0.0)
+            );
+            assert!(
+                crate::lint_config(&config)
+                    .iter()
+                    .any(|finding| finding.rule == crate::ConfigLintRule::MalformedRollout)
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_load_compiles_rollouts() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["rollout"] = serde_json::json!({
+            "type": "gradual",
+            "startPercentage": 0.25,
+            "startDate": "2030-01-01T00:00:00Z",
+            "stages": []
+        });
+        let config = config_body(json);
+        let target = &config.features[0].configuration.targets[0];
+        let timeline = target.compiled_rollout.as_ref().unwrap();
+        assert_percentage(
+            timeline.percentage_at("2030-02-01T00:00:00Z".parse().unwrap()),
+            0.25,
+        );
+        assert_eq!(
+            target.rollout_percentage("2030-02-01T00:00:00Z".parse().unwrap()),
+            Some(0.25)
+        );
+    }
 }
@@ -0,0 +1,342 @@
+use crate::bucketing::{
+    determine_user_bucketing_value_for_target, generate_bucketed_config_from_config,
+};
+use crate::config::platform_data::PlatformData;
+use crate::config::{ConfigBody, FullConfig};
+use crate::errors::DevCycleError;
+use crate::events::event::EvaluationReason;
+use crate::murmurhash::murmurhash;
+use crate::segmentation::optin::InMemoryOptInStore;
+use crate::user::{BucketedUserConfig, PopulatedUser, User};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// The vector format version `run_conformance_vectors` reads.
+pub const CONFORMANCE_VECTOR_VERSION: u32 = 1;
+
+// Hashes from SDKs that print fewer digits still compare equal
+const HASH_TOLERANCE: f64 = 1e-9;
+
+/// A file of conformance vectors, shared with the other DevCycle SDKs so they can check
+/// they bucket users exactly like this crate.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceVectorFile {
+    pub version: u32,
+    #[serde(default)]
+    pub description: String,
+    // Full config JSON, as served by the config CDN
+    pub config: Value,
+    // Platform data of every user, defaults to `conformance_platform_data`
+    #[serde(default)]
+    pub platform_data: Option<PlatformData>,
+    #[serde(default)]
+    pub hashes: Vec<HashVector>,
+    #[serde(default)]
+    pub cases: Vec<ConformanceCase>,
+}
+
+/// The bucketing value a target's bucketing key picks for a user, and the hashes it gives.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashVector {
+    #[serde(default)]
+    pub bucketing_key: String,
+    pub user: ConformanceUser,
+    pub target_id: String,
+    pub bucketing_value: String,
+    pub rollout_hash: f64,
+    pub bucketing_hash: f64,
+}
+
+/// A user's bucketed config at a point in time. Features and variables missing from the
+/// expected maps must not be served.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceCase {
+    pub name: String,
+    pub user: ConformanceUser,
+    #[serde(default)]
+    pub client_custom_data: HashMap<String, Value>,
+    pub time: DateTime<Utc>,
+    pub expected: ExpectedBucketing,
+}
+
+/// User fields of a vector, all optional but the user ID.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConformanceUser {
+    pub user_id: String,
+    pub email: String,
+    pub name: String,
+    pub language: String,
+    pub country: String,
+    pub ip: String,
+    pub app_version: String,
+    pub app_build: String,
+    pub device_model: String,
+    pub custom_data: HashMap<String, Value>,
+    pub private_custom_data: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExpectedBucketing {
+    pub features: BTreeMap<String, ExpectedFeature>,
+    pub variables: BTreeMap<String, ExpectedVariable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedFeature {
+    pub variation_id: String,
+    pub variation_key: String,
+    pub eval_reason: EvaluationReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedVariable {
+    #[serde(rename = "type")]
+    pub variable_type: String,
+    pub value: Value,
+    pub eval_reason: EvaluationReason,
+}
+
+/// The outcome of a vector file, passing when there are no failures.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceReport {
+    pub description: String,
+    pub hashes: usize,
+    pub cases: usize,
+    pub failures: Vec<ConformanceFailure>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A value that differs from the vector, `null` on either side when it is missing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceFailure {
+    // The hash vector index or case name
+    pub vector: String,
+    pub path: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+/// Platform data given to vector users when the file sets none.
+pub fn conformance_platform_data() -> PlatformData {
+    PlatformData {
+        sdk_type: "server".to_string(),
+        sdk_version: "1.0.0".to_string(),
+        platform_version: "1.0.0".to_string(),
+        device_model: "conformance".to_string(),
+        platform: "conformance".to_string(),
+        hostname: "localhost".to_string(),
+    }
+}
+
+// Runs a vector file against this crate's bucketing, with no users opted in. Errors are
+// for files that can't be run at all; mismatches are reported as failures.
+pub fn run_conformance_vectors(vector_json: &str) -> Result<ConformanceReport, DevCycleError> {
+    let file: ConformanceVectorFile = serde_json::from_str(vector_json)?;
+    if file.version != CONFORMANCE_VECTOR_VERSION {
+        return Err(DevCycleError::Parse {
+            message: format!(
+                "Unsupported conformance vector version {}, expected {}",
+                file.version, CONFORMANCE_VECTOR_VERSION
+            ),
+            source: None,
+        });
+    }
+    let full_config: FullConfig = serde_json::from_value(file.config)?;
+    let config = ConfigBody::from_full_config(full_config).map_err(|e| DevCycleError::Parse {
+        message: format!("Invalid vector config: {}", e),
+        source: None,
+    })?;
+    let platform_data = Arc::new(file.platform_data.unwrap_or_else(conformance_platform_data));
+
+    let mut failures = Vec::new();
+    for (index, vector) in file.hashes.iter().enumerate() {
+        check_hash_vector(&format!("hashes[{}]", index), vector, &mut failures);
+    }
+    let opt_in_store = InMemoryOptInStore::new();
+    for case in &file.cases {
+        let user = populate_user(
+            &case.user,
+            &platform_data,
+            &case.client_custom_data,
+            case.time,
+        );
+        match generate_bucketed_config_from_config(
+            &config,
+            user,
+            case.client_custom_data.clone(),
+            &opt_in_store,
+            case.time,
+        ) {
+            Ok(bucketed_config) => check_case(case, &bucketed_config, &mut failures),
+            Err(e) => failures.push(ConformanceFailure {
+                vector: case.name.clone(),
+                path: "bucketedConfig".to_string(),
+                expected: Value::Null,
+                actual: Value::String(e.to_string()),
+            }),
+        }
+    }
+
+    Ok(ConformanceReport {
+        description: file.description,
+        hashes: file.hashes.len(),
+        cases: file.cases.len(),
+        failures,
+    })
+}
+
+fn populate_user(
+    user: &ConformanceUser,
+    platform_data: &Arc<PlatformData>,
+    client_custom_data: &HashMap<String, Value>,
+    time: DateTime<Utc>,
+) -> PopulatedUser {
+    let user = User {
+        user_id: user.user_id.clone(),
+        email: user.email.clone(),
+        name: user.name.clone(),
+        language: user.language.clone(),
+        country: user.country.clone(),
+        ip: user.ip.clone(),
+        app_version: user.app_version.clone(),
+        app_build: user.app_build.clone(),
+        custom_data: user.custom_data.clone(),
+        private_custom_data: user.private_custom_data.clone(),
+        device_model: user.device_model.clone(),
+        last_seen_date: time,
+    };
+    PopulatedUser {
+        created_date: time,
+        ..PopulatedUser::new(user, platform_data.clone(), client_custom_data.clone())
+    }
+}
+
+fn check_hash_vector(name: &str, vector: &HashVector, failures: &mut Vec<ConformanceFailure>) {
+    let mut merged_custom_data = vector.user.custom_data.clone();
+    merged_custom_data.extend(vector.user.private_custom_data.clone());
+    let bucketing_value = determine_user_bucketing_value_for_target(
+        vector.bucketing_key.clone(),
+        vector.user.user_id.clone(),
+        merged_custom_data,
+    );
+    if bucketing_value != vector.bucketing_value {
+        failures.push(ConformanceFailure {
+            vector: name.to_string(),
+            path: "bucketingValue".to_string(),
+            expected: Value::from(vector.bucketing_value.clone()),
+            actual: Value::from(bucketing_value),
+        });
+    }
+    // Hashes are checked for the expected value, so they don't fail twice on one mismatch
+    let hashes = murmurhash::generate_bounded_hashes(
+        vector.bucketing_value.clone(),
+        vector.target_id.clone(),
+    );
+    for (path, expected, actual) in [
+        ("rolloutHash", vector.rollout_hash, hashes.rollout_hash),
+        (
+            "bucketingHash",
+            vector.bucketing_hash,
+            hashes.bucketing_hash,
+        ),
+    ] {
+        if (expected - actual).abs() > HASH_TOLERANCE {
+            failures.push(ConformanceFailure {
+                vector: name.to_string(),
+                path: path.to_string(),
+                expected: Value::from(expected),
+                actual: Value::from(actual),
+            });
+        }
+    }
+}
+
+fn check_case(
+    case: &ConformanceCase,
+    bucketed_config: &BucketedUserConfig,
+    failures: &mut Vec<ConformanceFailure>,
+) {
+    let features: BTreeMap<String, ExpectedFeature> = bucketed_config
+        .features
+        .iter()
+        .map(|(key, feature)| {
+            (
+                key.clone(),
+                ExpectedFeature {
+                    variation_id: feature.variation.clone(),
+                    variation_key: feature.variationkey.clone(),
+                    eval_reason: feature
+                        .evalreason
+                        .clone()
+                        .unwrap_or(EvaluationReason::Default),
+                },
+            )
+        })
+        .collect();
+    let variables: BTreeMap<String, ExpectedVariable> = bucketed_config
+        .variables
+        .iter()
+        .map(|(key, variable)| {
+            (
+                key.clone(),
+                ExpectedVariable {
+                    variable_type: variable._type.clone(),
+                    value: variable.value.clone(),
+                    eval_reason: variable.eval.reason.clone(),
+                },
+            )
+        })
+        .collect();
+    compare_maps(
+        &case.name,
+        "features",
+        &case.expected.features,
+        &features,
+        failures,
+    );
+    compare_maps(
+        &case.name,
+        "variables",
+        &case.expected.variables,
+        &variables,
+        failures,
+    );
+}
+
+fn compare_maps<T: PartialEq + Serialize>(
+    name: &str,
+    path: &str,
+    expected: &BTreeMap<String, T>,
+    actual: &BTreeMap<String, T>,
+    failures: &mut Vec<ConformanceFailure>,
+) {
+    let keys: BTreeSet<&String> = expected.keys().chain(actual.keys()).collect();
+    for key in keys {
+        let (expected, actual) = (expected.get(key), actual.get(key));
+        if expected != actual {
+            failures.push(ConformanceFailure {
+                vector: name.to_string(),
+                path: format!("{}.{}", path, key),
+                expected: serde_json::to_value(expected).unwrap_or_default(),
+                actual: serde_json::to_value(actual).unwrap_or_default(),
+            });
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::bucketing::conformance::run_conformance_vectors;
+    use serde_json::{Value, json};
+
+    fn vectors() -> Value {
+        serde_json::from_str(include_str!("../../tests/conformance/split.json")).unwrap()
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let mut vectors = vectors();
+        vectors["hashes"][0]["bucketingHash"] = json!(0.5);
+        vectors["cases"][0]["expected"]["features"]["test"]["variationKey"] = json!("other");
+        vectors["cases"][0]["expected"]["variables"]
+            .as_object_mut()
+            .unwrap()
+            .remove("test");
+
+        let report = run_conformance_vectors(&vectors.to_string()).unwrap();
+        assert!(!report.passed());
+        let failures: Vec<(&str, &str)> = report
+            .failures
+            .iter()
+            .map(|failure| (failure.vector.as_str(), failure.path.as_str()))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                ("hashes[0]", "bucketingHash"),
+                ("user-1", "features.test"),
+                ("user-1", "variables.test"),
+            ]
+        );
+        // Variables served but not expected have no expected value
+        assert_eq!(report.failures[2].expected, Value::Null);
+        assert_eq!(report.failures[2].actual["value"], json!(false));
+    }
+
+    #[test]
+    fn test_unsupported_version_is_an_error() {
+        let mut vectors = vectors();
+        vectors["version"] = json!(2);
+        let err = run_conformance_vectors(&vectors.to_string()).unwrap_err();
+        assert_eq!(err.code(), "PARSE_ERROR");
+        let err = run_conformance_vectors("not json").unwrap_err();
+        assert_eq!(err.code(), "PARSE_ERROR");
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
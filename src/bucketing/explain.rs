@@ -0,0 +1,410 @@
+use crate::bucketing::{
+    decide_variation_for_target, determine_user_bucketing_value_for_target,
+    does_user_pass_prerequisites, does_user_qualify_for_feature,
+    generate_bucketed_config_from_config, generate_bucketed_variable_for_user,
+    get_forced_variation, get_winning_variation, is_user_in_rollout, is_variable_type_valid,
+    prerequisite_passes,
+};
+use crate::client::ClientState;
+use crate::config::ConfigBody;
+use crate::errors::{self, DevCycleError};
+use crate::events::event::EvaluationReason;
+use crate::feature::{ConfigFeature, Variation};
+use crate::murmurhash::murmurhash;
+use crate::segmentation::compiled_filters::FilterCompiler;
+use crate::segmentation::optin::{OptInContext, OptInStore};
+use crate::target::{Target, TargetAndHashes};
+use crate::user::{BucketedUserConfig, PopulatedUser};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A bucketed config along with how each feature in the config was evaluated.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketedConfigExplanation {
+    pub config: BucketedUserConfig,
+    pub features: Vec<FeatureTrace>,
+}
+
+/// The result of a variable evaluation along with how its feature was evaluated.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableExplanation {
+    pub variable_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    pub eval_reason: EvaluationReason,
+    // Why the variable defaulted, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature: Option<FeatureTrace>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureTrace {
+    pub feature_id: String,
+    pub feature_key: String,
+    // Set when the user is a forced user of the feature, targeting is skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forced_variation: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prerequisites: Vec<PrerequisiteTrace>,
+    // Targets in evaluation order, up to the one the user matched
+    pub targets: Vec<TargetTrace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    // Set when a concluded experiment serves its winning variation instead of the distribution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winning_variation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<DistributionBucket>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variation_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eval_reason: Option<EvaluationReason>,
+    // Why the user wasn't bucketed into the feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrerequisiteTrace {
+    pub feature_id: String,
+    pub comparator: String,
+    pub bucketed: bool,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetTrace {
+    pub target_id: String,
+    pub bucketing_key: String,
+    pub bucketing_value: String,
+    pub rollout_hash: f64,
+    pub bucketing_hash: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<RolloutTrace>,
+    // None when the rollout excluded the user before the audience was evaluated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<OperatorTrace>,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutTrace {
+    #[serde(rename = "type")]
+    pub rollout_type: String,
+    // Rollout percentage at evaluation time
+    pub percentage: f64,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorTrace {
+    pub operator: String,
+    pub passed: bool,
+    pub filters: Vec<FilterTrace>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterTrace {
+    #[serde(rename = "type")]
+    pub filter_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparator: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<Value>,
+    // The user value the filter values were compared with, None when the user has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_value: Option<Value>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub audiences: Vec<AudienceTrace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<OperatorTrace>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceTrace {
+    pub audience_id: String,
+    pub passed: bool,
+    // None when the audience is missing from the config or references itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<OperatorTrace>,
+}
+
+/// The distribution entry the bucketing hash fell into, covering hashes in [rangeStart, rangeEnd).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionBucket {
+    pub variation_id: String,
+    pub bucketing_hash: f64,
+    pub range_start: f64,
+    pub range_end: f64,
+}
+
+impl FeatureTrace {
+    fn new(feature: &ConfigFeature) -> Self {
+        FeatureTrace {
+            feature_id: feature._id.clone(),
+            feature_key: feature.key.clone(),
+            forced_variation: None,
+            prerequisites: Vec::new(),
+            targets: Vec::new(),
+            target_id: None,
+            winning_variation: None,
+            bucket: None,
+            variation_id: None,
+            variation_key: None,
+            eval_reason: None,
+            error: None,
+        }
+    }
+
+    fn set_variation(&mut self, variation: &Variation, eval_reason: EvaluationReason) {
+        self.variation_id = Some(variation._id.clone());
+        self.variation_key = Some(variation.key.clone());
+        self.eval_reason = Some(eval_reason);
+    }
+}
+
+// Evaluates a feature the way generate_bucketed_config does, recording every step
+pub(crate) fn explain_feature(
+    config: &ConfigBody,
+    feature: &ConfigFeature,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    bucketed_features: &mut HashMap<String, bool>,
+    now: DateTime<Utc>,
+) -> FeatureTrace {
+    let mut trace = FeatureTrace::new(feature);
+    if let Some(variation) = get_forced_variation(feature, &user.user_id) {
+        trace.forced_variation = Some(variation._id.clone());
+        trace.set_variation(variation, EvaluationReason::Override);
+        return trace;
+    }
+
+    let passes_prerequisites = does_user_pass_prerequisites(
+        config,
+        feature,
+        user,
+        client_custom_data,
+        opt_in_store,
+        bucketed_features,
+        now,
+    );
+    // Prerequisites are checked in order up to the first failure, each result memoized
+    for prerequisite in &feature.configuration.prerequisites {
+        let bucketed = bucketed_features
+            .get(&prerequisite._feature)
+            .copied()
+            .unwrap_or(false);
+        let passed = prerequisite_passes(&prerequisite.comparator, bucketed);
+        trace.prerequisites.push(PrerequisiteTrace {
+            feature_id: prerequisite._feature.clone(),
+            comparator: prerequisite.comparator.clone(),
+            bucketed,
+            passed,
+        });
+        if !passed {
+            break;
+        }
+    }
+    if !passes_prerequisites {
+        trace.error = Some(errors::failed_user_does_not_meet_prerequisites().to_string());
+        return trace;
+    }
+
+    let target_and_hashes = match does_user_qualify_for_feature(
+        config,
+        feature,
+        user.clone(),
+        client_custom_data.clone(),
+        opt_in_store,
+        now,
+        Some(&mut trace.targets),
+    ) {
+        Ok(target_and_hashes) => target_and_hashes,
+        Err(err) => {
+            trace.error = Some(err.to_string());
+            return trace;
+        }
+    };
+    trace.target_id = Some(target_and_hashes.target._id.clone());
+    match get_winning_variation(feature) {
+        Some(variation) => trace.winning_variation = Some(variation._id.clone()),
+        None => trace.bucket = distribution_bucket(&target_and_hashes),
+    }
+
+    match decide_variation_for_target(feature, target_and_hashes) {
+        Ok((variation, eval_reason)) => trace.set_variation(&variation, eval_reason),
+        Err(err) => trace.error = Some(err.to_string()),
+    }
+    trace
+}
+
+// Records how a target was evaluated, leaving `matched` for the caller to set;
+// `rollout_passed` is false when a passthrough rollout excluded the user before the audience
+// was checked
+pub(crate) fn trace_target(
+    config: &ConfigBody,
+    target: &Target,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in: &OptInContext,
+    rollout_passed: bool,
+    now: DateTime<Utc>,
+) -> TargetTrace {
+    let bucketing_value = determine_user_bucketing_value_for_target(
+        target.bucketingkey.clone(),
+        user.user_id.clone(),
+        user.combined_custom_data(),
+    );
+    let bounded_hash =
+        murmurhash::generate_bounded_hashes(bucketing_value.clone(), target._id.clone());
+    let rollout = target
+        .rollout
+        .as_ref()
+        .zip(target.rollout_percentage(now))
+        .map(|(rollout, percentage)| RolloutTrace {
+            rollout_type: rollout._type.clone(),
+            percentage,
+            passed: is_user_in_rollout(target, bounded_hash.rollout_hash, now),
+        });
+    let audience = rollout_passed.then(|| {
+        FilterCompiler::new(&config.audiences).explain_operator(
+            &target.audience.filters,
+            user,
+            client_custom_data,
+            Some(opt_in),
+        )
+    });
+    TargetTrace {
+        target_id: target._id.clone(),
+        bucketing_key: target.bucketingkey.clone(),
+        bucketing_value,
+        rollout_hash: bounded_hash.rollout_hash,
+        bucketing_hash: bounded_hash.bucketing_hash,
+        rollout,
+        audience,
+        matched: false,
+    }
+}
+
+fn distribution_bucket(target_and_hashes: &TargetAndHashes) -> Option<DistributionBucket> {
+    let bucketing_hash = target_and_hashes.bounded_hash.bucketing_hash;
+    let (distribution, range_start) = target_and_hashes.target.find_distribution(bucketing_hash)?;
+    Some(DistributionBucket {
+        variation_id: distribution.variation.clone(),
+        bucketing_hash,
+        range_start,
+        range_end: range_start + distribution.percentage,
+    })
+}
+
+pub(crate) fn explain_bucketed_config(
+    config: &ConfigBody,
+    user: PopulatedUser,
+    client_custom_data: HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> Result<BucketedConfigExplanation, DevCycleError> {
+    let mut bucketed_features = HashMap::new();
+    let features = config
+        .features
+        .iter()
+        .map(|feature| {
+            let trace = explain_feature(
+                config,
+                feature,
+                &user,
+                &client_custom_data,
+                opt_in_store,
+                &mut bucketed_features,
+                now,
+            );
+            bucketed_features.insert(feature._id.clone(), trace.variation_id.is_some());
+            trace
+        })
+        .collect();
+    let bucketed_config =
+        generate_bucketed_config_from_config(config, user, client_custom_data, opt_in_store, now)?;
+    Ok(BucketedConfigExplanation {
+        config: bucketed_config,
+        features,
+    })
+}
+
+// Unlike variable_for_user, explaining a variable queues no evaluation events
+pub(crate) fn explain_variable_for_user(
+    client: &ClientState,
+    user: PopulatedUser,
+    variable_key: &str,
+    expected_variable_type: &str,
+    client_custom_data: HashMap<String, Value>,
+) -> Result<VariableExplanation, DevCycleError> {
+    let config = client.get_config().ok_or_else(errors::missing_config)?;
+    let now = client.now();
+    let feature = config
+        .get_variable_for_key(variable_key)
+        .and_then(|variable| config.get_feature_for_variable_id(&variable._id))
+        .map(|feature| {
+            explain_feature(
+                &config,
+                feature,
+                &user,
+                &client_custom_data,
+                client.get_opt_in_store().as_ref(),
+                &mut HashMap::new(),
+                now,
+            )
+        });
+
+    let mut explanation = VariableExplanation {
+        variable_key: variable_key.to_string(),
+        variable_id: None,
+        variable_type: None,
+        value: None,
+        eval_reason: EvaluationReason::Default,
+        error: None,
+        feature,
+    };
+    match generate_bucketed_variable_for_user(client, user, variable_key, client_custom_data, now) {
+        Ok(result) => {
+            if !expected_variable_type.is_empty()
+                && !is_variable_type_valid(&result.variable_type, expected_variable_type)
+            {
+                explanation.error = Some(errors::invalid_variable_type().to_string());
+            } else {
+                explanation.value = Some(result.value);
+                explanation.eval_reason = result.eval.reason;
+            }
+            explanation.variable_id = Some(result.variable_id);
+            explanation.variable_type = Some(result.variable_type);
+        }
+        Err((err, eval_reason)) => {
+            explanation.eval_reason = eval_reason;
+            explanation.error = Some(err.to_string());
+        }
+    }
+    Ok(explanation)
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::user::PopulatedUser;
+    use crate::util::test_fixtures::{self, TEST_TARGET_ID, client_with_config, test_config_json};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    const FEATURE_ID: &str = "6216422850294da359385e8b";
+
+    fn create_user(user_id: &str, country: &str) -> PopulatedUser {
+        PopulatedUser {
+            country: country.to_string(),
+            ..test_fixtures::create_user(user_id)
+        }
+    }
+
+    // The test config with a US-only target, gated by `rollout`, ahead of its catch-all target
+    fn create_client(sdk_key: &str, rollout: Value) -> DevCycleLocalBucketing {
+        let mut json = test_config_json();
+        json["audiences"] = json!({
+            "us-audience": {"filters": {"operator": "and", "filters": [
+                {"type": "user", "subType": "country", "comparator": "=", "values": ["US"]}
+            ]}}
+        });
+        let targets = json["features"][0]["configuration"]["targets"]
+            .as_array_mut()
+            .unwrap();
+        let mut us_target = targets[0].clone();
+        us_target["_id"] = json!("us-target");
+        us_target["rollout"] = rollout;
+        us_target["_audience"]["filters"] = json!({"operator": "and", "filters": [
+            {"type": "audienceMatch", "comparator": "=", "_audiences": ["us-audience"]}
+        ]});
+        targets.insert(0, us_target);
+
+        client_with_config(sdk_key, json)
+    }
+
+    #[test]
+    fn test_explain_bucketed_config_matches_generated_config() {
+        let client = create_client("dvc_server_explain_config", Value::Null);
+        for user_id in ["user-1", "user-2", "user-3", "user-4"] {
+            let user = create_user(user_id, "CA");
+            let explanation = client
+                .explain_bucketed_config(user.clone(), HashMap::new())
+                .unwrap();
+            let expected = client
+                .generate_bucketed_config(user, HashMap::new())
+                .unwrap();
+            assert_eq!(
+                serde_json::to_value(&explanation.config).unwrap(),
+                serde_json::to_value(&expected).unwrap()
+            );
+
+            let trace = &explanation.features[0];
+            assert_eq!(trace.feature_id, FEATURE_ID);
+            assert_eq!(
+                trace.variation_id.as_deref(),
+                Some(expected.features["test"].variation.as_str())
+            );
+            assert_eq!(trace.target_id.as_deref(), Some(TEST_TARGET_ID));
+            assert_eq!(trace.targets.len(), 2);
+
+            let us_target = &trace.targets[0];
+            assert!(!us_target.matched);
+            assert_eq!(us_target.bucketing_value, user_id);
+            let audience_filter = &us_target.audience.as_ref().unwrap().filters[0];
+            assert!(!audience_filter.passed);
+            let country_filter = &audience_filter.audiences[0]
+                .filters
+                .as_ref()
+                .unwrap()
+                .filters[0];
+            assert_eq!(country_filter.user_value, Some(json!("CA")));
+            assert_eq!(country_filter.values, vec![json!("US")]);
+            assert!(!country_filter.passed);
+
+            let catch_all = &trace.targets[1];
+            assert!(catch_all.matched);
+            let bucket = trace.bucket.as_ref().unwrap();
+            assert_eq!(bucket.bucketing_hash, catch_all.bucketing_hash);
+            assert_eq!(Some(&bucket.variation_id), trace.variation_id.as_ref());
+            assert!(bucket.range_start <= bucket.bucketing_hash);
+            assert!(bucket.bucketing_hash < bucket.range_end);
+        }
+    }
+
+    #[test]
+    fn test_explain_records_rollouts() {
+        let client = create_client(
+            "dvc_server_explain_rollout",
+            json!({"type": "schedule", "startDate": "2999-01-01T00:00:00Z"}),
+        );
+        let explanation = client
+            .explain_bucketed_config(create_user("user-1", "US"), HashMap::new())
+            .unwrap();
+        let trace = &explanation.features[0];
+        let rollout = trace.targets[0].rollout.as_ref().unwrap();
+        assert_eq!(rollout.rollout_type, "schedule");
+        assert_eq!(rollout.percentage, 0.0);
+        assert!(!rollout.passed);
+        // The rollout excluded the user before the audience was evaluated
+        assert!(trace.targets[0].audience.is_none());
+        assert_eq!(trace.target_id.as_deref(), Some(TEST_TARGET_ID));
+
+        let client = create_client(
+            "dvc_server_explain_rollout_started",
+            json!({"type": "schedule", "startDate": "2000-01-01T00:00:00Z"}),
+        );
+        let explanation = client
+            .explain_bucketed_config(create_user("user-1", "US"), HashMap::new())
+            .unwrap();
+        let trace = &explanation.features[0];
+        assert_eq!(trace.targets.len(), 1);
+        assert_eq!(trace.targets[0].rollout.as_ref().unwrap().percentage, 1.0);
+        assert_eq!(trace.target_id.as_deref(), Some("us-target"));
+        assert_eq!(
+            trace.eval_reason,
+            Some(crate::EvaluationReason::Split),
+            "users let in by a rollout are split"
+        );
+
+        let serialized = serde_json::to_value(trace).unwrap();
+        assert_eq!(serialized["targets"][0]["rollout"]["type"], "schedule");
+        assert!(serialized["targets"][0]["rolloutHash"].is_f64());
+        assert_eq!(
+            serialized["targets"][0]["audience"]["filters"][0]["audiences"][0]["filters"]["filters"]
+                [0]["userValue"],
+            "US"
+        );
+    }
+
+    #[test]
+    fn test_explain_variable_for_user() {
+        let client = create_client("dvc_server_explain_variable", Value::Null);
+        // No event queue is initialized, explaining doesn't queue evaluation events
+        let explanation = client
+            .explain_variable_for_user(
+                create_user("user-1", "CA"),
+                "test-number-variable",
+                "Number",
+            )
+            .unwrap();
+        let config = client
+            .generate_bucketed_config(create_user("user-1", "CA"), HashMap::new())
+            .unwrap();
+        let expected = &config.variables["test-number-variable"];
+        assert_eq!(explanation.value.as_ref(), Some(&expected.value));
+        assert_eq!(
+            explanation.variable_id.as_deref(),
+            Some(expected._id.as_str())
+        );
+        assert!(explanation.error.is_none());
+        let feature = explanation.feature.as_ref().unwrap();
+        assert_eq!(
+            feature.variation_id.as_deref(),
+            Some(config.features["test"].variation.as_str())
+        );
+
+        let mismatch = client
+            .explain_variable_for_user(
+                create_user("user-1", "CA"),
+                "test-number-variable",
+                "String",
+            )
+            .unwrap();
+        assert!(mismatch.value.is_none());
+        assert!(mismatch.error.is_some());
+        assert!(mismatch.feature.is_some());
+
+        let missing = client
+            .explain_variable_for_user(create_user("user-1", "CA"), "missing-variable", "")
+            .unwrap();
+        assert!(missing.feature.is_none());
+        assert_eq!(missing.eval_reason, crate::EvaluationReason::Disabled);
+    }
+}
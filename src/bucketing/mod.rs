@@ -1,7 +1,21 @@
+#[cfg(feature = "bench")]
+pub mod bench_support;
 pub(crate) mod bucketing;
+pub(crate) mod conformance;
+pub(crate) mod explain;
+pub(crate) mod rollout_timeline;
+pub(crate) mod simulation;
 
 #[cfg(test)]
 mod bucketing_tests;
+#[cfg(test)]
+mod conformance_tests;
+#[cfg(test)]
+mod explain_tests;
+#[cfg(test)]
+mod rollout_timeline_tests;
+#[cfg(test)]
+mod simulation_tests;
 
 // Re-export main function
 pub use bucketing::generate_bucketed_config;
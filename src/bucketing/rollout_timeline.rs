@@ -0,0 +1,249 @@
+use crate::bucketing::{
+    determine_user_bucketing_value_for_target, does_user_match_target_audience,
+    get_forced_variation,
+};
+use crate::config::ConfigBody;
+use crate::constants;
+use crate::feature::ConfigFeature;
+use crate::murmurhash::murmurhash;
+use crate::segmentation::optin::{OptInContext, OptInStore};
+use crate::target::Rollout;
+use crate::user::PopulatedUser;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The percentage of users a target's rollout lets in over time. The percentage is 0 until
+/// the first point, and takes a point's percentage right after its date. Between a point and
+/// a linear one after it, the percentage ramps proportionally to the time elapsed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutTimeline {
+    #[serde(rename = "type")]
+    pub rollout_type: String,
+    pub points: Vec<RolloutTimelinePoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutTimelinePoint {
+    pub date: DateTime<Utc>,
+    pub percentage: f64,
+    // Whether the percentage ramps linearly from the previous point up to this one
+    pub linear: bool,
+}
+
+impl RolloutTimeline {
+    // Fails for rollouts `rollout_problems` finds anything wrong with
+    pub(crate) fn from_rollout(rollout: &Rollout) -> Result<Self, String> {
+        let problems = rollout_problems(rollout);
+        if !problems.is_empty() {
+            return Err(problems.join("; "));
+        }
+
+        // Schedule rollouts let everyone in once they start
+        if rollout._type == constants::ROLLOUT_TYPE_SCHEDULE {
+            return Ok(RolloutTimeline {
+                rollout_type: rollout._type.clone(),
+                points: vec![RolloutTimelinePoint {
+                    date: rollout.start_date,
+                    percentage: 1.0,
+                    linear: false,
+                }],
+            });
+        }
+
+        let mut points = vec![RolloutTimelinePoint {
+            date: rollout.start_date,
+            percentage: rollout.start_percentage,
+            linear: false,
+        }];
+        points.extend(rollout.stages.iter().map(|stage| RolloutTimelinePoint {
+            date: stage.date,
+            percentage: stage.percentage,
+            linear: stage._type == constants::ROLLOUT_TYPE_LINEAR,
+        }));
+        Ok(RolloutTimeline {
+            rollout_type: rollout._type.clone(),
+            points,
+        })
+    }
+
+    pub fn percentage_at(&self, date: DateTime<Utc>) -> f64 {
+        let next_index = self.points.partition_point(|point| point.date < date);
+        let Some(current) = next_index.checked_sub(1).map(|i| &self.points[i]) else {
+            return 0.0;
+        };
+        match self.points.get(next_index) {
+            Some(next) if next.linear => interpolate(current, next, date),
+            _ => current.percentage,
+        }
+    }
+
+    // The first time after `now` at which a user with `rollout_hash` enters or leaves the
+    // rollout, None when their membership never changes again
+    pub(crate) fn next_membership_change(
+        &self,
+        rollout_hash: f64,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let is_in = |percentage: f64| percentage != 0.0 && rollout_hash <= percentage;
+        let current = is_in(self.percentage_at(now));
+        for (i, point) in self.points.iter().enumerate() {
+            if point.date > now && is_in(point.percentage) != current {
+                return Some(point.date);
+            }
+            let Some(next) = self.points.get(i + 1) else {
+                break;
+            };
+            // A ramp changes membership once, when it crosses the user's hash
+            if next.linear && next.date > now && is_in(next.percentage) != current {
+                let fraction =
+                    (rollout_hash - point.percentage) / (next.percentage - point.percentage);
+                let span = (next.date - point.date).num_milliseconds() as f64;
+                let crossing = point.date + Duration::milliseconds((fraction * span).ceil() as i64);
+                return Some(crossing.clamp(point.date.max(now), next.date));
+            }
+        }
+        None
+    }
+}
+
+// Everything that keeps a rollout from being evaluated: an unknown rollout or stage type, a
+// percentage outside 0 to 1, or a stage not dated strictly after the stage or start before it.
+// Config linting reports each problem as a finding.
+pub(crate) fn rollout_problems(rollout: &Rollout) -> Vec<String> {
+    let mut problems = Vec::new();
+    let is_percentage = |percentage: f64| (0.0..=1.0).contains(&percentage);
+
+    match rollout._type.as_str() {
+        constants::ROLLOUT_TYPE_SCHEDULE => return problems,
+        constants::ROLLOUT_TYPE_GRADUAL
+        | constants::ROLLOUT_TYPE_STEPPED
+        | constants::ROLLOUT_TYPE_PERCENTAGE => {}
+        other => {
+            problems.push(format!("unknown rollout type {}", other));
+            return problems;
+        }
+    }
+    if !is_percentage(rollout.start_percentage) {
+        problems.push(format!(
+            "start percentage {} is not between 0 and 1",
+            rollout.start_percentage
+        ));
+    }
+
+    let mut previous_date = rollout.start_date;
+    for (index, stage) in rollout.stages.iter().enumerate() {
+        if stage._type != constants::ROLLOUT_TYPE_LINEAR
+            && stage._type != constants::ROLLOUT_TYPE_DISCRETE
+        {
+            problems.push(format!("stage {} has unknown type {}", index, stage._type));
+        }
+        if !is_percentage(stage.percentage) {
+            problems.push(format!(
+                "stage {} percentage {} is not between 0 and 1",
+                index, stage.percentage
+            ));
+        }
+        if stage.date <= previous_date {
+            problems.push(format!(
+                "stage {} date {} is not after the previous stage or the start date",
+                index,
+                stage.date.to_rfc3339()
+            ));
+        }
+        previous_date = previous_date.max(stage.date);
+    }
+    problems
+}
+
+fn interpolate(from: &RolloutTimelinePoint, to: &RolloutTimelinePoint, date: DateTime<Utc>) -> f64 {
+    let span = (to.date - from.date).num_milliseconds() as f64;
+    if span <= 0.0 {
+        return to.percentage;
+    }
+    let fraction = (date - from.date).num_milliseconds() as f64 / span;
+    from.percentage + fraction * (to.percentage - from.percentage)
+}
+
+// The first time after `now` at which any rollout could move the user between the feature's
+// targets. Audiences, forced users and prerequisites are taken as they are now.
+pub(crate) fn next_feature_assignment_change(
+    config: &ConfigBody,
+    feature: &ConfigFeature,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if get_forced_variation(feature, &user.user_id).is_some() {
+        return None;
+    }
+    let opt_in = OptInContext {
+        store: opt_in_store,
+        feature_id: &feature._id,
+        enabled: config.project.settings.optin.enabled,
+    };
+    let passthrough_enabled = !config.project.settings.disable_passthrough_rollouts;
+    let mut user = user.clone();
+    let merged_custom_data = user.combined_custom_data();
+    let mut next_change: Option<DateTime<Utc>> = None;
+    for target in &feature.configuration.targets {
+        if !does_user_match_target_audience(config, target, &mut user, client_custom_data, &opt_in)
+        {
+            continue;
+        }
+        let Some(timeline) = target.rollout_timeline() else {
+            // Targets after one the user always qualifies for are never reached
+            break;
+        };
+        let bucketing_value = determine_user_bucketing_value_for_target(
+            target.bucketingkey.clone(),
+            user.user_id.clone(),
+            merged_custom_data.clone(),
+        );
+        let rollout_hash =
+            murmurhash::generate_bounded_hashes(bucketing_value, target._id.clone()).rollout_hash;
+        let target_change = timeline.next_membership_change(rollout_hash, now);
+        next_change = earliest(next_change, target_change);
+        // Without passthrough only the first matching target's rollout is checked
+        if !passthrough_enabled {
+            break;
+        }
+    }
+    next_change
+}
+
+// The first time after `now` at which the user's bucketed config could change because of a
+// rollout, usable as the config's cache expiry. The assignment changes right after it.
+pub(crate) fn next_assignment_change(
+    config: &ConfigBody,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    // Prerequisites depend on other features, which are all included
+    config.features.iter().fold(None, |next_change, feature| {
+        earliest(
+            next_change,
+            next_feature_assignment_change(
+                config,
+                feature,
+                user,
+                client_custom_data,
+                opt_in_store,
+                now,
+            ),
+        )
+    })
+}
+
+fn earliest(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
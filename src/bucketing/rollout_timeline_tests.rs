@@ -0,0 +1,280 @@
+#[cfg(test)]
+mod tests {
+    use crate::bucketing::rollout_timeline::{RolloutTimeline, RolloutTimelinePoint};
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::config_lint::ConfigLintRule;
+    use crate::errors::DevCycleError;
+    use crate::murmurhash::murmurhash;
+    use crate::target::Rollout;
+    use crate::util::clock::ManualClock;
+    use crate::util::test_fixtures::{
+        TEST_TARGET_ID, client_with_config, create_user, test_config_json,
+    };
+    use chrono::{DateTime, Duration, Utc};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn timeline(rollout: Value) -> RolloutTimeline {
+        let rollout: Rollout = serde_json::from_value(rollout).unwrap();
+        RolloutTimeline::from_rollout(&rollout).unwrap()
+    }
+
+    fn gradual_rollout() -> Value {
+        json!({
+            "type": "gradual",
+            "startPercentage": 0.1,
+            "startDate": "2030-01-01T00:00:00Z",
+            "stages": [
+                {"type": "linear", "date": "2030-01-11T00:00:00Z", "percentage": 0.6},
+                {"type": "discrete", "date": "2030-02-01T00:00:00Z", "percentage": 1.0}
+            ]
+        })
+    }
+
+    fn create_client(sdk_key: &str, rollout: Value) -> DevCycleLocalBucketing {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["rollout"] = rollout;
+        client_with_config(sdk_key, json)
+    }
+
+    #[test]
+    fn test_timeline_lists_every_stage() {
+        let timeline = timeline(gradual_rollout());
+        assert_eq!(timeline.rollout_type, "gradual");
+        assert_eq!(
+            timeline.points,
+            vec![
+                RolloutTimelinePoint {
+                    date: date("2030-01-01T00:00:00Z"),
+                    percentage: 0.1,
+                    linear: false,
+                },
+                RolloutTimelinePoint {
+                    date: date("2030-01-11T00:00:00Z"),
+                    percentage: 0.6,
+                    linear: true,
+                },
+                RolloutTimelinePoint {
+                    date: date("2030-02-01T00:00:00Z"),
+                    percentage: 1.0,
+                    linear: false,
+                },
+            ]
+        );
+
+        assert_eq!(timeline.percentage_at(date("2029-12-31T00:00:00Z")), 0.0);
+        assert_eq!(timeline.percentage_at(date("2030-01-01T00:00:00Z")), 0.0);
+        assert!((timeline.percentage_at(date("2030-01-06T00:00:00Z")) - 0.35).abs() < 1e-9);
+        assert_eq!(timeline.percentage_at(date("2030-01-20T00:00:00Z")), 0.6);
+        assert_eq!(timeline.percentage_at(date("2030-03-01T00:00:00Z")), 1.0);
+
+        let schedule =
+            self::timeline(json!({"type": "schedule", "startDate": "2030-01-01T00:00:00Z"}));
+        assert_eq!(schedule.points.len(), 1);
+        assert_eq!(schedule.percentage_at(date("2029-12-31T00:00:00Z")), 0.0);
+        assert_eq!(schedule.percentage_at(date("2030-01-02T00:00:00Z")), 1.0);
+    }
+
+    #[test]
+    fn test_next_membership_change() {
+        let timeline = timeline(gradual_rollout());
+        let before_start = date("2029-12-01T00:00:00Z");
+
+        // A user let in by the start percentage joins when the rollout starts
+        assert_eq!(
+            timeline.next_membership_change(0.05, before_start),
+            Some(date("2030-01-01T00:00:00Z"))
+        );
+        // Halfway up the ramp from 0.1 to 0.6
+        assert_eq!(
+            timeline.next_membership_change(0.35, before_start),
+            Some(date("2030-01-06T00:00:00Z"))
+        );
+        assert_eq!(
+            timeline.next_membership_change(0.35, date("2030-01-03T00:00:00Z")),
+            Some(date("2030-01-06T00:00:00Z"))
+        );
+        // Above the ramp, only the final discrete stage lets the user in
+        assert_eq!(
+            timeline.next_membership_change(0.9, date("2030-01-03T00:00:00Z")),
+            Some(date("2030-02-01T00:00:00Z"))
+        );
+        // Once in, nothing takes the user out again
+        assert_eq!(
+            timeline.next_membership_change(0.35, date("2030-01-07T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_assignment_change_follows_the_users_rollout_hash() {
+        let rollout = json!({
+            "type": "gradual",
+            "startPercentage": 0.0,
+            "startDate": "2000-01-01T00:00:00Z",
+            "stages": [
+                {"type": "discrete", "date": "2999-01-01T00:00:00Z", "percentage": 1.0}
+            ]
+        });
+        let client = create_client("dvc_server_rollout_timeline", rollout);
+        let user = create_user("user-1");
+        assert_eq!(
+            client
+                .next_assignment_change(&user, &HashMap::new())
+                .unwrap(),
+            Some(date("2999-01-01T00:00:00Z"))
+        );
+
+        let timeline = client
+            .rollout_timeline("test", TEST_TARGET_ID)
+            .unwrap()
+            .unwrap();
+        assert_eq!(timeline.points.len(), 2);
+        assert!(matches!(
+            client.rollout_timeline("missing", TEST_TARGET_ID),
+            Err(DevCycleError::MissingFeature)
+        ));
+        assert!(matches!(
+            client.rollout_timeline("test", "missing-target"),
+            Err(DevCycleError::MissingTarget)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_rollout_timeline_is_an_error() {
+        let rollout = json!({
+            "type": "gradual",
+            "startPercentage": 0.0,
+            "startDate": "2000-01-10T00:00:00Z",
+            "stages": [
+                {"type": "discrete", "date": "2000-01-05T00:00:00Z", "percentage": 1.0}
+            ]
+        });
+        let parsed: Rollout = serde_json::from_value(rollout.clone()).unwrap();
+        let error = RolloutTimeline::from_rollout(&parsed).unwrap_err();
+        assert!(error.contains("stage 0 date"), "{}", error);
+
+        // The config still loads, and the target lets nobody in, which never changes
+        let client = create_client("dvc_server_rollout_timeline_malformed", rollout);
+        match client.rollout_timeline("test", TEST_TARGET_ID) {
+            Err(DevCycleError::InvalidConfig(findings)) => {
+                assert_eq!(findings[0].rule, ConfigLintRule::MalformedRollout);
+                assert_eq!(
+                    findings[0].path,
+                    format!("features[test].targets[{}].rollout", TEST_TARGET_ID)
+                );
+            }
+            _ => panic!("expected an invalid config error"),
+        }
+        let user = create_user("user-1");
+        let bucketed = client
+            .generate_bucketed_config(user.clone(), HashMap::new())
+            .unwrap();
+        assert!(!bucketed.features.contains_key("test"));
+        assert_eq!(
+            client
+                .next_assignment_change(&user, &HashMap::new())
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_assignment_change_on_a_linear_ramp() {
+        let start = Utc::now() - Duration::days(1);
+        let end = start + Duration::days(10);
+        let client = create_client(
+            "dvc_server_rollout_timeline_linear",
+            json!({
+                "type": "gradual",
+                "startPercentage": 0.0,
+                "startDate": start,
+                "stages": [{"type": "linear", "date": end, "percentage": 1.0}]
+            }),
+        );
+        for user_id in ["user-1", "user-2", "user-3", "user-4"] {
+            let rollout_hash = murmurhash::generate_bounded_hashes(
+                user_id.to_string(),
+                TEST_TARGET_ID.to_string(),
+            )
+            .rollout_hash;
+            let next_change = client
+                .next_assignment_change(&create_user(user_id), &HashMap::new())
+                .unwrap();
+            let crossing = start + Duration::milliseconds((rollout_hash * 864e6).ceil() as i64);
+            if crossing > Utc::now() {
+                assert_eq!(next_change, Some(crossing));
+            } else {
+                assert_eq!(next_change, None);
+            }
+        }
+
+        // Targets without a rollout never change
+        let client = create_client("dvc_server_rollout_timeline_none", Value::Null);
+        assert_eq!(
+            client
+                .next_assignment_change(&create_user("user-1"), &HashMap::new())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            client.rollout_timeline("test", TEST_TARGET_ID).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rollouts_follow_the_client_clock() {
+        let start = date("2030-01-01T00:00:00Z");
+        let client = create_client(
+            "dvc_server_rollout_timeline_clock",
+            json!({"type": "schedule", "startDate": start}),
+        );
+        let clock = Arc::new(ManualClock::new(start - Duration::days(1)));
+        client.set_clock(clock.clone());
+        let user = create_user("user-1");
+
+        let before = client
+            .generate_bucketed_config(user.clone(), HashMap::new())
+            .unwrap();
+        assert!(!before.features.contains_key("test"));
+        assert_eq!(
+            client
+                .next_assignment_change(&user, &HashMap::new())
+                .unwrap(),
+            Some(start)
+        );
+
+        clock.advance(Duration::days(2));
+        let after = client
+            .generate_bucketed_config(user.clone(), HashMap::new())
+            .unwrap();
+        assert!(after.features.contains_key("test"));
+        assert_eq!(
+            client
+                .next_assignment_change(&user, &HashMap::new())
+                .unwrap(),
+            None
+        );
+
+        // "As of" evaluation ignores the clock and queues no events
+        let past = client
+            .generate_bucketed_config_at(user.clone(), HashMap::new(), start - Duration::hours(1))
+            .unwrap();
+        assert!(!past.features.contains_key("test"));
+        let variable = client
+            .variable_for_user_at(user.clone(), "test", "Boolean", start - Duration::hours(1))
+            .unwrap();
+        assert_eq!(variable.variable_value, Value::Null);
+        let variable = client
+            .variable_for_user_at(user, "test", "Boolean", start + Duration::hours(1))
+            .unwrap();
+        assert_eq!(variable.feature_id, "6216422850294da359385e8b");
+        assert_eq!(variable.variable_value, after.variables["test"].value);
+    }
+}
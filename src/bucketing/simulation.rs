@@ -0,0 +1,284 @@
+use crate::bucketing::{
+    determine_user_bucketing_value_for_target, does_user_pass_prerequisites,
+    does_user_qualify_for_feature, get_forced_variation, is_user_in_rollout,
+};
+use crate::config::ConfigBody;
+use crate::config::platform_data::PlatformData;
+use crate::errors::{self, DevCycleError};
+use crate::murmurhash::murmurhash;
+use crate::segmentation::optin::InMemoryOptInStore;
+use crate::user::PopulatedUser;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The users a traffic simulation buckets.
+pub enum SimulatedUsers {
+    /// Users with ids `simulated-user-0` to `simulated-user-{n - 1}` and no other attributes,
+    /// so only audiences that match every user let them in.
+    Synthetic(usize),
+    Users(Vec<PopulatedUser>),
+}
+
+/// How a feature's targets split a set of users.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficSimulation {
+    pub feature_id: String,
+    pub feature_key: String,
+    pub users: usize,
+    pub forced: usize,
+    pub failed_prerequisites: usize,
+    // Users no target (or, without passthrough, no rollout) let in
+    pub not_targeted: usize,
+    pub variations: Vec<VariationCount>,
+    pub targets: Vec<TargetSimulation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationCount {
+    pub variation_id: String,
+    pub variation_key: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSimulation {
+    pub target_id: String,
+    pub users: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<RolloutSimulation>,
+    pub distribution: Vec<DistributionCount>,
+    // Pearson's chi-squared statistic of the counts against the configured percentages, None
+    // when no user landed in the target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chi_squared: Option<f64>,
+    pub degrees_of_freedom: usize,
+}
+
+/// Of all simulated users, how many the target's rollout lets in.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutSimulation {
+    pub percentage: f64,
+    pub passed: usize,
+    pub pass_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionCount {
+    pub variation_id: String,
+    pub percentage: f64,
+    pub count: usize,
+}
+
+// Buckets every user through the same targeting, rollout and hashing as
+// generate_bucketed_config, as of `at`. Users land on the variation their target's
+// distribution picks, ignoring winning variations, and nobody is opted in.
+pub fn simulate_traffic(
+    config: &ConfigBody,
+    feature_key: &str,
+    users: SimulatedUsers,
+    at: DateTime<Utc>,
+) -> Result<TrafficSimulation, DevCycleError> {
+    let feature = config
+        .get_feature_for_key(feature_key)
+        .ok_or_else(errors::missing_feature)?;
+    let users = match users {
+        SimulatedUsers::Synthetic(count) => synthetic_users(count, at),
+        SimulatedUsers::Users(users) => users,
+    };
+    let opt_in_store = InMemoryOptInStore::new();
+    let client_custom_data = HashMap::new();
+
+    let mut simulation = TrafficSimulation {
+        feature_id: feature._id.clone(),
+        feature_key: feature.key.clone(),
+        users: users.len(),
+        forced: 0,
+        failed_prerequisites: 0,
+        not_targeted: 0,
+        variations: feature
+            .variations
+            .iter()
+            .map(|variation| VariationCount {
+                variation_id: variation._id.clone(),
+                variation_key: variation.key.clone(),
+                count: 0,
+            })
+            .collect(),
+        targets: feature
+            .configuration
+            .targets
+            .iter()
+            .map(|target| TargetSimulation {
+                target_id: target._id.clone(),
+                users: 0,
+                rollout: target
+                    .rollout_percentage(at)
+                    .map(|percentage| RolloutSimulation {
+                        percentage,
+                        passed: 0,
+                        pass_rate: 0.0,
+                    }),
+                distribution: target
+                    .distribution
+                    .iter()
+                    .map(|distribution| DistributionCount {
+                        variation_id: distribution.variation.clone(),
+                        percentage: distribution.percentage,
+                        count: 0,
+                    })
+                    .collect(),
+                chi_squared: None,
+                degrees_of_freedom: 0,
+            })
+            .collect(),
+    };
+
+    for user in users {
+        // Rollout pass rates cover every user, whichever target they end up in
+        for (target, target_simulation) in feature
+            .configuration
+            .targets
+            .iter()
+            .zip(simulation.targets.iter_mut())
+        {
+            let Some(rollout_simulation) = target_simulation.rollout.as_mut() else {
+                continue;
+            };
+            let bucketing_value = determine_user_bucketing_value_for_target(
+                target.bucketingkey.clone(),
+                user.user_id.clone(),
+                user.combined_custom_data(),
+            );
+            let rollout_hash =
+                murmurhash::generate_bounded_hashes(bucketing_value, target._id.clone())
+                    .rollout_hash;
+            if is_user_in_rollout(target, rollout_hash, at) {
+                rollout_simulation.passed += 1;
+            }
+        }
+
+        if let Some(variation) = get_forced_variation(feature, &user.user_id) {
+            simulation.forced += 1;
+            simulation.count_variation(&variation._id);
+            continue;
+        }
+        if !does_user_pass_prerequisites(
+            config,
+            feature,
+            &user,
+            &client_custom_data,
+            &opt_in_store,
+            &mut HashMap::new(),
+            at,
+        ) {
+            simulation.failed_prerequisites += 1;
+            continue;
+        }
+        let Ok(target_and_hashes) = does_user_qualify_for_feature(
+            config,
+            feature,
+            user,
+            client_custom_data.clone(),
+            &opt_in_store,
+            at,
+            None,
+        ) else {
+            simulation.not_targeted += 1;
+            continue;
+        };
+
+        let target_id = target_and_hashes.target._id.clone();
+        // A distribution that doesn't cover the user's hash serves them nothing
+        let Ok((variation_id, _)) = target_and_hashes
+            .target
+            .decide_target_variation(target_and_hashes.bounded_hash.bucketing_hash)
+        else {
+            simulation.not_targeted += 1;
+            continue;
+        };
+        if let Some(target_simulation) = simulation
+            .targets
+            .iter_mut()
+            .find(|target| target.target_id == target_id)
+        {
+            target_simulation.users += 1;
+            if let Some(distribution) = target_simulation
+                .distribution
+                .iter_mut()
+                .find(|distribution| distribution.variation_id == variation_id)
+            {
+                distribution.count += 1;
+            }
+        }
+        simulation.count_variation(&variation_id);
+    }
+
+    for target in &mut simulation.targets {
+        if let Some(rollout) = &mut target.rollout
+            && simulation.users > 0
+        {
+            rollout.pass_rate = rollout.passed as f64 / simulation.users as f64;
+        }
+        (target.chi_squared, target.degrees_of_freedom) =
+            chi_squared(&target.distribution, target.users);
+    }
+    Ok(simulation)
+}
+
+impl TrafficSimulation {
+    fn count_variation(&mut self, variation_id: &str) {
+        if let Some(variation) = self
+            .variations
+            .iter_mut()
+            .find(|variation| variation.variation_id == variation_id)
+        {
+            variation.count += 1;
+        }
+    }
+}
+
+fn synthetic_users(count: usize, at: DateTime<Utc>) -> Vec<PopulatedUser> {
+    let platform_data = Arc::new(PlatformData::generate());
+    (0..count)
+        .map(|i| PopulatedUser {
+            user_id: format!("simulated-user-{}", i),
+            email: String::new(),
+            name: String::new(),
+            language: String::new(),
+            country: String::new(),
+            ip: String::new(),
+            app_version: String::new(),
+            app_build: String::new(),
+            custom_data: HashMap::new(),
+            private_custom_data: HashMap::new(),
+            device_model: String::new(),
+            last_seen_date: at,
+            platform_data: platform_data.clone(),
+            created_date: at,
+        })
+        .collect()
+}
+
+// Variations configured at 0% expect no users and are left out of the statistic
+fn chi_squared(distribution: &[DistributionCount], users: usize) -> (Option<f64>, usize) {
+    let expected_counts: Vec<(f64, usize)> = distribution
+        .iter()
+        .map(|entry| (entry.percentage * users as f64, entry.count))
+        .filter(|(expected, _)| *expected > 0.0)
+        .collect();
+    if users == 0 || expected_counts.is_empty() {
+        return (None, 0);
+    }
+    let statistic = expected_counts
+        .iter()
+        .map(|(expected, observed)| (*observed as f64 - expected).powi(2) / expected)
+        .sum();
+    (Some(statistic), expected_counts.len() - 1)
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use crate::bucketing::simulation::{SimulatedUsers, simulate_traffic};
+    use crate::errors::DevCycleError;
+    use crate::util::test_fixtures::{
+        TEST_TARGET_ID, VARIATION_OFF, VARIATION_ON, config_body, create_user, test_config_json,
+    };
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_simulated_split_matches_distribution() {
+        let config = config_body(test_config_json());
+        let simulation = simulate_traffic(
+            &config,
+            "test",
+            SimulatedUsers::Synthetic(10_000),
+            date("2030-01-01T00:00:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(simulation.users, 10_000);
+        assert_eq!(simulation.not_targeted, 0);
+        let target = &simulation.targets[0];
+        assert_eq!(target.target_id, TEST_TARGET_ID);
+        assert_eq!(target.users, 10_000);
+        assert!(target.rollout.is_none());
+        assert_eq!(
+            target.distribution.iter().map(|d| d.count).sum::<usize>(),
+            10_000
+        );
+        for distribution in &target.distribution {
+            assert_eq!(distribution.percentage, 0.5);
+            assert!(distribution.count.abs_diff(5_000) < 200);
+        }
+        // Below the 0.1% critical value for one degree of freedom
+        assert_eq!(target.degrees_of_freedom, 1);
+        assert!(target.chi_squared.unwrap() < 10.83);
+
+        let counts: HashMap<&str, usize> = simulation
+            .variations
+            .iter()
+            .map(|variation| (variation.variation_id.as_str(), variation.count))
+            .collect();
+        assert_eq!(counts[VARIATION_ON] + counts[VARIATION_OFF], 10_000);
+        assert_eq!(
+            counts[VARIATION_ON],
+            target
+                .distribution
+                .iter()
+                .find(|d| d.variation_id == VARIATION_ON)
+                .unwrap()
+                .count
+        );
+    }
+
+    #[test]
+    fn test_simulated_rollout_pass_rate() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["rollout"] = json!({
+            "type": "gradual",
+            "startPercentage": 0.3,
+            "startDate": "2030-01-01T00:00:00Z",
+            "stages": []
+        });
+        let config = config_body(json);
+
+        let simulation = simulate_traffic(
+            &config,
+            "test",
+            SimulatedUsers::Synthetic(5_000),
+            date("2030-02-01T00:00:00Z"),
+        )
+        .unwrap();
+        let rollout = simulation.targets[0].rollout.as_ref().unwrap();
+        assert_eq!(rollout.percentage, 0.3);
+        assert!((rollout.pass_rate - 0.3).abs() < 0.03);
+        // Users the rollout passes over have no other target to fall through to
+        assert_eq!(simulation.targets[0].users, rollout.passed);
+        assert_eq!(simulation.not_targeted, 5_000 - rollout.passed);
+
+        // Before the rollout starts nobody is let in
+        let simulation = simulate_traffic(
+            &config,
+            "test",
+            SimulatedUsers::Synthetic(100),
+            date("2029-12-01T00:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(simulation.targets[0].rollout.as_ref().unwrap().passed, 0);
+        assert_eq!(simulation.targets[0].chi_squared, None);
+        assert_eq!(simulation.not_targeted, 100);
+    }
+
+    #[test]
+    fn test_simulated_supplied_users() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["forcedUsers"] = json!({"forced": VARIATION_OFF});
+        let config = config_body(json);
+
+        let users = vec![create_user("forced"), create_user("a"), create_user("b")];
+        let simulation =
+            simulate_traffic(&config, "test", SimulatedUsers::Users(users), Utc::now()).unwrap();
+        assert_eq!(simulation.users, 3);
+        assert_eq!(simulation.forced, 1);
+        assert_eq!(simulation.targets[0].users, 2);
+        assert_eq!(
+            simulation.variations.iter().map(|v| v.count).sum::<usize>(),
+            3
+        );
+
+        let serialized = serde_json::to_value(&simulation).unwrap();
+        assert_eq!(serialized["targets"][0]["targetId"], TEST_TARGET_ID);
+        assert!(serialized["targets"][0]["chiSquared"].is_number());
+
+        assert!(matches!(
+            simulate_traffic(&config, "missing", SimulatedUsers::Synthetic(1), Utc::now()),
+            Err(DevCycleError::MissingFeature)
+        ));
+    }
+}
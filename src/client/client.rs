@@ -0,0 +1,595 @@
+use crate::bucketing;
+use crate::bucketing::VariableForUserResult;
+use crate::bucketing::explain::{self, BucketedConfigExplanation, VariableExplanation};
+use crate::bucketing::rollout_timeline::{self, RolloutTimeline};
+use crate::config::ConfigBody;
+use crate::config::config_lint::{ConfigLintFinding, lint_config, lint_rollout};
+use crate::config::config_listeners::{
+    ConfigChangeEvent, ConfigListenerId, ConfigListeners, build_config_change_event,
+};
+use crate::config::config_source::{ConfigPoller, ConfigPollerHandle, ConfigSourceOptions};
+use crate::config::platform_data::PlatformData;
+use crate::errors::{self, DevCycleError};
+use crate::events::event::CustomEvent;
+use crate::events::event_queue::EventQueue;
+use crate::events::{EventQueueHandle, EventQueueOptions};
+use crate::segmentation::optin::{InMemoryOptInStore, OptInStore};
+use crate::user::{BucketedUserConfig, PopulatedUser, User};
+use crate::util::clock::{Clock, SystemClock};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+// State owned by a single client, shared with its event queue
+pub(crate) struct ClientState {
+    pub(crate) sdk_key: String,
+    config: RwLock<Option<Arc<ConfigBody>>>,
+    platform_data: RwLock<Option<Arc<PlatformData>>>,
+    client_custom_data: RwLock<HashMap<String, Value>>,
+    opt_in_store: RwLock<Arc<dyn OptInStore>>,
+    clock: RwLock<Arc<dyn Clock>>,
+    event_queue: RwLock<Option<Arc<EventQueue>>>,
+    config_poller: RwLock<Option<Arc<ConfigPoller>>>,
+    config_listeners: RwLock<ConfigListeners>,
+}
+
+impl ClientState {
+    fn new(sdk_key: &str) -> Self {
+        ClientState {
+            sdk_key: sdk_key.to_string(),
+            config: RwLock::new(None),
+            platform_data: RwLock::new(None),
+            client_custom_data: RwLock::new(HashMap::new()),
+            opt_in_store: RwLock::new(Arc::new(InMemoryOptInStore::new())),
+            clock: RwLock::new(Arc::new(SystemClock)),
+            event_queue: RwLock::new(None),
+            config_poller: RwLock::new(None),
+            config_listeners: RwLock::new(ConfigListeners::default()),
+        }
+    }
+
+    pub(crate) fn get_config(&self) -> Option<Arc<ConfigBody>> {
+        self.config.read().unwrap().clone() // Clones the Arc, not the ConfigBody
+    }
+
+    // Listeners are called on the calling thread once the new config is in use
+    pub(crate) fn set_config(&self, config: ConfigBody) {
+        let config = Arc::new(config);
+        let previous = self.config.write().unwrap().replace(config.clone());
+
+        let (listeners, watched_users) = {
+            let config_listeners = self
+                .config_listeners
+                .read()
+                .expect("config listeners RwLock poisoned");
+            if config_listeners.is_empty() {
+                return;
+            }
+            (
+                config_listeners.listeners(),
+                config_listeners.watched_users(),
+            )
+        };
+        let event = build_config_change_event(
+            &self.sdk_key,
+            previous.as_deref(),
+            &config,
+            watched_users,
+            &self.get_client_custom_data(),
+            self.get_opt_in_store().as_ref(),
+            self.now(),
+        );
+        for listener in listeners {
+            listener(&event);
+        }
+    }
+
+    pub(crate) fn add_config_listener(
+        &self,
+        listener: impl Fn(&ConfigChangeEvent) + Send + Sync + 'static,
+    ) -> ConfigListenerId {
+        self.config_listeners
+            .write()
+            .expect("config listeners RwLock poisoned")
+            .add_listener(Arc::new(listener))
+    }
+
+    pub(crate) fn remove_config_listener(&self, id: ConfigListenerId) -> bool {
+        self.config_listeners
+            .write()
+            .expect("config listeners RwLock poisoned")
+            .remove_listener(id)
+    }
+
+    pub(crate) fn watch_user(&self, user: PopulatedUser) {
+        self.config_listeners
+            .write()
+            .expect("config listeners RwLock poisoned")
+            .watch_user(user);
+    }
+
+    pub(crate) fn unwatch_user(&self, user_id: &str) -> bool {
+        self.config_listeners
+            .write()
+            .expect("config listeners RwLock poisoned")
+            .unwatch_user(user_id)
+    }
+
+    pub(crate) fn has_config(&self) -> bool {
+        self.config.read().unwrap().is_some()
+    }
+
+    pub(crate) fn get_platform_data(&self) -> Result<Arc<PlatformData>, DevCycleError> {
+        let data = self
+            .platform_data
+            .read()
+            .expect("Failed to acquire read lock on platform data: lock poisoned");
+        data.clone()
+            .ok_or_else(|| errors::missing_platform_data(&self.sdk_key))
+    }
+
+    pub(crate) fn set_platform_data(&self, platform_data: PlatformData) {
+        let mut data = self
+            .platform_data
+            .write()
+            .expect("Failed to acquire write lock on platform data (lock may be poisoned)");
+        *data = Some(Arc::new(platform_data));
+    }
+
+    pub(crate) fn get_client_custom_data(&self) -> HashMap<String, Value> {
+        self.client_custom_data.read().unwrap().clone()
+    }
+
+    pub(crate) fn set_client_custom_data(
+        &self,
+        data: HashMap<String, Value>,
+    ) -> HashMap<String, Value> {
+        std::mem::replace(&mut *self.client_custom_data.write().unwrap(), data)
+    }
+
+    pub(crate) fn get_opt_in_store(&self) -> Arc<dyn OptInStore> {
+        self.opt_in_store
+            .read()
+            .expect("Failed to acquire read lock on opt-in store: lock poisoned")
+            .clone()
+    }
+
+    pub(crate) fn set_opt_in_store(&self, store: Arc<dyn OptInStore>) {
+        *self
+            .opt_in_store
+            .write()
+            .expect("Failed to acquire write lock on opt-in store: lock poisoned") = store;
+    }
+
+    pub(crate) fn get_clock(&self) -> Arc<dyn Clock> {
+        self.clock
+            .read()
+            .expect("Failed to acquire read lock on clock: lock poisoned")
+            .clone()
+    }
+
+    pub(crate) fn set_clock(&self, clock: Arc<dyn Clock>) {
+        *self
+            .clock
+            .write()
+            .expect("Failed to acquire write lock on clock: lock poisoned") = clock;
+    }
+
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.get_clock().now()
+    }
+
+    pub(crate) fn get_event_queue(&self) -> Option<Arc<EventQueue>> {
+        self.event_queue
+            .read()
+            .expect("event queue RwLock poisoned")
+            .clone()
+    }
+
+    // Returns the queue previously registered for the client, if any
+    pub(crate) fn set_event_queue(&self, event_queue: Arc<EventQueue>) -> Option<Arc<EventQueue>> {
+        self.event_queue
+            .write()
+            .expect("event queue RwLock poisoned")
+            .replace(event_queue)
+    }
+
+    pub(crate) fn remove_event_queue(&self) -> Option<Arc<EventQueue>> {
+        self.event_queue
+            .write()
+            .expect("event queue RwLock poisoned")
+            .take()
+    }
+
+    // Only unregister the queue if it has not been replaced since
+    pub(crate) fn remove_event_queue_if_same(&self, event_queue: &Arc<EventQueue>) {
+        let mut current = self
+            .event_queue
+            .write()
+            .expect("event queue RwLock poisoned");
+        if current
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, event_queue))
+        {
+            *current = None;
+        }
+    }
+
+    // Returns the poller previously registered for the client, if any
+    pub(crate) fn set_config_poller(&self, poller: Arc<ConfigPoller>) -> Option<Arc<ConfigPoller>> {
+        self.config_poller
+            .write()
+            .expect("config poller RwLock poisoned")
+            .replace(poller)
+    }
+
+    pub(crate) fn remove_config_poller(&self) -> Option<Arc<ConfigPoller>> {
+        self.config_poller
+            .write()
+            .expect("config poller RwLock poisoned")
+            .take()
+    }
+
+    // Only unregister the poller if it has not been replaced since
+    pub(crate) fn remove_config_poller_if_same(&self, poller: &Arc<ConfigPoller>) {
+        let mut current = self
+            .config_poller
+            .write()
+            .expect("config poller RwLock poisoned");
+        if current
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, poller))
+        {
+            *current = None;
+        }
+    }
+}
+
+/// A local bucketing client owning its own config, platform data, client custom data,
+/// opt-in store, event queue and config poller.
+///
+/// Several clients can use the same SDK key without sharing any state. Dropping a client
+/// stops its config poller and its event queue without flushing, call `close` first to
+/// publish queued events.
+pub struct DevCycleLocalBucketing {
+    pub(crate) state: Arc<ClientState>,
+}
+
+impl DevCycleLocalBucketing {
+    pub fn new(sdk_key: &str) -> Self {
+        DevCycleLocalBucketing {
+            state: Arc::new(ClientState::new(sdk_key)),
+        }
+    }
+
+    /// Create a client with its config, custom data and platform data set and its event
+    /// queue started.
+    pub async fn init(
+        sdk_key: &str,
+        config_body: ConfigBody,
+        event_queue_options: EventQueueOptions,
+        client_custom_data: HashMap<String, Value>,
+        platform_data: PlatformData,
+    ) -> Result<Self, DevCycleError> {
+        let client = DevCycleLocalBucketing::new(sdk_key);
+        client.set_platform_data(platform_data);
+        client.set_client_custom_data(client_custom_data)?;
+        client.set_config(config_body)?;
+        client.init_event_queue(event_queue_options).await?;
+        Ok(client)
+    }
+
+    pub fn sdk_key(&self) -> &str {
+        &self.state.sdk_key
+    }
+
+    pub fn set_config(&self, config_body: ConfigBody) -> Result<(), DevCycleError> {
+        self.state.set_config(config_body);
+        Ok(())
+    }
+
+    /// Set the config only if linting it finds no errors, otherwise return
+    /// `DevCycleError::InvalidConfig` with the error findings and keep the current config.
+    pub fn set_config_strict(&self, config_body: ConfigBody) -> Result<(), DevCycleError> {
+        let errors: Vec<ConfigLintFinding> = lint_config(&config_body)
+            .into_iter()
+            .filter(ConfigLintFinding::is_error)
+            .collect();
+        if !errors.is_empty() {
+            return Err(DevCycleError::InvalidConfig(errors));
+        }
+        self.set_config(config_body)
+    }
+
+    /// Set the config from a serialized `ConfigBodyProto` message.
+    #[cfg(feature = "protobuf")]
+    pub fn set_config_from_protobuf_bytes(&self, bytes: &[u8]) -> Result<(), DevCycleError> {
+        self.set_config(crate::protobuf::decode_config_body(bytes)?)
+    }
+
+    pub fn has_config(&self) -> bool {
+        self.state.has_config()
+    }
+
+    /// Register a callback invoked with what changed every time the config is replaced,
+    /// including by the config poller.
+    pub fn on_config_change(
+        &self,
+        listener: impl Fn(&ConfigChangeEvent) + Send + Sync + 'static,
+    ) -> ConfigListenerId {
+        self.state.add_config_listener(listener)
+    }
+
+    /// Returns false when no listener is registered with the id.
+    pub fn remove_config_listener(&self, id: ConfigListenerId) -> bool {
+        self.state.remove_config_listener(id)
+    }
+
+    /// Re-evaluate the user on every config change and report their changed variable
+    /// values to the config change listeners. Replaces a watched user with the same user id.
+    pub fn watch_user(&self, user: PopulatedUser) {
+        self.state.watch_user(user);
+    }
+
+    pub fn unwatch_user(&self, user_id: &str) -> bool {
+        self.state.unwatch_user(user_id)
+    }
+
+    pub fn set_platform_data(&self, platform_data: PlatformData) {
+        self.state.set_platform_data(platform_data);
+    }
+
+    pub fn set_client_custom_data(
+        &self,
+        client_custom_data: HashMap<String, Value>,
+    ) -> Result<(), DevCycleError> {
+        self.state.set_client_custom_data(client_custom_data);
+        Ok(())
+    }
+
+    pub fn set_opt_in_store(&self, opt_in_store: Arc<dyn OptInStore>) {
+        self.state.set_opt_in_store(opt_in_store);
+    }
+
+    pub fn get_opt_in_store(&self) -> Arc<dyn OptInStore> {
+        self.state.get_opt_in_store()
+    }
+
+    /// Replace the clock rollouts are evaluated with, `SystemClock` by default.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        self.state.set_clock(clock);
+    }
+
+    pub fn generate_bucketed_config(
+        &self,
+        user: PopulatedUser,
+        client_custom_data: HashMap<String, Value>,
+    ) -> Result<BucketedUserConfig, DevCycleError> {
+        self.generate_bucketed_config_at(user, client_custom_data, self.state.now())
+    }
+
+    /// Generate the bucketed config with rollouts evaluated as of `at` instead of the clock's
+    /// time. Targeting uses the current config, so this replays past rollout schedules.
+    pub fn generate_bucketed_config_at(
+        &self,
+        user: PopulatedUser,
+        client_custom_data: HashMap<String, Value>,
+        at: DateTime<Utc>,
+    ) -> Result<BucketedUserConfig, DevCycleError> {
+        let config = self.state.get_config().ok_or(errors::missing_config())?;
+        bucketing::generate_bucketed_config_from_config(
+            &config,
+            user,
+            client_custom_data,
+            self.state.get_opt_in_store().as_ref(),
+            at,
+        )
+    }
+
+    pub fn generate_bucketed_config_from_user(
+        &self,
+        user: User,
+    ) -> Result<BucketedUserConfig, DevCycleError> {
+        let platform_data = self.state.get_platform_data()?;
+        let populated_user = user.get_populated_user_with_platform_data_and_time(
+            &self.state.sdk_key,
+            Some(platform_data),
+            self.state.now(),
+        );
+        self.generate_bucketed_config(populated_user, self.state.get_client_custom_data())
+    }
+
+    pub fn variable_for_user(
+        &self,
+        user: PopulatedUser,
+        variable_key: &str,
+        variable_type: &str,
+    ) -> Result<VariableForUserResult, DevCycleError> {
+        bucketing::variable_for_user_with_client(
+            &self.state,
+            user,
+            variable_key,
+            variable_type,
+            self.state.get_client_custom_data(),
+        )
+    }
+
+    /// Evaluate the variable with rollouts evaluated as of `at`. Meant for auditing, so no
+    /// evaluation events are queued and no event queue is needed.
+    pub fn variable_for_user_at(
+        &self,
+        user: PopulatedUser,
+        variable_key: &str,
+        variable_type: &str,
+        at: DateTime<Utc>,
+    ) -> Result<VariableForUserResult, DevCycleError> {
+        bucketing::evaluate_variable_for_user(
+            &self.state,
+            user,
+            variable_key,
+            variable_type,
+            self.state.get_client_custom_data(),
+            at,
+            None,
+        )
+    }
+
+    // Generates the bucketed config along with a trace of how every feature was evaluated
+    pub fn explain_bucketed_config(
+        &self,
+        user: PopulatedUser,
+        client_custom_data: HashMap<String, Value>,
+    ) -> Result<BucketedConfigExplanation, DevCycleError> {
+        let config = self.state.get_config().ok_or(errors::missing_config())?;
+        explain::explain_bucketed_config(
+            &config,
+            user,
+            client_custom_data,
+            self.state.get_opt_in_store().as_ref(),
+            self.state.now(),
+        )
+    }
+
+    // Evaluates the variable like variable_for_user and traces its feature, without queueing
+    // any events
+    pub fn explain_variable_for_user(
+        &self,
+        user: PopulatedUser,
+        variable_key: &str,
+        variable_type: &str,
+    ) -> Result<VariableExplanation, DevCycleError> {
+        explain::explain_variable_for_user(
+            &self.state,
+            user,
+            variable_key,
+            variable_type,
+            self.state.get_client_custom_data(),
+        )
+    }
+
+    // The rollout percentage timeline of one of the feature's targets, None when the target
+    // has no rollout and always lets everyone in
+    pub fn rollout_timeline(
+        &self,
+        feature_key: &str,
+        target_id: &str,
+    ) -> Result<Option<RolloutTimeline>, DevCycleError> {
+        let config = self.state.get_config().ok_or(errors::missing_config())?;
+        let feature = config
+            .get_feature_for_key(feature_key)
+            .ok_or(errors::missing_feature())?;
+        let target = feature
+            .configuration
+            .targets
+            .iter()
+            .find(|target| target._id == target_id)
+            .ok_or(errors::missing_target())?;
+        if let Some(timeline) = &target.compiled_rollout {
+            return Ok(Some(timeline.as_ref().clone()));
+        }
+        let Some(rollout) = &target.rollout else {
+            return Ok(None);
+        };
+        let mut findings = Vec::new();
+        lint_rollout(
+            rollout,
+            &format!("features[{}].targets[{}].rollout", feature_key, target_id),
+            &mut findings,
+        );
+        if !findings.is_empty() {
+            return Err(DevCycleError::InvalidConfig(findings));
+        }
+        RolloutTimeline::from_rollout(rollout)
+            .map(Some)
+            .map_err(|e| DevCycleError::Parse {
+                message: e,
+                source: None,
+            })
+    }
+
+    // When a rollout could next change the user's bucketed config, None when no rollout ever
+    // will. Usable as the expiry of a cached bucketed config.
+    pub fn next_assignment_change(
+        &self,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+    ) -> Result<Option<DateTime<Utc>>, DevCycleError> {
+        let config = self.state.get_config().ok_or(errors::missing_config())?;
+        Ok(rollout_timeline::next_assignment_change(
+            &config,
+            user,
+            client_custom_data,
+            self.state.get_opt_in_store().as_ref(),
+            self.state.now(),
+        ))
+    }
+
+    // Starts a background task that processes queued events and flushes them on the
+    // configured interval. A previously initialized queue is stopped without flushing.
+    pub async fn init_event_queue(
+        &self,
+        event_queue_options: EventQueueOptions,
+    ) -> Result<EventQueueHandle, DevCycleError> {
+        let queue = Arc::new(EventQueue::new(&self.state, event_queue_options)?);
+        queue.start_processing();
+        if let Some(previous) = self.state.set_event_queue(queue.clone()) {
+            previous.shutdown();
+        }
+        Ok(EventQueueHandle { event_queue: queue })
+    }
+
+    // Fetches the config from the CDN, then keeps polling it in the background on the
+    // configured interval. A previously started poller is stopped.
+    pub async fn init_config_poller(
+        &self,
+        options: ConfigSourceOptions,
+    ) -> Result<ConfigPollerHandle, DevCycleError> {
+        let poller = Arc::new(ConfigPoller::new(&self.state, options));
+        poller.fetch_config().await?;
+        poller.start_polling();
+        if let Some(previous) = self.state.set_config_poller(poller.clone()) {
+            previous.shutdown();
+        }
+        Ok(ConfigPollerHandle { poller })
+    }
+
+    // Queues a custom event for the user, returns false when custom event logging is disabled
+    pub fn track(&self, user: User, custom_event: CustomEvent) -> Result<bool, DevCycleError> {
+        match self.state.get_event_queue() {
+            Some(event_queue) => event_queue.queue_custom_event(user, custom_event),
+            None => Err(errors::event_queue_not_initialized()),
+        }
+    }
+
+    // Drains queued events and publishes them now, returning how many were reported
+    pub async fn flush_events(&self) -> Result<i64, DevCycleError> {
+        match self.state.get_event_queue() {
+            Some(event_queue) => event_queue.flush_events().await,
+            None => Err(errors::event_queue_not_initialized()),
+        }
+    }
+
+    // Stops the config poller and the event queue, draining queued events and publishing
+    // them in a final flush
+    pub async fn close(&self) -> Result<(), DevCycleError> {
+        if let Some(poller) = self.state.remove_config_poller() {
+            poller.shutdown();
+        }
+        match self.state.remove_event_queue() {
+            Some(event_queue) => event_queue.close().await.map(|_| ()),
+            None => Err(errors::event_queue_not_initialized()),
+        }
+    }
+}
+
+impl Drop for DevCycleLocalBucketing {
+    fn drop(&mut self) {
+        if let Some(poller) = self.state.remove_config_poller() {
+            poller.shutdown();
+        }
+        if let Some(event_queue) = self.state.remove_event_queue() {
+            event_queue.shutdown();
+        }
+    }
+}
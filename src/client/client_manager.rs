@@ -0,0 +1,30 @@
+use crate::client::DevCycleLocalBucketing;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+// Clients backing the sdk_key based free functions
+pub(crate) static CLIENTS: Lazy<RwLock<HashMap<String, Arc<DevCycleLocalBucketing>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+pub(crate) fn get_client(sdk_key: &str) -> Option<Arc<DevCycleLocalBucketing>> {
+    let clients = CLIENTS.read().expect("CLIENTS RwLock poisoned");
+    clients.get(sdk_key).cloned()
+}
+
+// Unregisters the SDK key's client, the caller is responsible for closing it
+pub(crate) fn remove_client(sdk_key: &str) -> Option<Arc<DevCycleLocalBucketing>> {
+    let mut clients = CLIENTS.write().expect("CLIENTS RwLock poisoned");
+    clients.remove(sdk_key)
+}
+
+pub(crate) fn get_or_create_client(sdk_key: &str) -> Arc<DevCycleLocalBucketing> {
+    if let Some(client) = get_client(sdk_key) {
+        return client;
+    }
+    let mut clients = CLIENTS.write().expect("CLIENTS RwLock poisoned");
+    clients
+        .entry(sdk_key.to_string())
+        .or_insert_with(|| Arc::new(DevCycleLocalBucketing::new(sdk_key)))
+        .clone()
+}
@@ -0,0 +1,277 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::{DevCycleLocalBucketing, client_manager};
+    use crate::config::platform_data::PlatformData;
+    use crate::config::{ConfigBody, FullConfig};
+    use crate::constants;
+    use crate::events::EventQueueOptions;
+    use crate::events::event::CustomEvent;
+    use crate::events::event_sink::InMemoryEventSink;
+    use crate::user::User;
+    use crate::util::clock::ManualClock;
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    fn load_test_config() -> ConfigBody {
+        let config_json = include_str!("../../tests/resources/test_config.json");
+        let full_config: FullConfig =
+            serde_json::from_str(config_json).expect("Failed to parse test config");
+        ConfigBody::from_full_config(full_config).expect("Failed to create config body")
+    }
+
+    fn create_platform_data(hostname: &str) -> PlatformData {
+        PlatformData {
+            sdk_type: "server".to_string(),
+            sdk_version: "1.0.0".to_string(),
+            platform_version: "1.0.0".to_string(),
+            device_model: "test-device".to_string(),
+            platform: "test".to_string(),
+            hostname: hostname.to_string(),
+        }
+    }
+
+    fn create_test_user(user_id: &str) -> User {
+        User {
+            user_id: user_id.to_string(),
+            email: String::new(),
+            name: String::new(),
+            language: String::new(),
+            country: String::new(),
+            ip: String::new(),
+            app_version: String::new(),
+            app_build: String::new(),
+            custom_data: HashMap::new(),
+            private_custom_data: HashMap::new(),
+            device_model: String::new(),
+            last_seen_date: Utc::now(),
+        }
+    }
+
+    fn in_memory_sink_options(sink: Arc<InMemoryEventSink>) -> EventQueueOptions {
+        EventQueueOptions {
+            event_sink: Some(sink),
+            flush_events_interval: Duration::from_secs(3600),
+            ..Default::default()
+        }
+    }
+
+    async fn init_client(sdk_key: &str, sink: Arc<InMemoryEventSink>) -> DevCycleLocalBucketing {
+        DevCycleLocalBucketing::init(
+            sdk_key,
+            load_test_config(),
+            in_memory_sink_options(sink),
+            HashMap::new(),
+            create_platform_data("client-host"),
+        )
+        .await
+        .expect("Failed to initialize client")
+    }
+
+    #[tokio::test]
+    async fn test_clients_with_same_sdk_key_are_isolated() {
+        let sdk_key = "test_client_isolation";
+        let configured = init_client(sdk_key, Arc::new(InMemoryEventSink::new())).await;
+        let empty = DevCycleLocalBucketing::new(sdk_key);
+        empty.set_platform_data(create_platform_data("other-host"));
+
+        assert!(configured.has_config());
+        assert!(!empty.has_config());
+        assert!(
+            configured
+                .generate_bucketed_config_from_user(create_test_user("user"))
+                .is_ok()
+        );
+        assert!(
+            empty
+                .generate_bucketed_config_from_user(create_test_user("user"))
+                .is_err()
+        );
+        // Neither client is visible through the sdk_key based functions
+        assert!(client_manager::get_client(sdk_key).is_none());
+        configured.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_populated_users_follow_the_client_clock() {
+        let client = DevCycleLocalBucketing::new("test_client_clock_user");
+        client.set_platform_data(create_platform_data("clock-host"));
+        client.set_config(load_test_config()).unwrap();
+        let now = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+        client.set_clock(Arc::new(ManualClock::new(now)));
+
+        let config = client
+            .generate_bucketed_config_from_user(create_test_user("user"))
+            .unwrap();
+        assert_eq!(config.user.created_date, now);
+    }
+
+    #[tokio::test]
+    async fn test_client_custom_data_is_per_instance() {
+        let sdk_key = "test_client_custom_data";
+        let first = DevCycleLocalBucketing::new(sdk_key);
+        let second = DevCycleLocalBucketing::new(sdk_key);
+        let mut custom_data = HashMap::new();
+        custom_data.insert("plan".to_string(), Value::String("pro".to_string()));
+        first.set_client_custom_data(custom_data).unwrap();
+
+        assert_eq!(
+            first.state.get_client_custom_data()["plan"],
+            Value::String("pro".to_string())
+        );
+        assert!(second.state.get_client_custom_data().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_events_are_published_to_its_own_sink() {
+        let sdk_key = "test_client_events";
+        let first_sink = Arc::new(InMemoryEventSink::new());
+        let second_sink = Arc::new(InMemoryEventSink::new());
+        let first = init_client(sdk_key, first_sink.clone()).await;
+        let second = init_client(sdk_key, second_sink.clone()).await;
+
+        let platform_data = first.state.get_platform_data().unwrap();
+        let user = create_test_user("client-user").get_populated_user_with_platform_data_and_time(
+            sdk_key,
+            Some(platform_data),
+            Utc::now(),
+        );
+        let result = first
+            .variable_for_user(user, "test", constants::VARIABLE_TYPES_BOOL)
+            .unwrap();
+        assert!(!result.feature_id.is_empty());
+        let tracked = first
+            .track(
+                create_test_user("client-user"),
+                CustomEvent {
+                    custom_type: "purchase".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(tracked);
+
+        first.close().await.unwrap();
+        second.close().await.unwrap();
+        assert_eq!(first_sink.event_count(), 2);
+        assert!(second_sink.batches().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dropping_client_stops_event_queue_and_frees_state() {
+        let sdk_key = "test_client_drop";
+        let client = DevCycleLocalBucketing::new(sdk_key);
+        client.set_platform_data(create_platform_data("client-host"));
+        let handle = client
+            .init_event_queue(in_memory_sink_options(Arc::new(InMemoryEventSink::new())))
+            .await
+            .unwrap();
+        let state = Arc::downgrade(&client.state);
+        assert!(handle.is_running());
+
+        drop(client);
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(!handle.is_running());
+        assert!(state.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_free_functions_use_registered_client() {
+        let sdk_key = "test_client_free_functions";
+        crate::set_platform_data(sdk_key, create_platform_data("client-host"));
+        crate::set_config(sdk_key, load_test_config()).unwrap();
+
+        let client = client_manager::get_client(sdk_key).unwrap();
+        assert!(client.has_config());
+        assert!(client.state.get_platform_data().is_ok());
+        assert!(
+            crate::generate_bucketed_config_from_user(sdk_key, create_test_user("user")).is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_close_unregisters_client() {
+        let sdk_key = "test_client_close_unregisters";
+        crate::set_platform_data(sdk_key, create_platform_data("client-host"));
+        crate::set_config(sdk_key, load_test_config()).unwrap();
+        crate::init_event_queue(
+            sdk_key,
+            in_memory_sink_options(Arc::new(InMemoryEventSink::new())),
+        )
+        .await
+        .unwrap();
+        let state = Arc::downgrade(&client_manager::get_client(sdk_key).unwrap().state);
+
+        crate::close(sdk_key).await.unwrap();
+        assert!(client_manager::get_client(sdk_key).is_none());
+        assert!(state.upgrade().is_none());
+        assert!(crate::close(sdk_key).await.is_err());
+    }
+
+    #[test]
+    fn test_reads_do_not_register_client() {
+        let sdk_key = "test_client_reads_only";
+        let user = create_test_user("user").get_populated_user_with_platform_data_and_time(
+            sdk_key,
+            Some(Arc::new(create_platform_data("client-host"))),
+            Utc::now(),
+        );
+
+        assert!(crate::get_opt_in_store(sdk_key).is_none());
+        assert!(matches!(
+            crate::generate_bucketed_config(sdk_key, user.clone(), HashMap::new()),
+            Err(crate::Error::MissingConfig)
+        ));
+        assert!(matches!(
+            crate::variable_for_user(sdk_key, user, "test", constants::VARIABLE_TYPES_BOOL),
+            Err(crate::Error::MissingConfig)
+        ));
+        assert!(client_manager::get_client(sdk_key).is_none());
+    }
+
+    // Config management and evaluation must not require an async runtime, only the event queue does
+    #[test]
+    fn test_sync_api_without_runtime() {
+        let sdk_key = "test_client_sync_api";
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let sink = Arc::new(InMemoryEventSink::new());
+        crate::set_platform_data(sdk_key, create_platform_data("client-host"));
+        crate::set_config(sdk_key, load_test_config()).unwrap();
+        crate::set_client_custom_data(sdk_key, HashMap::new()).unwrap();
+
+        runtime
+            .block_on(crate::init_event_queue(
+                sdk_key,
+                in_memory_sink_options(sink.clone()),
+            ))
+            .unwrap();
+
+        let config =
+            crate::generate_bucketed_config_from_user(sdk_key, create_test_user("user")).unwrap();
+        assert!(!config.variables.is_empty());
+
+        let platform_data = client_manager::get_client(sdk_key)
+            .unwrap()
+            .state
+            .get_platform_data()
+            .unwrap();
+        let user = create_test_user("user").get_populated_user_with_platform_data_and_time(
+            sdk_key,
+            Some(platform_data),
+            Utc::now(),
+        );
+        let result =
+            crate::variable_for_user(sdk_key, user, "test", constants::VARIABLE_TYPES_BOOL)
+                .unwrap();
+        assert!(!result.feature_id.is_empty());
+
+        runtime.block_on(async {
+            crate::close(sdk_key).await.unwrap();
+        });
+        assert_eq!(sink.event_count(), 1);
+    }
+}
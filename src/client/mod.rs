@@ -0,0 +1,8 @@
+pub mod client;
+pub(crate) mod client_manager;
+
+#[cfg(test)]
+mod client_tests;
+
+pub(crate) use client::ClientState;
+pub use client::DevCycleLocalBucketing;
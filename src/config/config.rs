@@ -1,8 +1,11 @@
+use crate::bucketing::rollout_timeline::RolloutTimeline;
 use crate::feature::ConfigFeature;
 use crate::filters::NoIdAudience;
+use crate::segmentation::compiled_filters::FilterCompiler;
 use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -74,18 +77,14 @@ pub(crate) struct BucketingConfiguration {
     pub disable_push_state_event_logging: bool,
 }
 
+// The name is kept as-is since it is part of the public config API
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SSE {
     pub hostname: String,
     pub path: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct SSEHost {
-    pub hostname: String,
-    pub path: String,
-}
-
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Variable {
     pub _id: String,
@@ -116,6 +115,8 @@ pub struct ConfigBody {
     pub environment: Environment,
     pub features: Vec<ConfigFeature>,
     pub variables: Vec<Variable>,
+    // Realtime updates endpoint, the hostname is empty when the project has realtime updates
+    // disabled
     pub sse: SSE,
     pub variable_id_map: HashMap<String, Variable>,
     pub variable_key_map: HashMap<String, Variable>,
@@ -141,31 +142,45 @@ impl ConfigBody {
             }
         }
 
-        let variable_id_map = HashMap::new();
-        let variable_key_map = HashMap::new();
-        let variable_id_to_feature_map = HashMap::new();
+        ConfigBody::from_parts(
+            full_config.project,
+            audiences_map,
+            full_config.environment,
+            full_config.features,
+            full_config.variables,
+            full_config.sse,
+        )
+    }
 
-        let sse = full_config.sse.unwrap_or_else(|| SSE {
-            hostname: "localhost".to_string(),
-            path: "/sse".to_string(),
+    // Builds the lookup maps and orders features, shared by every config source
+    pub(crate) fn from_parts(
+        project: Project,
+        audiences: HashMap<String, NoIdAudience>,
+        environment: Environment,
+        features: Vec<ConfigFeature>,
+        variables: Vec<Variable>,
+        sse: Option<SSE>,
+    ) -> Result<ConfigBody, String> {
+        let sse = sse.unwrap_or_else(|| SSE {
+            hostname: String::new(),
+            path: String::new(),
         });
-
         let mut config = ConfigBody {
-            project: full_config.project,
-            audiences: audiences_map,
-            environment: full_config.environment,
-            features: full_config.features,
-            variables: full_config.variables,
+            project,
+            audiences,
+            environment,
+            features,
+            variables,
             sse,
-            variable_id_map,
-            variable_key_map,
-            variable_id_to_feature_map,
+            variable_id_map: HashMap::new(),
+            variable_key_map: HashMap::new(),
+            variable_id_to_feature_map: HashMap::new(),
             etag: String::new(),
             ray_id: String::new(),
             last_modified: chrono::Utc::now(),
         };
 
-        config.compile();
+        config.compile()?;
         Ok(config)
     }
 
@@ -183,6 +198,13 @@ impl ConfigBody {
         None
     }
 
+    pub(crate) fn get_feature_for_id(&self, id: &str) -> Option<&ConfigFeature> {
+        if let Some(feature) = self.features.iter().find(|f| f._id == id) {
+            return Some(feature);
+        }
+        None
+    }
+
     pub(crate) fn get_variable_for_id(&self, id: &str) -> Option<&Variable> {
         if let Some(variable) = self.variable_id_map.get(id) {
             return Some(variable);
@@ -197,19 +219,7 @@ impl ConfigBody {
         None
     }
 
-    pub(crate) fn compile(&mut self) {
-        // Build mapping of variable IDs to features
-        for feature in &self.features {
-            for variation in &feature.variations {
-                for variable in &variation.variables {
-                    if !self.variable_id_to_feature_map.contains_key(&variable._var) {
-                        self.variable_id_to_feature_map
-                            .insert(variable._var.clone(), feature.clone());
-                    }
-                }
-            }
-        }
-
+    pub(crate) fn compile(&mut self) -> Result<(), String> {
         // Build mappings for variables by key and ID
         for variable in &self.variables {
             self.variable_key_map
@@ -218,13 +228,130 @@ impl ConfigBody {
                 .insert(variable._id.clone(), variable.clone());
         }
 
-        // Sort the feature distributions by "_variation" attribute in descending alphabetical order
+        // Sort the feature distributions by "_variation" attribute in descending alphabetical order,
+        // lower each target audience into a filter plan and build each rollout's timeline
+        let mut filter_compiler = FilterCompiler::new(&self.audiences);
         for feature in &mut self.features {
             for target in &mut feature.configuration.targets {
                 target
                     .distribution
                     .sort_by(|a, b| b.variation.cmp(&a.variation));
+                target.compiled_audience = Some(Arc::new(
+                    filter_compiler.compile_operator(&target.audience.filters),
+                ));
+                // Malformed rollouts are left uncompiled and let nobody in, lint_config
+                // reports them
+                target.compiled_rollout = target
+                    .rollout
+                    .as_ref()
+                    .and_then(|rollout| RolloutTimeline::from_rollout(rollout).ok())
+                    .map(Arc::new);
             }
         }
+
+        self.sort_features_by_prerequisites()?;
+
+        // Build mapping of variable IDs to features last, so variable evaluation sees the sorted
+        // distributions and resolves shared variables in the order generate_bucketed_config
+        // evaluates features
+        for feature in &self.features {
+            for variation in &feature.variations {
+                for variable in &variation.variables {
+                    if !self.variable_id_to_feature_map.contains_key(&variable._var) {
+                        self.variable_id_to_feature_map
+                            .insert(variable._var.clone(), feature.clone());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Reorder features so every feature comes after the features it lists as prerequisites,
+    // keeping the original order otherwise. Fails if the prerequisites form a cycle.
+    fn sort_features_by_prerequisites(&mut self) -> Result<(), String> {
+        let index_by_id: HashMap<&str, usize> = self
+            .features
+            .iter()
+            .enumerate()
+            .map(|(index, feature)| (feature._id.as_str(), index))
+            .collect();
+
+        let mut visit_states = vec![PrerequisiteVisit::Pending; self.features.len()];
+        let mut path: Vec<usize> = Vec::new();
+        let mut order: Vec<usize> = Vec::with_capacity(self.features.len());
+        for index in 0..self.features.len() {
+            visit_feature_prerequisites(
+                index,
+                &self.features,
+                &index_by_id,
+                &mut visit_states,
+                &mut path,
+                &mut order,
+            )?;
+        }
+
+        let mut features: Vec<Option<ConfigFeature>> = std::mem::take(&mut self.features)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.features = order
+            .into_iter()
+            .filter_map(|index| features[index].take())
+            .collect();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PrerequisiteVisit {
+    Pending,
+    InProgress,
+    Done,
+}
+
+fn visit_feature_prerequisites(
+    index: usize,
+    features: &[ConfigFeature],
+    index_by_id: &HashMap<&str, usize>,
+    visit_states: &mut [PrerequisiteVisit],
+    path: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) -> Result<(), String> {
+    match visit_states[index] {
+        PrerequisiteVisit::Done => return Ok(()),
+        PrerequisiteVisit::InProgress => {
+            let cycle_start = path.iter().position(|&i| i == index).unwrap_or(0);
+            let cycle: Vec<&str> = path[cycle_start..]
+                .iter()
+                .chain(std::iter::once(&index))
+                .map(|&i| features[i].key.as_str())
+                .collect();
+            return Err(format!(
+                "Feature prerequisites form a cycle: {}",
+                cycle.join(" -> ")
+            ));
+        }
+        PrerequisiteVisit::Pending => {}
+    }
+
+    visit_states[index] = PrerequisiteVisit::InProgress;
+    path.push(index);
+    for prerequisite in &features[index].configuration.prerequisites {
+        // Prerequisites on features missing from the config are evaluated as not bucketed
+        if let Some(&prerequisite_index) = index_by_id.get(prerequisite._feature.as_str()) {
+            visit_feature_prerequisites(
+                prerequisite_index,
+                features,
+                index_by_id,
+                visit_states,
+                path,
+                order,
+            )?;
+        }
     }
+    path.pop();
+    visit_states[index] = PrerequisiteVisit::Done;
+    order.push(index);
+    Ok(())
 }
@@ -0,0 +1,420 @@
+use crate::config::{ConfigBody, FullConfig, Variable};
+use crate::feature::{ConfigFeature, Variation};
+use crate::target::Target;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// A value that differs between two configs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueChange<T> {
+    pub previous: T,
+    pub current: T,
+}
+
+impl<T: PartialEq> ValueChange<T> {
+    fn between(previous: T, current: T) -> Option<Self> {
+        (previous != current).then_some(ValueChange { previous, current })
+    }
+}
+
+/// Typed diff between two versions of a config, see `diff_config_bodies` and
+/// `diff_full_configs`. Entries are sorted by key or id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDiff {
+    pub features_added: Vec<String>,
+    pub features_removed: Vec<String>,
+    pub features_changed: Vec<FeatureDiff>,
+    pub variables_added: Vec<VariableSummary>,
+    pub variables_removed: Vec<VariableSummary>,
+    pub variables_retyped: Vec<VariableTypeChange>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.features_added.is_empty()
+            && self.features_removed.is_empty()
+            && self.features_changed.is_empty()
+            && self.variables_added.is_empty()
+            && self.variables_removed.is_empty()
+            && self.variables_retyped.is_empty()
+    }
+}
+
+/// Changes to a feature present in both configs, identified by its key. Variations and
+/// targets are matched by id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureDiff {
+    pub key: String,
+    pub variations_added: Vec<String>,
+    pub variations_removed: Vec<String>,
+    pub variations_changed: Vec<VariationDiff>,
+    pub targets_added: Vec<String>,
+    pub targets_removed: Vec<String>,
+    pub targets_changed: Vec<TargetDiff>,
+}
+
+impl FeatureDiff {
+    fn is_empty(&self) -> bool {
+        self.variations_added.is_empty()
+            && self.variations_removed.is_empty()
+            && self.variations_changed.is_empty()
+            && self.targets_added.is_empty()
+            && self.targets_removed.is_empty()
+            && self.targets_changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationDiff {
+    pub id: String,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_changed: Option<ValueChange<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_changed: Option<ValueChange<String>>,
+    pub variable_values: Vec<VariationValueChange>,
+}
+
+/// A variable value served by a variation, `None` when the variation does not set it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationValueChange {
+    pub variable_key: String,
+    pub previous: Option<Value>,
+    pub current: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetDiff {
+    pub id: String,
+    /// Previous and current audience, as found in the config JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<ValueChange<Value>>,
+    pub distribution: Vec<DistributionChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<ValueChange<Option<Value>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucketing_key: Option<ValueChange<String>>,
+}
+
+/// A variation whose share of the target moved, 0 when it was not or is no longer served.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionChange {
+    pub variation: String,
+    pub variation_key: String,
+    pub previous: f64,
+    pub current: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableSummary {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub _type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableTypeChange {
+    pub key: String,
+    pub previous: String,
+    pub current: String,
+}
+
+pub fn diff_config_bodies(previous: &ConfigBody, current: &ConfigBody) -> ConfigDiff {
+    diff_features_and_variables(
+        (&previous.features, &previous.variables),
+        (&current.features, &current.variables),
+    )
+}
+
+pub fn diff_full_configs(previous: &FullConfig, current: &FullConfig) -> ConfigDiff {
+    diff_features_and_variables(
+        (&previous.features, &previous.variables),
+        (&current.features, &current.variables),
+    )
+}
+
+// Everything in `current` is reported as added when there is no previous config
+pub(crate) fn diff_config_change(
+    previous: Option<&ConfigBody>,
+    current: &ConfigBody,
+) -> ConfigDiff {
+    match previous {
+        Some(previous) => diff_config_bodies(previous, current),
+        None => diff_features_and_variables((&[], &[]), (&current.features, &current.variables)),
+    }
+}
+
+fn diff_features_and_variables(
+    previous: (&[ConfigFeature], &[Variable]),
+    current: (&[ConfigFeature], &[Variable]),
+) -> ConfigDiff {
+    let (previous_features, previous_variables) = previous;
+    let (current_features, current_variables) = current;
+    let mut diff = ConfigDiff::default();
+
+    // Variation values are reported by variable key, looked up in either config
+    let variable_keys: HashMap<&str, &str> = previous_variables
+        .iter()
+        .chain(current_variables)
+        .map(|variable| (variable._id.as_str(), variable.key.as_str()))
+        .collect();
+
+    let previous_features: BTreeMap<&str, &ConfigFeature> = previous_features
+        .iter()
+        .map(|feature| (feature.key.as_str(), feature))
+        .collect();
+    let current_features: BTreeMap<&str, &ConfigFeature> = current_features
+        .iter()
+        .map(|feature| (feature.key.as_str(), feature))
+        .collect();
+    for (key, current) in &current_features {
+        match previous_features.get(key) {
+            None => diff.features_added.push(key.to_string()),
+            Some(previous) => {
+                let feature_diff = diff_feature(previous, current, &variable_keys);
+                if !feature_diff.is_empty() {
+                    diff.features_changed.push(feature_diff);
+                }
+            }
+        }
+    }
+    diff.features_removed = previous_features
+        .keys()
+        .filter(|key| !current_features.contains_key(*key))
+        .map(|key| key.to_string())
+        .collect();
+
+    let previous_variables: BTreeMap<&str, &Variable> = previous_variables
+        .iter()
+        .map(|variable| (variable.key.as_str(), variable))
+        .collect();
+    let current_variables: BTreeMap<&str, &Variable> = current_variables
+        .iter()
+        .map(|variable| (variable.key.as_str(), variable))
+        .collect();
+    for (key, current) in &current_variables {
+        match previous_variables.get(key) {
+            None => diff.variables_added.push(VariableSummary {
+                key: key.to_string(),
+                _type: current._type.clone(),
+            }),
+            Some(previous) if previous._type != current._type => {
+                diff.variables_retyped.push(VariableTypeChange {
+                    key: key.to_string(),
+                    previous: previous._type.clone(),
+                    current: current._type.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    diff.variables_removed = previous_variables
+        .iter()
+        .filter(|(key, _)| !current_variables.contains_key(*key))
+        .map(|(key, variable)| VariableSummary {
+            key: key.to_string(),
+            _type: variable._type.clone(),
+        })
+        .collect();
+
+    diff
+}
+
+fn diff_feature(
+    previous: &ConfigFeature,
+    current: &ConfigFeature,
+    variable_keys: &HashMap<&str, &str>,
+) -> FeatureDiff {
+    let mut diff = FeatureDiff {
+        key: current.key.clone(),
+        ..Default::default()
+    };
+
+    let previous_variations: BTreeMap<&str, &Variation> = previous
+        .variations
+        .iter()
+        .map(|variation| (variation._id.as_str(), variation))
+        .collect();
+    let current_variations: BTreeMap<&str, &Variation> = current
+        .variations
+        .iter()
+        .map(|variation| (variation._id.as_str(), variation))
+        .collect();
+    for (id, current) in &current_variations {
+        match previous_variations.get(id) {
+            None => diff.variations_added.push(current.key.clone()),
+            Some(previous) => {
+                if let Some(variation_diff) = diff_variation(previous, current, variable_keys) {
+                    diff.variations_changed.push(variation_diff);
+                }
+            }
+        }
+    }
+    diff.variations_removed = previous_variations
+        .iter()
+        .filter(|(id, _)| !current_variations.contains_key(*id))
+        .map(|(_, variation)| variation.key.clone())
+        .collect();
+
+    // Distributions name variations by id, resolved to a key in either version of the feature
+    let variation_keys: HashMap<&str, &str> = previous_variations
+        .iter()
+        .chain(current_variations.iter())
+        .map(|(id, variation)| (*id, variation.key.as_str()))
+        .collect();
+    let previous_targets: BTreeMap<&str, &Target> = previous
+        .configuration
+        .targets
+        .iter()
+        .map(|target| (target._id.as_str(), target))
+        .collect();
+    let current_targets: BTreeMap<&str, &Target> = current
+        .configuration
+        .targets
+        .iter()
+        .map(|target| (target._id.as_str(), target))
+        .collect();
+    for (id, current) in &current_targets {
+        match previous_targets.get(id) {
+            None => diff.targets_added.push(id.to_string()),
+            Some(previous) => {
+                if let Some(target_diff) = diff_target(previous, current, &variation_keys) {
+                    diff.targets_changed.push(target_diff);
+                }
+            }
+        }
+    }
+    diff.targets_removed = previous_targets
+        .keys()
+        .filter(|id| !current_targets.contains_key(*id))
+        .map(|id| id.to_string())
+        .collect();
+
+    diff
+}
+
+fn diff_variation(
+    previous: &Variation,
+    current: &Variation,
+    variable_keys: &HashMap<&str, &str>,
+) -> Option<VariationDiff> {
+    let previous_values: BTreeMap<&str, &Value> = previous
+        .variables
+        .iter()
+        .map(|variable| (variable._var.as_str(), &variable.value))
+        .collect();
+    let current_values: BTreeMap<&str, &Value> = current
+        .variables
+        .iter()
+        .map(|variable| (variable._var.as_str(), &variable.value))
+        .collect();
+    let mut variable_ids: Vec<&str> = previous_values
+        .keys()
+        .chain(current_values.keys())
+        .copied()
+        .collect();
+    variable_ids.sort_unstable();
+    variable_ids.dedup();
+
+    let variable_values: Vec<VariationValueChange> = variable_ids
+        .into_iter()
+        .filter_map(|id| {
+            let previous = previous_values.get(id).map(|value| (*value).clone());
+            let current = current_values.get(id).map(|value| (*value).clone());
+            (previous != current).then(|| VariationValueChange {
+                variable_key: variable_keys.get(id).unwrap_or(&id).to_string(),
+                previous,
+                current,
+            })
+        })
+        .collect();
+
+    let diff = VariationDiff {
+        id: current._id.clone(),
+        key: current.key.clone(),
+        key_changed: ValueChange::between(previous.key.clone(), current.key.clone()),
+        name_changed: ValueChange::between(previous.name.clone(), current.name.clone()),
+        variable_values,
+    };
+    let changed = diff.key_changed.is_some()
+        || diff.name_changed.is_some()
+        || !diff.variable_values.is_empty();
+    changed.then_some(diff)
+}
+
+fn diff_target(
+    previous: &Target,
+    current: &Target,
+    variation_keys: &HashMap<&str, &str>,
+) -> Option<TargetDiff> {
+    let percentages = |target: &Target| {
+        let mut percentages: BTreeMap<String, f64> = BTreeMap::new();
+        for distribution in &target.distribution {
+            *percentages
+                .entry(distribution.variation.clone())
+                .or_default() += distribution.percentage;
+        }
+        percentages
+    };
+    let previous_percentages = percentages(previous);
+    let current_percentages = percentages(current);
+    let mut variations: Vec<&String> = previous_percentages
+        .keys()
+        .chain(current_percentages.keys())
+        .collect();
+    variations.sort_unstable();
+    variations.dedup();
+    let distribution: Vec<DistributionChange> = variations
+        .into_iter()
+        .filter_map(|variation| {
+            let previous = previous_percentages.get(variation).copied().unwrap_or(0.0);
+            let current = current_percentages.get(variation).copied().unwrap_or(0.0);
+            (previous != current).then(|| DistributionChange {
+                variation: variation.clone(),
+                variation_key: variation_keys
+                    .get(variation.as_str())
+                    .map(|key| key.to_string())
+                    .unwrap_or_default(),
+                previous,
+                current,
+            })
+        })
+        .collect();
+
+    let diff = TargetDiff {
+        id: current._id.clone(),
+        audience: ValueChange::between(
+            serde_json::to_value(&previous.audience).unwrap_or_default(),
+            serde_json::to_value(&current.audience).unwrap_or_default(),
+        ),
+        distribution,
+        rollout: ValueChange::between(
+            previous
+                .rollout
+                .as_ref()
+                .and_then(|rollout| serde_json::to_value(rollout).ok()),
+            current
+                .rollout
+                .as_ref()
+                .and_then(|rollout| serde_json::to_value(rollout).ok()),
+        ),
+        bucketing_key: ValueChange::between(
+            previous.bucketingkey.clone(),
+            current.bucketingkey.clone(),
+        ),
+    };
+    let changed = diff.audience.is_some()
+        || !diff.distribution.is_empty()
+        || diff.rollout.is_some()
+        || diff.bucketing_key.is_some();
+    changed.then_some(diff)
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::config_diff::*;
+    use crate::util::test_fixtures::{
+        TEST_FEATURE_KEY, TEST_TARGET_ID, VARIATION_OFF, VARIATION_ON, config_body, full_config,
+        test_config_json,
+    };
+    use serde_json::{Value, json};
+
+    // A second feature, a variation value and name edit, a new variation, a narrower
+    // audience, a 80/20 split with a rollout and variables added, removed and retyped
+    fn changed_config_json() -> Value {
+        let mut json = test_config_json();
+        let mut new_feature = json["features"][0].clone();
+        new_feature["_id"] = json!("new-feature-id");
+        new_feature["key"] = json!("new-feature");
+        new_feature["configuration"]["targets"][0]["_id"] = json!("new-target-id");
+
+        let feature = &mut json["features"][0];
+        feature["variations"][0]["name"] = json!("Variation On Renamed");
+        feature["variations"][0]["variables"][3]["value"] = json!("enabled");
+        feature["variations"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"_id": "new-variation-id", "key": "variation-new", "name": "New", "variables": []}));
+        let target = &mut feature["configuration"]["targets"][0];
+        target["_audience"] = json!({
+            "filters": {
+                "operator": "and",
+                "filters": [{"type": "user", "subType": "country", "comparator": "=", "values": ["CA"]}]
+            }
+        });
+        target["distribution"] = json!([
+            {"_variation": VARIATION_ON, "percentage": 0.8},
+            {"_variation": VARIATION_OFF, "percentage": 0.2}
+        ]);
+        target["rollout"] = json!({
+            "type": "gradual",
+            "startPercentage": 0.1,
+            "startDate": "2026-10-01T00:00:00Z",
+            "stages": [{"type": "linear", "date": "2026-10-15T00:00:00Z", "percentage": 1.0}]
+        });
+        json["features"].as_array_mut().unwrap().push(new_feature);
+
+        let variables = json["variables"].as_array_mut().unwrap();
+        variables[4]["type"] = json!("String");
+        variables.remove(2);
+        variables.push(json!({"_id": "new-variable-id", "type": "Boolean", "key": "new-variable"}));
+        json
+    }
+
+    #[test]
+    fn test_identical_configs_have_an_empty_diff() {
+        let diff = diff_full_configs(
+            &full_config(test_config_json()),
+            &full_config(test_config_json()),
+        );
+        assert!(diff.is_empty());
+        assert_eq!(diff, ConfigDiff::default());
+    }
+
+    #[test]
+    fn test_diff_reports_feature_and_variable_changes() {
+        let diff = diff_full_configs(
+            &full_config(test_config_json()),
+            &full_config(changed_config_json()),
+        );
+
+        assert_eq!(diff.features_added, vec!["new-feature".to_string()]);
+        assert!(diff.features_removed.is_empty());
+        assert_eq!(
+            diff.variables_added,
+            vec![VariableSummary {
+                key: "new-variable".to_string(),
+                _type: "Boolean".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.variables_removed,
+            vec![VariableSummary {
+                key: "test-float-variable".to_string(),
+                _type: "Number".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.variables_retyped,
+            vec![VariableTypeChange {
+                key: "test-json-variable".to_string(),
+                previous: "JSON".to_string(),
+                current: "String".to_string(),
+            }]
+        );
+
+        assert_eq!(diff.features_changed.len(), 1);
+        let feature = &diff.features_changed[0];
+        assert_eq!(feature.key, "test");
+        assert_eq!(feature.variations_added, vec!["variation-new".to_string()]);
+        assert!(feature.variations_removed.is_empty());
+        assert_eq!(
+            feature.variations_changed,
+            vec![VariationDiff {
+                id: VARIATION_ON.to_string(),
+                key: "variation-on".to_string(),
+                key_changed: None,
+                name_changed: Some(ValueChange {
+                    previous: "Variation On".to_string(),
+                    current: "Variation On Renamed".to_string(),
+                }),
+                variable_values: vec![VariationValueChange {
+                    variable_key: "test-string-variable".to_string(),
+                    previous: Some(json!("on")),
+                    current: Some(json!("enabled")),
+                }],
+            }]
+        );
+
+        assert!(feature.targets_added.is_empty());
+        assert_eq!(feature.targets_changed.len(), 1);
+        let target = &feature.targets_changed[0];
+        assert_eq!(target.id, TEST_TARGET_ID);
+        assert!(target.audience.is_some());
+        assert!(target.bucketing_key.is_none());
+        assert_eq!(
+            target.distribution,
+            vec![
+                DistributionChange {
+                    variation: VARIATION_ON.to_string(),
+                    variation_key: "variation-on".to_string(),
+                    previous: 0.5,
+                    current: 0.8,
+                },
+                DistributionChange {
+                    variation: VARIATION_OFF.to_string(),
+                    variation_key: "variation-off".to_string(),
+                    previous: 0.5,
+                    current: 0.2,
+                },
+            ]
+        );
+        let rollout = target.rollout.as_ref().unwrap();
+        assert_eq!(rollout.previous, None);
+        assert_eq!(rollout.current.as_ref().unwrap()["type"], "gradual");
+    }
+
+    #[test]
+    fn test_removed_features_and_targets() {
+        let mut json = test_config_json();
+        let mut second_target = json["features"][0]["configuration"]["targets"][0].clone();
+        second_target["_id"] = json!("second-target-id");
+        json["features"][0]["configuration"]["targets"]
+            .as_array_mut()
+            .unwrap()
+            .push(second_target);
+        let previous = full_config(json);
+
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["_id"] = json!("replacement-target-id");
+        let diff = diff_full_configs(&previous, &full_config(json));
+        let feature = &diff.features_changed[0];
+        assert_eq!(feature.targets_added, vec!["replacement-target-id"]);
+        assert_eq!(
+            feature.targets_removed,
+            vec![TEST_TARGET_ID.to_string(), "second-target-id".to_string()]
+        );
+
+        let mut json = test_config_json();
+        json["features"] = json!([]);
+        let diff = diff_full_configs(&previous, &full_config(json));
+        assert_eq!(diff.features_removed, vec!["test".to_string()]);
+        assert!(diff.features_changed.is_empty());
+    }
+
+    #[test]
+    fn test_config_body_diff_serializes_to_json() {
+        let diff = diff_config_bodies(
+            &config_body(test_config_json()),
+            &config_body(changed_config_json()),
+        );
+        // Compiling a config body sorts distributions, the diff does not depend on their order
+        assert_eq!(
+            diff,
+            diff_full_configs(
+                &full_config(test_config_json()),
+                &full_config(changed_config_json())
+            )
+        );
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["featuresAdded"], json!(["new-feature"]));
+        assert_eq!(
+            json["variablesRetyped"],
+            json!([{"key": "test-json-variable", "previous": "JSON", "current": "String"}])
+        );
+        let target = &json["featuresChanged"][0]["targetsChanged"][0];
+        assert_eq!(target["distribution"][0]["variationKey"], "variation-on");
+        assert!(target.get("bucketingKey").is_none());
+        let variation = &json["featuresChanged"][0]["variationsChanged"][0];
+        assert!(variation.get("keyChanged").is_none());
+        assert_eq!(variation["nameChanged"]["current"], "Variation On Renamed");
+    }
+
+    #[test]
+    fn test_diff_without_previous_config_adds_everything() {
+        let config = config_body(test_config_json());
+
+        let diff = diff_config_change(None, &config);
+        assert_eq!(diff.features_added, vec![TEST_FEATURE_KEY.to_string()]);
+        assert_eq!(diff.variables_added.len(), config.variables.len());
+        assert!(diff.features_changed.is_empty());
+
+        assert!(diff_config_change(Some(&config), &config_body(test_config_json())).is_empty());
+    }
+}
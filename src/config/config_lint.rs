@@ -0,0 +1,487 @@
+use crate::bucketing::rollout_timeline::rollout_problems;
+use crate::config::{ConfigBody, FullConfig, Variable};
+use crate::constants;
+use crate::feature::ConfigFeature;
+use crate::filters::{AudienceOperator, Filter, NoIdAudience};
+use crate::target::{Rollout, Target};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Distributions are percentages stored as floats, allow for rounding in the config
+const DISTRIBUTION_TOLERANCE: f64 = 1e-6;
+
+const KNOWN_SUB_TYPES: &[&str] = &[
+    constants::SUB_TYPE_USER_ID,
+    constants::SUB_TYPE_EMAIL,
+    constants::SUB_TYPE_IP,
+    constants::SUB_TYPE_COUNTRY,
+    constants::SUB_TYPE_PLATFORM,
+    constants::SUB_TYPE_PLATFORM_VERSION,
+    constants::SUB_TYPE_APP_VERSION,
+    constants::SUB_TYPE_DEVICE_MODEL,
+    constants::SUB_TYPE_CUSTOM_DATA,
+];
+
+const KNOWN_COMPARATORS: &[&str] = &[
+    constants::COMPARATOR_EQUAL,
+    constants::COMPARATOR_NOT_EQUAL,
+    constants::COMPARATOR_GREATER,
+    constants::COMPARATOR_GREATER_EQUAL,
+    constants::COMPARATOR_LESS,
+    constants::COMPARATOR_LESS_EQUAL,
+    constants::COMPARATOR_EXIST,
+    constants::COMPARATOR_NOT_EXIST,
+    constants::COMPARATOR_CONTAIN,
+    constants::COMPARATOR_NOT_CONTAIN,
+    constants::COMPARATOR_START_WITH,
+    constants::COMPARATOR_NOT_START_WITH,
+    constants::COMPARATOR_END_WITH,
+    constants::COMPARATOR_NOT_END_WITH,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConfigLintRule {
+    InvalidAudience,
+    DistributionSum,
+    UnknownVariation,
+    MissingAudience,
+    AudienceCycle,
+    UnknownVariable,
+    InvalidVariableType,
+    VariableTypeMismatch,
+    OrphanVariable,
+    UnknownFilterType,
+    UnknownSubType,
+    UnknownComparator,
+    UnknownOperator,
+    MalformedRollout,
+}
+
+impl ConfigLintRule {
+    /// Stable identifier of the rule, the same string the rule serializes to.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigLintRule::InvalidAudience => "INVALID_AUDIENCE",
+            ConfigLintRule::DistributionSum => "DISTRIBUTION_SUM",
+            ConfigLintRule::UnknownVariation => "UNKNOWN_VARIATION",
+            ConfigLintRule::MissingAudience => "MISSING_AUDIENCE",
+            ConfigLintRule::AudienceCycle => "AUDIENCE_CYCLE",
+            ConfigLintRule::UnknownVariable => "UNKNOWN_VARIABLE",
+            ConfigLintRule::InvalidVariableType => "INVALID_VARIABLE_TYPE",
+            ConfigLintRule::VariableTypeMismatch => "VARIABLE_TYPE_MISMATCH",
+            ConfigLintRule::OrphanVariable => "ORPHAN_VARIABLE",
+            ConfigLintRule::UnknownFilterType => "UNKNOWN_FILTER_TYPE",
+            ConfigLintRule::UnknownSubType => "UNKNOWN_SUB_TYPE",
+            ConfigLintRule::UnknownComparator => "UNKNOWN_COMPARATOR",
+            ConfigLintRule::UnknownOperator => "UNKNOWN_OPERATOR",
+            ConfigLintRule::MalformedRollout => "MALFORMED_ROLLOUT",
+        }
+    }
+
+    /// Warnings point at unused or suspicious parts of the config that do not change how
+    /// users are bucketed, everything else is an error.
+    pub fn severity(&self) -> ConfigLintSeverity {
+        match self {
+            ConfigLintRule::OrphanVariable => ConfigLintSeverity::Warning,
+            _ => ConfigLintSeverity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLintSeverity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a config. `path` points at the offending entry, with features
+/// identified by key and targets, variations, variables and audiences by id, e.g.
+/// `features[my-feature].targets[61f...].distribution`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigLintFinding {
+    pub rule: ConfigLintRule,
+    pub severity: ConfigLintSeverity,
+    pub path: String,
+    pub message: String,
+}
+
+impl ConfigLintFinding {
+    fn new(rule: ConfigLintRule, path: String, message: String) -> Self {
+        ConfigLintFinding {
+            rule,
+            severity: rule.severity(),
+            path,
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == ConfigLintSeverity::Error
+    }
+}
+
+pub fn lint_config(config: &ConfigBody) -> Vec<ConfigLintFinding> {
+    lint(
+        &config.features,
+        &config.variables,
+        &config.audiences,
+        Vec::new(),
+    )
+}
+
+// Unlike `ConfigBody::from_full_config`, audiences that fail to parse are reported as
+// findings instead of failing the whole lint
+pub fn lint_full_config(config: &FullConfig) -> Vec<ConfigLintFinding> {
+    let mut findings = Vec::new();
+    let mut audiences: HashMap<String, NoIdAudience> = HashMap::new();
+    for (id, value) in &config.audiences {
+        match serde_json::from_value::<NoIdAudience>(value.clone()) {
+            Ok(audience) => {
+                audiences.insert(id.clone(), audience);
+            }
+            Err(e) => findings.push(ConfigLintFinding::new(
+                ConfigLintRule::InvalidAudience,
+                format!("audiences[{}]", id),
+                format!("audience could not be parsed: {}", e),
+            )),
+        }
+    }
+    lint(&config.features, &config.variables, &audiences, findings)
+}
+
+fn lint(
+    features: &[ConfigFeature],
+    variables: &[Variable],
+    audiences: &HashMap<String, NoIdAudience>,
+    mut findings: Vec<ConfigLintFinding>,
+) -> Vec<ConfigLintFinding> {
+    let variables_by_id: HashMap<&str, &Variable> = variables
+        .iter()
+        .map(|variable| (variable._id.as_str(), variable))
+        .collect();
+
+    // Sorted so findings come out in the same order on every run
+    let audiences: BTreeMap<&String, &NoIdAudience> = audiences.iter().collect();
+    for (id, audience) in &audiences {
+        lint_audience_operator(
+            &audience.filters,
+            &format!("audiences[{}].filters", id),
+            &audiences,
+            &mut findings,
+        );
+    }
+    lint_audience_cycles(&audiences, &mut findings);
+
+    let mut used_variables: HashSet<&str> = HashSet::new();
+    for feature in features {
+        let path = format!("features[{}]", feature.key);
+        let variation_ids: HashSet<&str> = feature
+            .variations
+            .iter()
+            .map(|variation| variation._id.as_str())
+            .collect();
+
+        for variation in &feature.variations {
+            for variation_variable in &variation.variables {
+                used_variables.insert(variation_variable._var.as_str());
+                let variable_path = format!(
+                    "{}.variations[{}].variables[{}]",
+                    path, variation._id, variation_variable._var
+                );
+                match variables_by_id.get(variation_variable._var.as_str()) {
+                    Some(variable) => lint_variable_value(
+                        variable,
+                        &variation_variable.value,
+                        variable_path,
+                        &mut findings,
+                    ),
+                    None => findings.push(ConfigLintFinding::new(
+                        ConfigLintRule::UnknownVariable,
+                        variable_path,
+                        format!(
+                            "variation {} sets variable {} which is not in the config",
+                            variation.key, variation_variable._var
+                        ),
+                    )),
+                }
+            }
+        }
+
+        for target in &feature.configuration.targets {
+            let target_path = format!("{}.targets[{}]", path, target._id);
+            lint_distribution(target, &variation_ids, &target_path, &mut findings);
+            lint_audience_operator(
+                &target.audience.filters,
+                &format!("{}.audience.filters", target_path),
+                &audiences,
+                &mut findings,
+            );
+            if let Some(rollout) = &target.rollout {
+                lint_rollout(rollout, &format!("{}.rollout", target_path), &mut findings);
+            }
+        }
+    }
+
+    for variable in variables {
+        if !used_variables.contains(variable._id.as_str()) {
+            findings.push(ConfigLintFinding::new(
+                ConfigLintRule::OrphanVariable,
+                format!("variables[{}]", variable._id),
+                format!(
+                    "variable {} is not set by any feature variation",
+                    variable.key
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+fn lint_distribution(
+    target: &Target,
+    variation_ids: &HashSet<&str>,
+    path: &str,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    let path = format!("{}.distribution", path);
+    let total: f64 = target
+        .distribution
+        .iter()
+        .map(|distribution| distribution.percentage)
+        .sum();
+    if (total - 1.0).abs() > DISTRIBUTION_TOLERANCE {
+        findings.push(ConfigLintFinding::new(
+            ConfigLintRule::DistributionSum,
+            path.clone(),
+            format!("distribution percentages sum to {} instead of 1", total),
+        ));
+    }
+    for distribution in &target.distribution {
+        if !variation_ids.contains(distribution.variation.as_str()) {
+            findings.push(ConfigLintFinding::new(
+                ConfigLintRule::UnknownVariation,
+                path.clone(),
+                format!(
+                    "distribution references variation {} which is not in the feature",
+                    distribution.variation
+                ),
+            ));
+        }
+    }
+}
+
+fn lint_variable_value(
+    variable: &Variable,
+    value: &Value,
+    path: String,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    let matches = match variable._type.as_str() {
+        constants::VARIABLE_TYPES_BOOL => value.is_boolean(),
+        constants::VARIABLE_TYPES_NUMBER => value.is_number(),
+        constants::VARIABLE_TYPES_STRING => value.is_string(),
+        constants::VARIABLE_TYPES_JSON => value.is_object() || value.is_array(),
+        _ => {
+            findings.push(ConfigLintFinding::new(
+                ConfigLintRule::InvalidVariableType,
+                path,
+                format!(
+                    "variable {} has unknown type {}",
+                    variable.key, variable._type
+                ),
+            ));
+            return;
+        }
+    };
+    if !matches {
+        findings.push(ConfigLintFinding::new(
+            ConfigLintRule::VariableTypeMismatch,
+            path,
+            format!(
+                "value {} does not match the {} type of variable {}",
+                value, variable._type, variable.key
+            ),
+        ));
+    }
+}
+
+fn lint_audience_operator(
+    operator: &AudienceOperator,
+    path: &str,
+    audiences: &BTreeMap<&String, &NoIdAudience>,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    lint_operator(Some(&operator.operator), path, findings);
+    for (index, filter) in operator.filters.iter().enumerate() {
+        lint_filter(filter, &format!("{}[{}]", path, index), audiences, findings);
+    }
+}
+
+fn lint_operator(operator: Option<&String>, path: &str, findings: &mut Vec<ConfigLintFinding>) {
+    if let Some(operator) = operator
+        && operator != constants::OPERATOR_AND
+        && operator != constants::OPERATOR_OR
+    {
+        findings.push(ConfigLintFinding::new(
+            ConfigLintRule::UnknownOperator,
+            path.to_string(),
+            format!("unknown operator {}", operator),
+        ));
+    }
+}
+
+fn lint_filter(
+    filter: &Filter,
+    path: &str,
+    audiences: &BTreeMap<&String, &NoIdAudience>,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    let mut push = |rule: ConfigLintRule, message: String| {
+        findings.push(ConfigLintFinding::new(rule, path.to_string(), message))
+    };
+    match filter._type.as_str() {
+        constants::TYPE_ALL | constants::TYPE_OPT_IN => {}
+        constants::TYPE_USER => {
+            match filter.sub_type.as_deref() {
+                Some(sub_type) if KNOWN_SUB_TYPES.contains(&sub_type) => {}
+                Some(sub_type) => push(
+                    ConfigLintRule::UnknownSubType,
+                    format!("unknown user filter subType {}", sub_type),
+                ),
+                None => push(
+                    ConfigLintRule::UnknownSubType,
+                    "user filter has no subType".to_string(),
+                ),
+            }
+            if let Some(comparator) = filter.comparator.as_deref()
+                && !KNOWN_COMPARATORS.contains(&comparator)
+            {
+                push(
+                    ConfigLintRule::UnknownComparator,
+                    format!("unknown comparator {}", comparator),
+                );
+            }
+        }
+        constants::TYPE_AUDIENCE_MATCH => {
+            if let Some(comparator) = filter.comparator.as_deref()
+                && comparator != constants::COMPARATOR_EQUAL
+                && comparator != constants::COMPARATOR_NOT_EQUAL
+            {
+                push(
+                    ConfigLintRule::UnknownComparator,
+                    format!(
+                        "audienceMatch filters only support = and !=, not {}",
+                        comparator
+                    ),
+                );
+            }
+            for audience_id in &filter._audiences {
+                if !audiences.contains_key(audience_id) {
+                    push(
+                        ConfigLintRule::MissingAudience,
+                        format!("audienceMatch references missing audience {}", audience_id),
+                    );
+                }
+            }
+        }
+        // Filters without a type group nested filters
+        "" if !filter.filters.is_empty() => {}
+        "" => push(
+            ConfigLintRule::UnknownFilterType,
+            "filter has no type".to_string(),
+        ),
+        other => push(
+            ConfigLintRule::UnknownFilterType,
+            format!("unknown filter type {}", other),
+        ),
+    }
+
+    lint_operator(filter.operator.as_ref(), path, findings);
+    for (index, nested) in filter.filters.iter().enumerate() {
+        lint_filter(
+            nested,
+            &format!("{}.filters[{}]", path, index),
+            audiences,
+            findings,
+        );
+    }
+}
+
+fn audience_references(filters: &[Filter], references: &mut Vec<String>) {
+    for filter in filters {
+        if filter._type == constants::TYPE_AUDIENCE_MATCH {
+            references.extend(filter._audiences.iter().cloned());
+        }
+        audience_references(&filter.filters, references);
+    }
+}
+
+// Evaluating an audience that matches itself, directly or through other audiences, never
+// terminates. Every cycle is reported once, on the audience it was first reached from.
+fn lint_audience_cycles(
+    audiences: &BTreeMap<&String, &NoIdAudience>,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    let references: BTreeMap<&str, Vec<String>> = audiences
+        .iter()
+        .map(|(id, audience)| {
+            let mut references = Vec::new();
+            audience_references(&audience.filters.filters, &mut references);
+            (id.as_str(), references)
+        })
+        .collect();
+
+    let mut finished: HashSet<&str> = HashSet::new();
+    for id in references.keys() {
+        let mut path: Vec<&str> = Vec::new();
+        visit_audience(id, &references, &mut path, &mut finished, findings);
+    }
+}
+
+fn visit_audience<'a>(
+    id: &'a str,
+    references: &'a BTreeMap<&str, Vec<String>>,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+    findings: &mut Vec<ConfigLintFinding>,
+) {
+    if finished.contains(id) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|visited| *visited == id) {
+        let mut cycle: Vec<&str> = path[start..].to_vec();
+        cycle.push(id);
+        findings.push(ConfigLintFinding::new(
+            ConfigLintRule::AudienceCycle,
+            format!("audiences[{}]", path[start]),
+            format!("audienceMatch filters form a cycle: {}", cycle.join(" -> ")),
+        ));
+        return;
+    }
+    // Missing audiences are reported by the filter lint
+    let Some(referenced) = references.get(id) else {
+        return;
+    };
+    path.push(id);
+    for next in referenced {
+        if let Some((next, _)) = references.get_key_value(next.as_str()) {
+            visit_audience(next, references, path, finished, findings);
+        }
+    }
+    path.pop();
+    finished.insert(id);
+}
+
+pub(crate) fn lint_rollout(rollout: &Rollout, path: &str, findings: &mut Vec<ConfigLintFinding>) {
+    for problem in rollout_problems(rollout) {
+        findings.push(ConfigLintFinding::new(
+            ConfigLintRule::MalformedRollout,
+            path.to_string(),
+            problem,
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::config_lint::*;
+    use crate::errors::DevCycleError;
+    use crate::util::test_fixtures::{VARIATION_ON, config_body, full_config, test_config_json};
+    use serde_json::{Value, json};
+
+    fn rules(findings: &[ConfigLintFinding]) -> Vec<ConfigLintRule> {
+        findings.iter().map(|finding| finding.rule).collect()
+    }
+
+    fn with_target_audience(filters: Value) -> Value {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["_audience"] =
+            json!({"filters": filters});
+        json
+    }
+
+    #[test]
+    fn test_valid_config_has_no_findings() {
+        assert_eq!(lint_config(&config_body(test_config_json())), vec![]);
+        assert_eq!(lint_full_config(&full_config(test_config_json())), vec![]);
+    }
+
+    #[test]
+    fn test_distribution_findings() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["distribution"] = json!([
+            {"_variation": VARIATION_ON, "percentage": 0.5},
+            {"_variation": "missing-variation", "percentage": 0.4}
+        ]);
+
+        let findings = lint_config(&config_body(json));
+        assert_eq!(
+            rules(&findings),
+            vec![
+                ConfigLintRule::DistributionSum,
+                ConfigLintRule::UnknownVariation
+            ]
+        );
+        assert_eq!(
+            findings[0].path,
+            "features[test].targets[621642332ea68943c8833c4d].distribution"
+        );
+        assert!(findings[1].message.contains("missing-variation"));
+    }
+
+    #[test]
+    fn test_variable_findings() {
+        let mut json = test_config_json();
+        json["features"][0]["variations"][0]["variables"][1]["value"] = json!("123");
+        json["features"][0]["variations"][1]["variables"][4]["value"] = json!(7);
+        json["variables"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"_id": "orphan-id", "type": "Boolean", "key": "orphan-variable"}));
+
+        let findings = lint_config(&config_body(json));
+        assert_eq!(
+            rules(&findings),
+            vec![
+                ConfigLintRule::VariableTypeMismatch,
+                ConfigLintRule::VariableTypeMismatch,
+                ConfigLintRule::OrphanVariable,
+            ]
+        );
+        assert_eq!(findings[2].severity, ConfigLintSeverity::Warning);
+        assert_eq!(findings[2].path, "variables[orphan-id]");
+    }
+
+    #[test]
+    fn test_filter_findings() {
+        let json = with_target_audience(json!({
+            "operator": "xor",
+            "filters": [
+                {"type": "user", "subType": "favouriteColour", "comparator": "=", "values": ["red"]},
+                {"type": "user", "subType": "email", "comparator": "like", "values": ["a"]},
+                {"type": "audienceMatch", "comparator": "=", "_audiences": ["missing-audience"]},
+                {"type": "geo", "values": []}
+            ]
+        }));
+
+        let findings = lint_config(&config_body(json));
+        assert_eq!(
+            rules(&findings),
+            vec![
+                ConfigLintRule::UnknownOperator,
+                ConfigLintRule::UnknownSubType,
+                ConfigLintRule::UnknownComparator,
+                ConfigLintRule::MissingAudience,
+                ConfigLintRule::UnknownFilterType,
+            ]
+        );
+        assert_eq!(
+            findings[3].path,
+            "features[test].targets[621642332ea68943c8833c4d].audience.filters[2]"
+        );
+    }
+
+    #[test]
+    fn test_audience_cycles_are_reported_once() {
+        let audience_matching = |id: &str| {
+            json!({"filters": {"operator": "and", "filters": [
+                {"type": "audienceMatch", "comparator": "=", "_audiences": [id]}
+            ]}})
+        };
+        let mut json = with_target_audience(json!({
+            "operator": "and",
+            "filters": [{"type": "audienceMatch", "comparator": "=", "_audiences": ["a"]}]
+        }));
+        json["audiences"] = json!({
+            "a": audience_matching("b"),
+            "b": audience_matching("c"),
+            "c": audience_matching("a"),
+            "d": audience_matching("d"),
+        });
+
+        let findings = lint_full_config(&full_config(json));
+        assert_eq!(
+            rules(&findings),
+            vec![ConfigLintRule::AudienceCycle, ConfigLintRule::AudienceCycle]
+        );
+        assert_eq!(findings[0].path, "audiences[a]");
+        assert!(findings[0].message.ends_with("a -> b -> c -> a"));
+        assert!(findings[1].message.ends_with("d -> d"));
+    }
+
+    #[test]
+    fn test_rollout_findings() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["rollout"] = json!({
+            "type": "gradual",
+            "startPercentage": 1.5,
+            "startDate": "2026-10-01T00:00:00Z",
+            "stages": [
+                {"type": "linear", "date": "2026-10-10T00:00:00Z", "percentage": 0.5},
+                {"type": "exponential", "date": "2026-10-05T00:00:00Z", "percentage": 1.0}
+            ]
+        });
+
+        let findings = lint_full_config(&full_config(json));
+        assert_eq!(findings.len(), 3);
+        assert!(
+            findings
+                .iter()
+                .all(|finding| finding.rule == ConfigLintRule::MalformedRollout)
+        );
+        assert!(findings[0].message.contains("start percentage"));
+        assert!(findings[1].message.contains("unknown type exponential"));
+        assert!(findings[2].message.contains("is not after"));
+
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["rollout"] =
+            json!({"type": "sometimes", "startDate": "2026-10-01T00:00:00Z"});
+        assert_eq!(
+            rules(&lint_full_config(&full_config(json.clone()))),
+            vec![ConfigLintRule::MalformedRollout]
+        );
+
+        // Only strict loading refuses a config over a malformed rollout
+        let client = DevCycleLocalBucketing::new("dvc_server_strict_malformed_rollout");
+        assert!(matches!(
+            client.set_config_strict(config_body(json.clone())),
+            Err(DevCycleError::InvalidConfig(_))
+        ));
+        assert!(client.set_config(config_body(json)).is_ok());
+    }
+
+    #[test]
+    fn test_unparsable_audience_is_a_finding() {
+        let mut json = test_config_json();
+        json["audiences"] = json!({"broken": {"filters": "not an operator"}});
+
+        let findings = lint_full_config(&full_config(json));
+        assert_eq!(rules(&findings), vec![ConfigLintRule::InvalidAudience]);
+        assert_eq!(findings[0].path, "audiences[broken]");
+    }
+
+    #[test]
+    fn test_findings_serialize_with_stable_codes() {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["distribution"] =
+            json!([{"_variation": VARIATION_ON, "percentage": 0.5}]);
+        let findings = lint_config(&config_body(json));
+
+        let serialized = serde_json::to_value(&findings).unwrap();
+        assert_eq!(serialized[0]["rule"], findings[0].rule.code());
+        assert_eq!(serialized[0]["rule"], "DISTRIBUTION_SUM");
+        assert_eq!(serialized[0]["severity"], "error");
+    }
+
+    #[test]
+    fn test_strict_set_config_rejects_errors_only() {
+        let client = DevCycleLocalBucketing::new("dvc_server_strict_config");
+        client
+            .set_config_strict(config_body(test_config_json()))
+            .unwrap();
+        let initial = client.state.get_config().unwrap();
+
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["distribution"] =
+            json!([{"_variation": VARIATION_ON, "percentage": 0.5}]);
+        match client.set_config_strict(config_body(json)) {
+            Err(err @ DevCycleError::InvalidConfig(_)) => {
+                assert_eq!(err.code(), "INVALID_CONFIG");
+                assert!(err.to_string().contains("sum to 0.5"));
+            }
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("expected the config to be rejected"),
+        }
+        assert!(std::sync::Arc::ptr_eq(
+            &initial,
+            &client.state.get_config().unwrap()
+        ));
+
+        // Orphan variables are only a warning
+        let mut json = test_config_json();
+        json["variables"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({"_id": "orphan-id", "type": "Boolean", "key": "orphan-variable"}));
+        client.set_config_strict(config_body(json)).unwrap();
+        assert!(!std::sync::Arc::ptr_eq(
+            &initial,
+            &client.state.get_config().unwrap()
+        ));
+    }
+}
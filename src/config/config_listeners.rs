@@ -0,0 +1,163 @@
+use crate::bucketing;
+use crate::config::ConfigBody;
+use crate::config::config_diff::{ConfigDiff, diff_config_change};
+use crate::segmentation::optin::OptInStore;
+use crate::user::PopulatedUser;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// Callback invoked after a client's config is replaced.
+pub type ConfigChangeListener = Arc<dyn Fn(&ConfigChangeEvent) + Send + Sync>;
+
+/// Identifies a listener registered with `on_config_change`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConfigListenerId(u64);
+
+/// A variable whose value changed for a watched user, `None` when the variable was not
+/// served to the user.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariableValueChange {
+    pub key: String,
+    pub previous: Option<Value>,
+    pub current: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserVariableChanges {
+    pub user_id: String,
+    pub changes: Vec<VariableValueChange>,
+}
+
+/// Passed to config change listeners. `diff` is taken against the previous config, and
+/// `user_variable_changes` only lists watched users with at least one changed variable value.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChangeEvent {
+    pub sdk_key: String,
+    pub diff: ConfigDiff,
+    pub user_variable_changes: Vec<UserVariableChanges>,
+}
+
+#[derive(Default)]
+pub(crate) struct ConfigListeners {
+    next_id: u64,
+    listeners: Vec<(ConfigListenerId, ConfigChangeListener)>,
+    // Users re-evaluated on every config change, keyed by user id
+    watched_users: HashMap<String, PopulatedUser>,
+}
+
+impl ConfigListeners {
+    pub(crate) fn add_listener(&mut self, listener: ConfigChangeListener) -> ConfigListenerId {
+        self.next_id += 1;
+        let id = ConfigListenerId(self.next_id);
+        self.listeners.push((id, listener));
+        id
+    }
+
+    pub(crate) fn remove_listener(&mut self, id: ConfigListenerId) -> bool {
+        let count = self.listeners.len();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.listeners.len() != count
+    }
+
+    pub(crate) fn watch_user(&mut self, user: PopulatedUser) {
+        self.watched_users.insert(user.user_id.clone(), user);
+    }
+
+    pub(crate) fn unwatch_user(&mut self, user_id: &str) -> bool {
+        self.watched_users.remove(user_id).is_some()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    pub(crate) fn listeners(&self) -> Vec<ConfigChangeListener> {
+        self.listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect()
+    }
+
+    pub(crate) fn watched_users(&self) -> Vec<PopulatedUser> {
+        self.watched_users.values().cloned().collect()
+    }
+}
+
+pub(crate) fn build_config_change_event(
+    sdk_key: &str,
+    previous: Option<&ConfigBody>,
+    current: &ConfigBody,
+    watched_users: Vec<PopulatedUser>,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> ConfigChangeEvent {
+    let mut user_variable_changes: Vec<UserVariableChanges> = watched_users
+        .into_iter()
+        .filter_map(|user| {
+            let user_id = user.user_id.clone();
+            let previous_values = previous
+                .map(|config| {
+                    variable_values(config, user.clone(), client_custom_data, opt_in_store, now)
+                })
+                .unwrap_or_default();
+            let current_values =
+                variable_values(current, user, client_custom_data, opt_in_store, now);
+            let changes = diff_variable_values(previous_values, current_values);
+            (!changes.is_empty()).then_some(UserVariableChanges { user_id, changes })
+        })
+        .collect();
+    user_variable_changes.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+
+    ConfigChangeEvent {
+        sdk_key: sdk_key.to_string(),
+        diff: diff_config_change(previous, current),
+        user_variable_changes,
+    }
+}
+
+// A user the config fails to bucket is treated as being served no variables
+fn variable_values(
+    config: &ConfigBody,
+    user: PopulatedUser,
+    client_custom_data: &HashMap<String, Value>,
+    opt_in_store: &dyn OptInStore,
+    now: DateTime<Utc>,
+) -> HashMap<String, Value> {
+    bucketing::generate_bucketed_config_from_config(
+        config,
+        user,
+        client_custom_data.clone(),
+        opt_in_store,
+        now,
+    )
+    .map(|bucketed| {
+        bucketed
+            .variables
+            .into_iter()
+            .map(|(key, variable)| (key, variable.value))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn diff_variable_values(
+    mut previous: HashMap<String, Value>,
+    mut current: HashMap<String, Value>,
+) -> Vec<VariableValueChange> {
+    let keys: BTreeSet<String> = previous.keys().chain(current.keys()).cloned().collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let previous = previous.remove(&key);
+            let current = current.remove(&key);
+            (previous != current).then_some(VariableValueChange {
+                key,
+                previous,
+                current,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::ConfigBody;
+    use crate::config::config_listeners::ConfigChangeEvent;
+    use crate::util::test_fixtures::{
+        TEST_FEATURE_KEY, TEST_TARGET_ID, VARIATION_OFF, VARIATION_ON, config_body, create_user,
+        test_config_json,
+    };
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    // The test config with its only target serving a single variation to everyone
+    fn config_serving(variation: &str) -> ConfigBody {
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["distribution"] =
+            json!([{"_variation": variation, "percentage": 1.0}]);
+        config_body(json)
+    }
+
+    fn record_events(client: &DevCycleLocalBucketing) -> Arc<Mutex<Vec<ConfigChangeEvent>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        client.on_config_change(move |event| recorded.lock().unwrap().push(event.clone()));
+        events
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn test_listeners_receive_watched_user_variable_changes() {
+        let client = DevCycleLocalBucketing::new("dvc_server_listeners");
+        let events = record_events(&client);
+        client.watch_user(create_user("watched"));
+
+        client.set_config(config_serving(VARIATION_ON)).unwrap();
+        client.set_config(config_serving(VARIATION_OFF)).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].sdk_key, "dvc_server_listeners");
+        assert_eq!(events[0].diff.features_added, keys(&[TEST_FEATURE_KEY]));
+        assert!(
+            events[0].user_variable_changes[0]
+                .changes
+                .iter()
+                .all(|change| change.previous.is_none() && change.current.is_some())
+        );
+
+        assert_eq!(
+            events[1].diff.features_changed[0].targets_changed[0].id,
+            TEST_TARGET_ID
+        );
+        assert_eq!(events[1].user_variable_changes.len(), 1);
+        let user_changes = &events[1].user_variable_changes[0];
+        assert_eq!(user_changes.user_id, "watched");
+        let values: HashMap<&str, (Value, Value)> = user_changes
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.key.as_str(),
+                    (
+                        change.previous.clone().unwrap(),
+                        change.current.clone().unwrap(),
+                    ),
+                )
+            })
+            .collect();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values["test"], (json!(true), json!(false)));
+        assert_eq!(values["test-string-variable"], (json!("on"), json!("off")));
+    }
+
+    #[test]
+    fn test_unchanged_values_and_removed_listeners_are_not_reported() {
+        let client = DevCycleLocalBucketing::new("dvc_server_listeners_removed");
+        let events = record_events(&client);
+        let other_events = Arc::new(Mutex::new(0));
+        let counter = other_events.clone();
+        let other = client.on_config_change(move |_| *counter.lock().unwrap() += 1);
+        client.watch_user(create_user("watched"));
+
+        client.set_config(config_serving(VARIATION_ON)).unwrap();
+        assert!(client.remove_config_listener(other));
+        assert!(!client.remove_config_listener(other));
+
+        // Only the target's rollout changes, the user is still served the same variation
+        let mut json = test_config_json();
+        json["features"][0]["configuration"]["targets"][0]["distribution"] =
+            json!([{"_variation": VARIATION_ON, "percentage": 1.0}]);
+        json["features"][0]["configuration"]["targets"][0]["rollout"] =
+            json!({"type": "schedule", "startDate": "2020-01-01T00:00:00.000Z"});
+        client.set_config(config_body(json)).unwrap();
+
+        assert!(client.unwatch_user("watched"));
+        client.set_config(config_serving(VARIATION_OFF)).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[1].diff.features_changed[0].targets_changed[0].id,
+            TEST_TARGET_ID
+        );
+        assert!(events[1].user_variable_changes.is_empty());
+        assert!(events[2].user_variable_changes.is_empty());
+        assert_eq!(*other_events.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::client::ClientState;
+use crate::config::config_sse;
+use crate::config::{ConfigBody, FullConfig};
+use crate::errors::{self, DevCycleError};
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+const CONFIG_PATH: &str = "/config/v2/server";
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+#[derive(Clone)]
+pub struct ConfigSourceOptions {
+    pub config_cdn_base_uri: String,
+    pub config_polling_interval: Duration,
+    pub request_timeout: Duration,
+    // Subscribe to the config's SSE endpoint and refetch when it announces a change. Polling
+    // pauses while the stream is connected.
+    pub enable_realtime_updates: bool,
+    // Change notifications received within this window trigger a single refetch
+    pub realtime_updates_debounce: Duration,
+    // Delay before the first reconnect attempt, doubled after each failed attempt
+    pub realtime_reconnect_backoff: Duration,
+    pub realtime_max_reconnect_backoff: Duration,
+    // A connected stream that receives nothing, keep-alives included, for this long is dropped
+    // and polling resumes until it reconnects
+    pub realtime_idle_timeout: Duration,
+}
+
+impl Default for ConfigSourceOptions {
+    fn default() -> Self {
+        ConfigSourceOptions {
+            config_cdn_base_uri: "https://config-cdn.devcycle.com".to_string(),
+            config_polling_interval: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(5),
+            enable_realtime_updates: false,
+            realtime_updates_debounce: Duration::from_millis(500),
+            realtime_reconnect_backoff: Duration::from_secs(1),
+            realtime_max_reconnect_backoff: Duration::from_secs(60),
+            realtime_idle_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+// Validators of the last config applied by the poller, sent back as conditional request headers
+#[derive(Default)]
+struct ConfigValidators {
+    etag: String,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+pub(crate) struct ConfigPoller {
+    pub(crate) sdk_key: String,
+    url: String,
+    pub(crate) http_client: reqwest::Client,
+    pub(crate) options: ConfigSourceOptions,
+    // Held for a whole fetch so a slower response can't replace a config fetched after it
+    validators: tokio::sync::Mutex<ConfigValidators>,
+    shutdown_tx: watch::Sender<bool>,
+    polling_task: Mutex<Option<JoinHandle<()>>>,
+    pub(crate) realtime_connected: AtomicBool,
+    // The client whose config is replaced when a new one is fetched
+    pub(crate) client: Weak<ClientState>,
+}
+
+/// Handle to a config poller started by `init_config_poller`, used to stop it or fetch
+/// the config on demand.
+#[derive(Clone)]
+pub struct ConfigPollerHandle {
+    pub(crate) poller: Arc<ConfigPoller>,
+}
+
+impl ConfigPollerHandle {
+    pub fn sdk_key(&self) -> &str {
+        &self.poller.sdk_key
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.poller.is_polling()
+    }
+
+    /// Whether the realtime updates stream is currently connected.
+    pub fn is_realtime_connected(&self) -> bool {
+        self.poller.realtime_connected.load(Ordering::Relaxed)
+    }
+
+    /// Stop polling. The last fetched config stays in use.
+    pub fn shutdown(&self) {
+        if let Some(client) = self.poller.client.upgrade() {
+            client.remove_config_poller_if_same(&self.poller);
+        }
+        self.poller.shutdown();
+    }
+
+    /// Fetch the config now, returns true when a new config was applied.
+    pub async fn fetch_config(&self) -> Result<bool, DevCycleError> {
+        self.poller.fetch_config().await
+    }
+}
+
+impl ConfigPoller {
+    pub(crate) fn new(client: &Arc<ClientState>, options: ConfigSourceOptions) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        ConfigPoller {
+            sdk_key: client.sdk_key.clone(),
+            url: format!(
+                "{}{}/{}.json",
+                options.config_cdn_base_uri.trim_end_matches('/'),
+                CONFIG_PATH,
+                client.sdk_key
+            ),
+            http_client: reqwest::Client::new(),
+            options,
+            validators: tokio::sync::Mutex::new(ConfigValidators::default()),
+            shutdown_tx,
+            polling_task: Mutex::new(None),
+            realtime_connected: AtomicBool::new(false),
+            client: Arc::downgrade(client),
+        }
+    }
+
+    // Conditionally request the config, only replacing the client's config when the CDN
+    // returns a different one
+    pub(crate) async fn fetch_config(&self) -> Result<bool, DevCycleError> {
+        let client = self.client.upgrade().ok_or(DevCycleError::ClientDropped)?;
+        let mut request = self.http_client.get(&self.url);
+        #[cfg(not(target_family = "wasm"))]
+        {
+            request = request.timeout(self.options.request_timeout);
+        }
+        let mut validators = self.validators.lock().await;
+        if !validators.etag.is_empty() {
+            request = request.header("If-None-Match", &validators.etag);
+        }
+        if let Some(last_modified) = validators.last_modified {
+            request = request.header(
+                "If-Modified-Since",
+                last_modified.format(HTTP_DATE_FORMAT).to_string(),
+            );
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| errors::config_fetch_failed("failed to fetch config", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(false);
+        }
+        if !status.is_success() {
+            return Err(DevCycleError::ConfigFetchFailed {
+                message: format!("config CDN returned {}", status),
+                source: None,
+            });
+        }
+
+        let header_value = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let etag = header_value("etag");
+        let ray_id = header_value("cf-ray");
+        let last_modified = DateTime::parse_from_rfc2822(&header_value("last-modified"))
+            .ok()
+            .map(|date| date.with_timezone(&Utc));
+
+        if !etag.is_empty() && etag == validators.etag {
+            return Ok(false);
+        }
+        // A CDN edge may still serve a config older than the one in use
+        if let (Some(current), Some(fetched)) = (validators.last_modified, last_modified)
+            && fetched < current
+        {
+            return Ok(false);
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| errors::config_fetch_failed("failed to read config response", e))?;
+        let full_config: FullConfig = serde_json::from_str(&body)?;
+        let mut config_body =
+            ConfigBody::from_full_config(full_config).map_err(|e| DevCycleError::Parse {
+                message: e,
+                source: None,
+            })?;
+        config_body.etag = etag.clone();
+        config_body.ray_id = ray_id;
+        if let Some(last_modified) = last_modified {
+            config_body.last_modified = last_modified;
+        }
+
+        *validators = ConfigValidators {
+            etag,
+            last_modified,
+        };
+        client.set_config(config_body);
+        Ok(true)
+    }
+
+    pub(crate) async fn poll(&self, mut shutdown: watch::Receiver<bool>) {
+        let period = self
+            .options
+            .config_polling_interval
+            .max(Duration::from_millis(1));
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            if *shutdown.borrow() {
+                return;
+            }
+            tokio::select! {
+                _ = shutdown.changed() => return,
+                _ = interval.tick() => {
+                    // The realtime stream announces changes while it is connected
+                    if self.realtime_connected.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    if let Err(e) = self.fetch_config().await {
+                        if matches!(e, DevCycleError::ClientDropped) {
+                            return;
+                        }
+                        eprintln!("Failed to poll config for SDK key {}: {}", self.sdk_key, e);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn start_polling(self: &Arc<Self>) {
+        // There is no multi-threaded runtime to spawn onto under WASM, the config is only
+        // fetched on demand
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let poller = self.clone();
+            let shutdown = self.shutdown_tx.subscribe();
+            let task = tokio::spawn(async move { poller.poll(shutdown).await });
+            *self
+                .polling_task
+                .lock()
+                .expect("Failed to acquire lock on polling task: lock poisoned") = Some(task);
+
+            // Stops on the same shutdown signal as the polling task
+            if self.options.enable_realtime_updates {
+                let poller = self.clone();
+                let shutdown = self.shutdown_tx.subscribe();
+                tokio::spawn(
+                    async move { config_sse::run_realtime_updates(poller, shutdown).await },
+                );
+            }
+        }
+    }
+
+    pub(crate) fn is_polling(&self) -> bool {
+        self.polling_task
+            .lock()
+            .expect("Failed to acquire lock on polling task: lock poisoned")
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.shutdown_tx.send_replace(true);
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::config_source::ConfigSourceOptions;
+    use crate::errors::DevCycleError;
+    use crate::util::stub_server::{StubResponse, start_stub_server};
+    use crate::util::test_fixtures::TEST_CONFIG;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio::time::sleep;
+
+    const LAST_MODIFIED: &str = "Wed, 21 Oct 2026 07:28:00 GMT";
+
+    struct StubConfig {
+        status: u16,
+        etag: String,
+        last_modified: String,
+        body: String,
+        // When set, the next response body is streamed from here instead of sent in full
+        held_body: Option<mpsc::Receiver<String>>,
+        // Headers of every request received, lower cased names
+        requests: Vec<HashMap<String, String>>,
+    }
+
+    // Config CDN stand-in: serves `body` with the current validators and replies 304 when the
+    // request's If-None-Match matches the current etag
+    async fn start_stub_cdn(stub: Arc<Mutex<StubConfig>>) -> String {
+        start_stub_server(move |request| {
+            let mut stub = stub.lock().unwrap();
+            let not_modified = request.header("if-none-match") == Some(stub.etag.as_str());
+            stub.requests.push(request.headers.clone());
+            if not_modified {
+                return StubResponse::new(304);
+            }
+            let response = StubResponse::new(stub.status)
+                .header("ETag", stub.etag.clone())
+                .header("Last-Modified", stub.last_modified.clone())
+                .header("CF-Ray", format!("ray-{}", stub.requests.len()))
+                .header("Content-Type", "application/json");
+            match stub.held_body.take() {
+                Some(chunks) => response.stream(chunks),
+                None => response.body(stub.body.clone()),
+            }
+        })
+        .await
+    }
+
+    fn stub_config(etag: &str) -> Arc<Mutex<StubConfig>> {
+        Arc::new(Mutex::new(StubConfig {
+            status: 200,
+            etag: etag.to_string(),
+            last_modified: LAST_MODIFIED.to_string(),
+            body: TEST_CONFIG.to_string(),
+            held_body: None,
+            requests: Vec::new(),
+        }))
+    }
+
+    fn options(base_uri: &str, polling_interval: Duration) -> ConfigSourceOptions {
+        ConfigSourceOptions {
+            config_cdn_base_uri: base_uri.to_string(),
+            config_polling_interval: polling_interval,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initial_fetch_populates_config_metadata() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_initial");
+
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        let config = client.state.get_config().expect("config should be set");
+        assert_eq!(config.etag, "\"etag-1\"");
+        assert_eq!(config.ray_id, "ray-1");
+        assert_eq!(
+            config.last_modified,
+            Utc.with_ymd_and_hms(2026, 10, 21, 7, 28, 0).unwrap()
+        );
+        assert!(!config.features.is_empty());
+        assert!(handle.is_running());
+
+        let requests = &stub.lock().unwrap().requests;
+        assert!(!requests[0].contains_key("if-none-match"));
+        assert!(!requests[0].contains_key("if-modified-since"));
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_keeps_current_config() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_not_modified");
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        let initial = client.state.get_config().unwrap();
+
+        assert!(!handle.fetch_config().await.unwrap());
+
+        assert!(Arc::ptr_eq(&initial, &client.state.get_config().unwrap()));
+        let requests = &stub.lock().unwrap().requests;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1]["if-none-match"], "\"etag-1\"");
+        assert_eq!(requests[1]["if-modified-since"], LAST_MODIFIED);
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_changed_config_is_applied() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_changed");
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        {
+            let mut stub = stub.lock().unwrap();
+            stub.etag = "\"etag-2\"".to_string();
+            stub.last_modified = "Thu, 22 Oct 2026 07:28:00 GMT".to_string();
+        }
+
+        assert!(handle.fetch_config().await.unwrap());
+        assert_eq!(client.state.get_config().unwrap().etag, "\"etag-2\"");
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_older_config_is_ignored() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_older");
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        {
+            let mut stub = stub.lock().unwrap();
+            stub.etag = "\"etag-stale\"".to_string();
+            stub.last_modified = "Tue, 20 Oct 2026 07:28:00 GMT".to_string();
+        }
+
+        assert!(!handle.fetch_config().await.unwrap());
+        assert_eq!(client.state.get_config().unwrap().etag, "\"etag-1\"");
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_slow_response_does_not_replace_newer_config() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_slow_response");
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        // The first fetch gets its headers but waits on the body
+        let (body_tx, body_rx) = mpsc::channel(1);
+        {
+            let mut stub = stub.lock().unwrap();
+            stub.etag = "\"etag-2\"".to_string();
+            stub.last_modified = "Thu, 22 Oct 2026 07:28:00 GMT".to_string();
+            stub.held_body = Some(body_rx);
+        }
+        let slow_fetch = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.fetch_config().await }
+        });
+        while stub.lock().unwrap().requests.len() < 2 {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        // A newer config is published and fetched while the first is still in flight
+        {
+            let mut stub = stub.lock().unwrap();
+            stub.etag = "\"etag-3\"".to_string();
+            stub.last_modified = "Fri, 23 Oct 2026 07:28:00 GMT".to_string();
+        }
+        let fast_fetch = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.fetch_config().await }
+        });
+        sleep(Duration::from_millis(50)).await;
+        body_tx.send(TEST_CONFIG.to_string()).await.unwrap();
+        drop(body_tx);
+
+        slow_fetch.await.unwrap().unwrap();
+        fast_fetch.await.unwrap().unwrap();
+        assert_eq!(client.state.get_config().unwrap().etag, "\"etag-3\"");
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_error_status_fails_without_replacing_config() {
+        let stub = stub_config("\"etag-1\"");
+        stub.lock().unwrap().status = 500;
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_error");
+
+        let result = client
+            .init_config_poller(options(&base_uri, Duration::from_secs(3600)))
+            .await;
+
+        match result {
+            Err(err @ DevCycleError::ConfigFetchFailed { .. }) => {
+                assert_eq!(err.code(), "CONFIG_FETCH_FAILED")
+            }
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("expected the fetch to fail"),
+        }
+        assert!(!client.has_config());
+    }
+
+    #[tokio::test]
+    async fn test_poller_picks_up_changes_until_dropped() {
+        let stub = stub_config("\"etag-1\"");
+        let base_uri = start_stub_cdn(stub.clone()).await;
+        let client = DevCycleLocalBucketing::new("dvc_server_poller_background");
+        let handle = client
+            .init_config_poller(options(&base_uri, Duration::from_millis(20)))
+            .await
+            .unwrap();
+
+        {
+            let mut stub = stub.lock().unwrap();
+            stub.etag = "\"etag-2\"".to_string();
+            stub.last_modified = "Thu, 22 Oct 2026 07:28:00 GMT".to_string();
+        }
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(client.state.get_config().unwrap().etag, "\"etag-2\"");
+
+        drop(client);
+        sleep(Duration::from_millis(50)).await;
+        assert!(!handle.is_running());
+    }
+}
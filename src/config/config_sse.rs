@@ -0,0 +1,252 @@
+use crate::config::config_source::ConfigPoller;
+use crate::errors::DevCycleError;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+const REFETCH_CONFIG_MESSAGE: &str = "refetchConfig";
+
+// A single server-sent event, fields other than `event` and `data` are ignored
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SseEvent {
+    pub(crate) event: String,
+    pub(crate) data: String,
+}
+
+// Incremental text/event-stream parser, fed with the response body as it arrives
+#[derive(Default)]
+pub(crate) struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        // CRLF line endings are normalized so events are always separated by a blank "\n\n"
+        self.buffer
+            .extend(chunk.iter().copied().filter(|byte| *byte != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_event(block: &str) -> Option<SseEvent> {
+    let mut event = SseEvent::default();
+    let mut data_lines: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        // Lines starting with a colon are comments, used as keep-alives
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event.event = value.to_string(),
+            "data" => data_lines.push(value),
+            _ => {}
+        }
+    }
+    if data_lines.is_empty() {
+        return None;
+    }
+    event.data = data_lines.join("\n");
+    Some(event)
+}
+
+// Config change notifications wrap their payload as a JSON string in `data`:
+// {"data": "{\"type\":\"refetchConfig\",\"etag\":\"...\",\"lastModified\":1700000000000}"}
+pub(crate) fn is_config_change_newer_than(
+    event: &SseEvent,
+    current_last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    let Ok(message) = serde_json::from_str::<Value>(&event.data) else {
+        return false;
+    };
+    let payload = match message.get("data") {
+        Some(Value::String(data)) => match serde_json::from_str::<Value>(data) {
+            Ok(payload) => payload,
+            Err(_) => return false,
+        },
+        Some(payload) => payload.clone(),
+        None => message,
+    };
+    match payload.get("type").and_then(Value::as_str) {
+        None | Some(REFETCH_CONFIG_MESSAGE) => {}
+        Some(_) => return false,
+    }
+    let announced = payload
+        .get("lastModified")
+        .and_then(Value::as_i64)
+        .and_then(DateTime::from_timestamp_millis);
+    match (announced, current_last_modified) {
+        (Some(announced), Some(current)) => announced > current,
+        _ => true,
+    }
+}
+
+fn realtime_url(poller: &ConfigPoller) -> Option<String> {
+    let client = poller.client.upgrade()?;
+    let config = client.get_config()?;
+    let sse = &config.sse;
+    if sse.hostname.is_empty() {
+        return None;
+    }
+    let hostname = sse.hostname.trim_end_matches('/');
+    if hostname.starts_with("http://") || hostname.starts_with("https://") {
+        Some(format!("{}{}", hostname, sse.path))
+    } else {
+        Some(format!("https://{}{}", hostname, sse.path))
+    }
+}
+
+fn current_last_modified(poller: &ConfigPoller) -> Option<DateTime<Utc>> {
+    let client = poller.client.upgrade()?;
+    Some(client.get_config()?.last_modified)
+}
+
+enum StreamEnd {
+    Shutdown,
+    ClientDropped,
+    Disconnected(String),
+}
+
+// Keeps a connection to the config's SSE endpoint open, reconnecting with exponential backoff.
+// While disconnected the poller falls back to polling on its interval.
+pub(crate) async fn run_realtime_updates(
+    poller: Arc<ConfigPoller>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let initial_backoff = poller
+        .options
+        .realtime_reconnect_backoff
+        .max(Duration::from_millis(1));
+    let mut backoff = initial_backoff;
+    let mut reconnecting = false;
+    loop {
+        if *shutdown.borrow() {
+            return;
+        }
+        match realtime_url(&poller) {
+            Some(url) => {
+                let connected_at = Instant::now();
+                let end = listen(&poller, &url, reconnecting, &mut shutdown).await;
+                poller.realtime_connected.store(false, Ordering::Relaxed);
+                reconnecting = true;
+                match end {
+                    StreamEnd::Shutdown | StreamEnd::ClientDropped => return,
+                    StreamEnd::Disconnected(reason) => {
+                        eprintln!(
+                            "Realtime updates disconnected for SDK key {}: {}",
+                            poller.sdk_key, reason
+                        );
+                    }
+                }
+                // A connection that stayed up for a while starts the backoff over
+                if connected_at.elapsed() > poller.options.realtime_max_reconnect_backoff {
+                    backoff = initial_backoff;
+                }
+            }
+            None if poller.client.strong_count() == 0 => return,
+            // The config has no SSE endpoint yet, check again after the next fetch
+            None => {}
+        }
+        tokio::select! {
+            _ = shutdown.changed() => return,
+            _ = tokio::time::sleep(backoff) => {}
+        }
+        backoff = (backoff * 2).min(
+            poller
+                .options
+                .realtime_max_reconnect_backoff
+                .max(initial_backoff),
+        );
+    }
+}
+
+// Listens until the stream ends. A stream that stays silent for longer than the idle timeout,
+// keep-alives included, is treated as a dropped connection.
+async fn listen(
+    poller: &ConfigPoller,
+    url: &str,
+    reconnecting: bool,
+    shutdown: &mut watch::Receiver<bool>,
+) -> StreamEnd {
+    let idle_timeout = poller
+        .options
+        .realtime_idle_timeout
+        .max(Duration::from_millis(1));
+    let request = poller
+        .http_client
+        .get(url)
+        .header("Accept", "text/event-stream")
+        .send();
+    let mut response = tokio::select! {
+        _ = shutdown.changed() => return StreamEnd::Shutdown,
+        response = tokio::time::timeout(idle_timeout, request) => match response {
+            Ok(Ok(response)) if response.status().is_success() => response,
+            Ok(Ok(response)) => {
+                return StreamEnd::Disconnected(format!("SSE endpoint returned {}", response.status()));
+            }
+            Ok(Err(e)) => return StreamEnd::Disconnected(e.to_string()),
+            Err(_) => return StreamEnd::Disconnected("timed out connecting".to_string()),
+        },
+    };
+    poller.realtime_connected.store(true, Ordering::Relaxed);
+
+    let mut parser = SseParser::default();
+    // Deadline of the pending refetch, later notifications are folded into it. Changes
+    // announced while disconnected were missed, so a reconnect refetches right away.
+    let mut refetch_at: Option<Instant> = reconnecting.then(Instant::now);
+    loop {
+        let refetch_deadline = async {
+            match refetch_at {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = shutdown.changed() => return StreamEnd::Shutdown,
+            _ = refetch_deadline => {
+                refetch_at = None;
+                match poller.fetch_config().await {
+                    Ok(_) => {}
+                    Err(DevCycleError::ClientDropped) => return StreamEnd::ClientDropped,
+                    Err(e) => eprintln!(
+                        "Failed to refetch config for SDK key {}: {}",
+                        poller.sdk_key, e
+                    ),
+                }
+            }
+            chunk = tokio::time::timeout(idle_timeout, response.chunk()) => match chunk {
+                Ok(Ok(Some(chunk))) => {
+                    let last_modified = current_last_modified(poller);
+                    let changed = parser
+                        .push(&chunk)
+                        .iter()
+                        .any(|event| is_config_change_newer_than(event, last_modified));
+                    if changed && refetch_at.is_none() {
+                        refetch_at =
+                            Some(Instant::now() + poller.options.realtime_updates_debounce);
+                    }
+                }
+                Ok(Ok(None)) => return StreamEnd::Disconnected("stream closed".to_string()),
+                Ok(Err(e)) => return StreamEnd::Disconnected(e.to_string()),
+                Err(_) => {
+                    return StreamEnd::Disconnected(format!(
+                        "no data received for {:?}",
+                        idle_timeout
+                    ));
+                }
+            },
+        }
+    }
+}
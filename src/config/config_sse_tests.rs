@@ -0,0 +1,332 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::config_source::ConfigSourceOptions;
+    use crate::config::config_sse::{SseEvent, SseParser, is_config_change_newer_than};
+    use crate::util::stub_server::{StubResponse, start_stub_server};
+    use crate::util::test_fixtures::test_config_json;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::{broadcast, mpsc};
+    use tokio::time::sleep;
+
+    const CLOSE_STREAM: &str = "__close__";
+
+    struct StubState {
+        etag: String,
+        last_modified: String,
+        config_requests: usize,
+        sse_connections: usize,
+        sse_available: bool,
+        // Streams accepted while set stay open without sending anything
+        sse_silent: bool,
+    }
+
+    struct StubCdn {
+        base_uri: String,
+        state: Arc<Mutex<StubState>>,
+        messages: broadcast::Sender<String>,
+    }
+
+    impl StubCdn {
+        fn set_config_version(&self, etag: &str, last_modified: &str) {
+            let mut state = self.state.lock().unwrap();
+            state.etag = etag.to_string();
+            state.last_modified = last_modified.to_string();
+        }
+
+        fn config_requests(&self) -> usize {
+            self.state.lock().unwrap().config_requests
+        }
+
+        fn send(&self, data: &str) {
+            self.messages.send(data.to_string()).unwrap();
+        }
+    }
+
+    // Serves the test config, with an `sse` section pointing back at this server, and a
+    // text/event-stream endpoint forwarding every message sent through `StubCdn::send`
+    async fn start_stub_cdn() -> StubCdn {
+        let config = test_config_json();
+        let state = Arc::new(Mutex::new(StubState {
+            etag: "\"etag-1\"".to_string(),
+            last_modified: "Wed, 21 Oct 2026 07:28:00 GMT".to_string(),
+            config_requests: 0,
+            sse_connections: 0,
+            sse_available: true,
+            sse_silent: false,
+        }));
+        let (messages, _) = broadcast::channel::<String>(16);
+
+        let server_state = state.clone();
+        let server_messages = messages.clone();
+        let base_uri = start_stub_server(move |request| {
+            if request.path.starts_with("/event-stream") {
+                let (available, silent) = {
+                    let mut state = server_state.lock().unwrap();
+                    state.sse_connections += 1;
+                    (state.sse_available, state.sse_silent)
+                };
+                if !available {
+                    return StubResponse::new(503);
+                }
+                let (chunks, stream) = mpsc::channel::<String>(16);
+                let mut receiver = server_messages.subscribe();
+                tokio::spawn(async move {
+                    if silent {
+                        chunks.closed().await;
+                        return;
+                    }
+                    if chunks.send(": connected\n\n".to_string()).await.is_err() {
+                        return;
+                    }
+                    while let Ok(data) = receiver.recv().await {
+                        if data == CLOSE_STREAM {
+                            break;
+                        }
+                        let frame = format!("event: message\ndata: {}\n\n", data);
+                        if chunks.send(frame).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                return StubResponse::new(200)
+                    .header("Content-Type", "text/event-stream")
+                    .header("Cache-Control", "no-cache")
+                    .stream(stream);
+            }
+
+            let mut state = server_state.lock().unwrap();
+            state.config_requests += 1;
+            if request.header("if-none-match") == Some(state.etag.as_str()) {
+                return StubResponse::new(304);
+            }
+            let mut config = config.clone();
+            config["sse"] = json!({
+                "hostname": format!("http://{}", request.header("host").unwrap_or_default()),
+                "path": "/event-stream?channels=test"
+            });
+            StubResponse::new(200)
+                .header("ETag", state.etag.clone())
+                .header("Last-Modified", state.last_modified.clone())
+                .header("Content-Type", "application/json")
+                .body(config.to_string())
+        })
+        .await;
+
+        StubCdn {
+            base_uri,
+            state,
+            messages,
+        }
+    }
+
+    fn realtime_options(base_uri: &str, polling_interval: Duration) -> ConfigSourceOptions {
+        ConfigSourceOptions {
+            config_cdn_base_uri: base_uri.to_string(),
+            config_polling_interval: polling_interval,
+            enable_realtime_updates: true,
+            realtime_updates_debounce: Duration::from_millis(50),
+            realtime_reconnect_backoff: Duration::from_millis(20),
+            realtime_max_reconnect_backoff: Duration::from_millis(100),
+            ..Default::default()
+        }
+    }
+
+    fn refetch_message(last_modified_ms: i64) -> String {
+        json!({
+            "data": json!({"type": "refetchConfig", "etag": "\"etag-2\"", "lastModified": last_modified_ms})
+                .to_string()
+        })
+        .to_string()
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    #[test]
+    fn test_parser_handles_split_chunks_and_comments() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b": keep-alive\n\nevent: mess").is_empty());
+        let events = parser.push(b"age\r\ndata: first\r\ndata: second\r\n\r\ndata:{}\n\n");
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "first\nsecond".to_string(),
+                },
+                SseEvent {
+                    event: String::new(),
+                    data: "{}".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_change_messages() {
+        let current_time = Utc.with_ymd_and_hms(2026, 10, 21, 7, 28, 0).unwrap();
+        let current = Some(current_time);
+        let event = |data: String| SseEvent {
+            event: "message".to_string(),
+            data,
+        };
+        let newer = current_time.timestamp_millis() + 1000;
+        let older = current_time.timestamp_millis() - 1000;
+
+        assert!(is_config_change_newer_than(
+            &event(refetch_message(newer)),
+            current
+        ));
+        assert!(!is_config_change_newer_than(
+            &event(refetch_message(older)),
+            current
+        ));
+        assert!(is_config_change_newer_than(
+            &event(refetch_message(older)),
+            None
+        ));
+        assert!(is_config_change_newer_than(
+            &event(json!({"data": "{}"}).to_string()),
+            current
+        ));
+        assert!(!is_config_change_newer_than(
+            &event(json!({"data": "{\"type\":\"other\"}"}).to_string()),
+            current
+        ));
+        assert!(!is_config_change_newer_than(
+            &event("not json".to_string()),
+            current
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_change_notifications_are_debounced_into_one_refetch() {
+        let stub = start_stub_cdn().await;
+        let client = DevCycleLocalBucketing::new("dvc_server_realtime_debounce");
+        let handle = client
+            .init_config_poller(realtime_options(&stub.base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        wait_until(|| handle.is_realtime_connected()).await;
+        assert_eq!(stub.config_requests(), 1);
+
+        stub.set_config_version("\"etag-2\"", "Thu, 22 Oct 2026 07:28:00 GMT");
+        let announced = Utc
+            .with_ymd_and_hms(2026, 10, 22, 7, 28, 0)
+            .unwrap()
+            .timestamp_millis();
+        stub.send(&refetch_message(announced));
+        stub.send(&refetch_message(announced));
+        stub.send(&refetch_message(announced));
+
+        wait_until(|| {
+            client
+                .state
+                .get_config()
+                .is_some_and(|config| config.etag == "\"etag-2\"")
+        })
+        .await;
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(stub.config_requests(), 2);
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_falls_back_to_polling_and_reconnects() {
+        let stub = start_stub_cdn().await;
+        let client = DevCycleLocalBucketing::new("dvc_server_realtime_fallback");
+        let handle = client
+            .init_config_poller(realtime_options(&stub.base_uri, Duration::from_millis(20)))
+            .await
+            .unwrap();
+        wait_until(|| handle.is_realtime_connected()).await;
+
+        // Polling is paused while the stream is connected
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(stub.config_requests(), 1);
+
+        stub.state.lock().unwrap().sse_available = false;
+        stub.send(CLOSE_STREAM);
+        wait_until(|| !handle.is_realtime_connected()).await;
+        stub.set_config_version("\"etag-2\"", "Thu, 22 Oct 2026 07:28:00 GMT");
+        wait_until(|| {
+            client
+                .state
+                .get_config()
+                .is_some_and(|config| config.etag == "\"etag-2\"")
+        })
+        .await;
+        // Polling can pick up the change before the next reconnect attempt
+        wait_until(|| stub.state.lock().unwrap().sse_connections > 1).await;
+
+        stub.state.lock().unwrap().sse_available = true;
+        wait_until(|| handle.is_realtime_connected()).await;
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_silent_stream_times_out_and_polling_resumes() {
+        let stub = start_stub_cdn().await;
+        stub.state.lock().unwrap().sse_silent = true;
+        let client = DevCycleLocalBucketing::new("dvc_server_realtime_idle");
+        let handle = client
+            .init_config_poller(ConfigSourceOptions {
+                realtime_idle_timeout: Duration::from_millis(100),
+                realtime_reconnect_backoff: Duration::from_secs(3600),
+                realtime_max_reconnect_backoff: Duration::from_secs(3600),
+                ..realtime_options(&stub.base_uri, Duration::from_millis(20))
+            })
+            .await
+            .unwrap();
+        wait_until(|| handle.is_realtime_connected()).await;
+
+        // The half-open stream is dropped and the poller picks up changes again
+        wait_until(|| !handle.is_realtime_connected()).await;
+        stub.set_config_version("\"etag-2\"", "Thu, 22 Oct 2026 07:28:00 GMT");
+        wait_until(|| {
+            client
+                .state
+                .get_config()
+                .is_some_and(|config| config.etag == "\"etag-2\"")
+        })
+        .await;
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_refetches_missed_changes() {
+        let stub = start_stub_cdn().await;
+        let client = DevCycleLocalBucketing::new("dvc_server_realtime_reconnect");
+        let handle = client
+            .init_config_poller(realtime_options(&stub.base_uri, Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        wait_until(|| handle.is_realtime_connected()).await;
+
+        // The change is never announced, only the reconnect can pick it up
+        stub.set_config_version("\"etag-2\"", "Thu, 22 Oct 2026 07:28:00 GMT");
+        stub.send(CLOSE_STREAM);
+        wait_until(|| {
+            client
+                .state
+                .get_config()
+                .is_some_and(|config| config.etag == "\"etag-2\"")
+        })
+        .await;
+        assert_eq!(stub.config_requests(), 2);
+        assert!(stub.state.lock().unwrap().sse_connections > 1);
+        handle.shutdown();
+    }
+}
@@ -850,4 +850,136 @@ mod tests {
             "Config missing project should fail to parse"
         );
     }
+
+    fn prerequisite_feature_json(id: &str, prerequisites: &[&str]) -> serde_json::Value {
+        let prerequisites: Vec<serde_json::Value> = prerequisites
+            .iter()
+            .map(|feature_id| serde_json::json!({"_feature": feature_id, "comparator": "="}))
+            .collect();
+        serde_json::json!({
+            "_id": id,
+            "key": format!("{}-key", id),
+            "type": "release",
+            "variations": [],
+            "configuration": {
+                "_id": format!("{}-config", id),
+                "prerequisites": prerequisites,
+                "targets": []
+            }
+        })
+    }
+
+    fn full_config_with_features(features: Vec<serde_json::Value>) -> FullConfig {
+        let config_json = include_str!("../../tests/resources/test_config.json");
+        let mut config: serde_json::Value = serde_json::from_str(config_json).unwrap();
+        config["features"] = serde_json::Value::Array(features);
+        serde_json::from_value(config).expect("Failed to parse config")
+    }
+
+    #[test]
+    fn test_parse_feature_prerequisites() {
+        let feature = prerequisite_feature_json("dependent", &["parent"]);
+        let feature: crate::feature::ConfigFeature = serde_json::from_value(feature).unwrap();
+        let prerequisite = &feature.configuration.prerequisites[0];
+        assert_eq!(prerequisite._feature, "parent");
+        assert_eq!(prerequisite.comparator, "=");
+    }
+
+    #[test]
+    fn test_compile_orders_features_by_prerequisites() {
+        let full_config = full_config_with_features(vec![
+            prerequisite_feature_json("a", &["c"]),
+            prerequisite_feature_json("b", &[]),
+            prerequisite_feature_json("c", &["b", "missing"]),
+            prerequisite_feature_json("d", &[]),
+        ]);
+
+        let config = ConfigBody::from_full_config(full_config).expect("Config should compile");
+        let order: Vec<&str> = config.features.iter().map(|f| f._id.as_str()).collect();
+        assert_eq!(order, vec!["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    fn test_variable_id_to_feature_map_follows_prerequisite_order() {
+        let variable_id = "6216422850294da359385e8d";
+        let with_variable = |id: &str, prerequisites: &[&str]| {
+            let mut feature = prerequisite_feature_json(id, prerequisites);
+            feature["variations"] = serde_json::json!([{
+                "_id": format!("{}-variation", id),
+                "key": format!("{}-variation", id),
+                "name": "Variation",
+                "variables": [{"_var": variable_id, "value": true}]
+            }]);
+            feature
+        };
+        // "a" is listed first but evaluated after its prerequisite "b"
+        let full_config =
+            full_config_with_features(vec![with_variable("a", &["b"]), with_variable("b", &[])]);
+
+        let config = ConfigBody::from_full_config(full_config).expect("Config should compile");
+        assert_eq!(config.features[0]._id, "b");
+        assert_eq!(
+            config.get_feature_for_variable_id(variable_id).unwrap()._id,
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_compile_rejects_prerequisite_cycle() {
+        let full_config = full_config_with_features(vec![
+            prerequisite_feature_json("a", &["b"]),
+            prerequisite_feature_json("b", &["c"]),
+            prerequisite_feature_json("c", &["a"]),
+        ]);
+
+        let err = ConfigBody::from_full_config(full_config)
+            .err()
+            .expect("Cyclic prerequisites should fail to compile");
+        assert_eq!(
+            err,
+            "Feature prerequisites form a cycle: a-key -> b-key -> c-key -> a-key"
+        );
+    }
+
+    #[test]
+    fn test_variable_id_to_feature_map_holds_sorted_distributions() {
+        use crate::util::test_fixtures::{
+            TEST_CONFIG, VARIATION_OFF, VARIATION_ON, client_with_config, create_user,
+            test_config_json,
+        };
+        use std::collections::HashMap;
+
+        // test_config.json lists the distribution in ascending order, compile sorts it
+        assert!(TEST_CONFIG.find(VARIATION_ON) < TEST_CONFIG.find(VARIATION_OFF));
+        let client = client_with_config("dvc_server_variable_feature_map", test_config_json());
+        let config = client.state.get_config().unwrap();
+        let feature = config
+            .get_feature_for_variable_id("6216422850294da359385e8d")
+            .unwrap();
+        let variations: Vec<&str> = feature.configuration.targets[0]
+            .distribution
+            .iter()
+            .map(|distribution| distribution.variation.as_str())
+            .collect();
+        assert_eq!(variations, vec![VARIATION_OFF, VARIATION_ON]);
+
+        // Variable evaluation buckets every user like the bucketed config
+        for i in 0..20 {
+            let user = create_user(&format!("user-{}", i));
+            let bucketed = client
+                .generate_bucketed_config(user.clone(), HashMap::new())
+                .unwrap();
+            let variable = crate::bucketing::evaluate_variable_for_user(
+                &client.state,
+                user,
+                "test",
+                "Boolean",
+                HashMap::new(),
+                chrono::Utc::now(),
+                None,
+            )
+            .unwrap();
+            assert_eq!(variable.variable_value, bucketed.variables["test"].value);
+        }
+    }
 }
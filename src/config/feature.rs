@@ -64,18 +64,17 @@ pub struct FeatureConfiguration {
     pub _id: String,
     #[serde(default)]
     pub prerequisites: Vec<FeaturePrerequisites>,
-    #[serde(default)]
+    #[serde(alias = "winning_variation", rename = "winningVariation", default)]
     pub winning_variation: Option<FeatureVariation>,
-    #[serde(default)]
+    #[serde(alias = "forced_users", rename = "forcedUsers", default)]
     pub forced_users: HashMap<String, String>,
     pub(crate) targets: Vec<Target>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
 pub struct FeaturePrerequisites {
-    _feature: String,
-    comparator: String,
+    pub _feature: String,
+    pub comparator: String,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
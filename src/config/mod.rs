@@ -1,8 +1,23 @@
 pub(crate) mod client_custom_data;
 pub(crate) mod config;
+pub(crate) mod config_diff;
+pub(crate) mod config_lint;
+pub(crate) mod config_listeners;
+pub(crate) mod config_source;
+pub(crate) mod config_sse;
 pub(crate) mod configmanager;
 pub(crate) mod feature;
 
+#[cfg(test)]
+mod config_diff_tests;
+#[cfg(test)]
+mod config_lint_tests;
+#[cfg(test)]
+mod config_listeners_tests;
+#[cfg(test)]
+mod config_source_tests;
+#[cfg(test)]
+mod config_sse_tests;
 #[cfg(test)]
 mod config_tests;
 #[cfg(test)]
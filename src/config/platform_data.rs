@@ -1,7 +1,7 @@
-use once_cell::sync::Lazy;
+use crate::client::client_manager;
+use crate::errors::{self, DevCycleError};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 cfg_if::cfg_if! {
     if #[cfg(target_family = "wasm")] {
@@ -20,10 +20,6 @@ cfg_if::cfg_if! {
     }
 }
 
-// Global platform data storage per SDK key
-pub(crate) static PLATFORM_DATA: Lazy<RwLock<HashMap<String, Arc<PlatformData>>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlatformData {
@@ -48,21 +44,15 @@ impl PlatformData {
     }
 }
 
-pub fn get_platform_data(sdk_key: &str) -> Result<Arc<PlatformData>, String> {
-    let data = PLATFORM_DATA
-        .read()
-        .expect("Failed to acquire read lock on PLATFORM_DATA: lock poisoned");
-    data.get(sdk_key).cloned().ok_or_else(|| {
-        format!(
-            "Platform data not set for SDK key: {}. Call set_platform_data() first.",
-            sdk_key
-        )
-    })
+pub fn get_platform_data(sdk_key: &str) -> Result<Arc<PlatformData>, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.state.get_platform_data(),
+        None => Err(errors::missing_platform_data(sdk_key)),
+    }
 }
 
 pub fn set_platform_data(sdk_key: String, platform_data: PlatformData) {
-    let mut data = PLATFORM_DATA
-        .write()
-        .expect("Failed to acquire write lock on PLATFORM_DATA (lock may be poisoned)");
-    data.insert(sdk_key, Arc::new(platform_data));
+    client_manager::get_or_create_client(&sdk_key)
+        .state
+        .set_platform_data(platform_data);
 }
@@ -24,6 +24,7 @@ mod tests {
         assert!(
             result
                 .unwrap_err()
+                .to_string()
                 .contains("Platform data not set for SDK key")
         );
     }
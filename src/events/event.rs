@@ -1,15 +1,22 @@
 use crate::user::{PopulatedUser, User};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum EventType {
+    #[serde(rename = "aggVariableEvaluated")]
     AggregateVariableEvaluated,
+    #[serde(rename = "aggVariableDefaulted")]
     AggregateVariableDefaulted,
+    #[serde(rename = "variableEvaluated")]
     VariableEvaluated,
+    #[serde(rename = "variableDefaulted")]
     VariableDefaulted,
+    #[serde(rename = "sdkConfig")]
     SDKConfig,
+    #[serde(rename = "customEvent")]
     CustomEvent,
 }
 
@@ -25,6 +32,8 @@ pub enum EvaluationReason {
     Disabled,
     #[serde(rename = "ERROR")]
     Error,
+    #[serde(rename = "OVERRIDE")]
+    Override,
 }
 
 impl fmt::Display for EvaluationReason {
@@ -35,6 +44,7 @@ impl fmt::Display for EvaluationReason {
             EvaluationReason::Default => "DEFAULT",
             EvaluationReason::Disabled => "DISABLED",
             EvaluationReason::Error => "ERROR",
+            EvaluationReason::Override => "OVERRIDE",
         };
         write!(f, "{}", s)
     }
@@ -56,6 +66,8 @@ pub enum DefaultReason {
     UserNotInRollout,
     #[serde(rename = "User Not Targeted")]
     UserNotTargeted,
+    #[serde(rename = "Prerequisite Not Met")]
+    PrerequisiteNotMet,
     #[serde(rename = "Invalid Variable Type")]
     InvalidVariableType,
     #[serde(rename = "Variable Type Mismatch")]
@@ -78,6 +90,7 @@ impl fmt::Display for DefaultReason {
             DefaultReason::MissingVariableForVariation => "Missing Variable for Variation",
             DefaultReason::UserNotInRollout => "User Not in Rollout",
             DefaultReason::UserNotTargeted => "User Not Targeted",
+            DefaultReason::PrerequisiteNotMet => "Prerequisite Not Met",
             DefaultReason::InvalidVariableType => "Invalid Variable Type",
             DefaultReason::VariableTypeMismatch => "Variable Type Mismatch",
             DefaultReason::Unknown => "Unknown",
@@ -104,22 +117,51 @@ pub(crate) type VariableAggMap = HashMap<String, FeatureAggMap>;
 pub(crate) type AggregateEventQueue = HashMap<EventType, VariableAggMap>;
 pub(crate) type UserEventQueue = HashMap<String, UserEventsBatchRecord>;
 
+#[derive(Clone, Serialize)]
 pub(crate) struct Event {
+    #[serde(rename = "type")]
     pub(crate) event_type: EventType,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub(crate) target: String,
+    #[serde(rename = "customType", skip_serializing_if = "String::is_empty")]
     pub(crate) custom_type: String,
     pub(crate) user_id: String,
-    pub(crate) client_date: std::time::Instant,
+    #[serde(rename = "clientDate")]
+    pub(crate) client_date: DateTime<Utc>,
     pub(crate) value: f64,
+    #[serde(rename = "featureVars")]
     pub(crate) feature_vars: HashMap<String, String>,
+    #[serde(rename = "metaData", skip_serializing_if = "HashMap::is_empty")]
     pub(crate) meta_data: HashMap<String, serde_json::Value>,
 }
 
+/// A custom event recorded for a user through `track`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomEvent {
+    #[serde(rename = "type")]
+    pub custom_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(
+        rename = "metaData",
+        alias = "metadata",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub metadata: HashMap<String, serde_json::Value>,
+    // Defaults to the time the event is tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<Utc>>,
+}
+
 pub(crate) struct UserEventData {
     pub(crate) event: Event,
     pub(crate) user: User,
 }
 
+#[derive(Clone, Serialize)]
 pub(crate) struct UserEventsBatchRecord {
     pub(crate) user: PopulatedUser,
     pub(crate) events: Vec<Event>,
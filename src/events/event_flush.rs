@@ -0,0 +1,133 @@
+use crate::errors::DevCycleError;
+use crate::events::event::*;
+use crate::events::event_sink::EventBatch;
+use crate::user::PopulatedUser;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static PAYLOAD_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// A chunk of user event records sent to the events API in a single request
+#[derive(Clone)]
+pub(crate) struct FlushPayload {
+    pub(crate) payload_id: String,
+    pub(crate) event_count: i64,
+    pub(crate) records: Vec<UserEventsBatchRecord>,
+}
+
+#[derive(Serialize)]
+struct BatchRequestBody<'a> {
+    batch: &'a [UserEventsBatchRecord],
+}
+
+fn next_payload_id() -> String {
+    format!(
+        "{}-{}",
+        Utc::now().timestamp_millis(),
+        PAYLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// Split records into payloads holding at most `batch_size` events each. A user with more
+// events than fit in the current payload has their events spread across payloads.
+pub(crate) fn construct_flush_payloads(
+    records: Vec<UserEventsBatchRecord>,
+    batch_size: usize,
+) -> Vec<FlushPayload> {
+    let batch_size = batch_size.max(1);
+    let mut payloads: Vec<FlushPayload> = Vec::new();
+    let mut current: Vec<UserEventsBatchRecord> = Vec::new();
+    let mut current_count = 0;
+
+    for record in records {
+        let mut events = record.events.into_iter().peekable();
+        while events.peek().is_some() {
+            let chunk: Vec<Event> = events.by_ref().take(batch_size - current_count).collect();
+            current_count += chunk.len();
+            current.push(UserEventsBatchRecord {
+                user: record.user.clone(),
+                events: chunk,
+            });
+            if current_count == batch_size {
+                payloads.push(FlushPayload {
+                    payload_id: next_payload_id(),
+                    event_count: current_count as i64,
+                    records: std::mem::take(&mut current),
+                });
+                current_count = 0;
+            }
+        }
+    }
+    if current_count > 0 {
+        payloads.push(FlushPayload {
+            payload_id: next_payload_id(),
+            event_count: current_count as i64,
+            records: current,
+        });
+    }
+    payloads
+}
+
+// Convert the aggregate counts into events attributed to the SDK instance's platform user
+pub(crate) fn generate_aggregate_event_record(
+    agg_event_queue: &AggregateEventQueue,
+    platform_user: PopulatedUser,
+) -> Option<UserEventsBatchRecord> {
+    let mut events: Vec<Event> = Vec::new();
+    for (event_type, variable_agg_map) in agg_event_queue {
+        for (variable_key, feature_agg_map) in variable_agg_map {
+            for (feature_id, variation_agg_map) in feature_agg_map {
+                for (variation_id, eval_reasons) in variation_agg_map {
+                    let count: i64 = eval_reasons.values().sum();
+                    if count == 0 {
+                        continue;
+                    }
+                    let mut meta_data: HashMap<String, serde_json::Value> = HashMap::new();
+                    if *event_type == EventType::AggregateVariableEvaluated {
+                        meta_data.insert("_feature".to_string(), feature_id.clone().into());
+                        meta_data.insert("_variation".to_string(), variation_id.clone().into());
+                    }
+                    meta_data.insert(
+                        "eval".to_string(),
+                        serde_json::to_value(eval_reasons).unwrap_or_default(),
+                    );
+                    events.push(Event {
+                        event_type: event_type.clone(),
+                        target: variable_key.clone(),
+                        custom_type: String::new(),
+                        user_id: platform_user.user_id.clone(),
+                        client_date: Utc::now(),
+                        value: count as f64,
+                        feature_vars: HashMap::new(),
+                        meta_data,
+                    });
+                }
+            }
+        }
+    }
+    if events.is_empty() {
+        return None;
+    }
+    Some(UserEventsBatchRecord {
+        user: platform_user,
+        events,
+    })
+}
+
+impl FlushPayload {
+    pub(crate) fn to_event_batch(&self) -> Result<EventBatch, DevCycleError> {
+        let body = serde_json::to_string(&BatchRequestBody {
+            batch: &self.records,
+        })
+        .map_err(|e| {
+            DevCycleError::with_source(&format!("Failed to serialize events batch: {}", e), e)
+        })?;
+        Ok(EventBatch {
+            payload_id: self.payload_id.clone(),
+            event_count: self.event_count,
+            body,
+        })
+    }
+}
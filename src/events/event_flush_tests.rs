@@ -0,0 +1,371 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::client_manager;
+    use crate::config::platform_data::{self, PlatformData};
+    use crate::events::event::*;
+    use crate::events::event_flush::*;
+    use crate::events::event_queue::{EventQueue, EventQueueOptions};
+    use crate::user::{PopulatedUser, User};
+    use crate::util::stub_server::{StubRequest, StubResponse, start_stub_server};
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Events API stand-in: records every request and replies with `status`
+    async fn start_events_api(status: u16) -> (String, Arc<Mutex<Vec<StubRequest>>>) {
+        let requests: Arc<Mutex<Vec<StubRequest>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let base_uri = start_stub_server(move |request| {
+            recorded.lock().unwrap().push(request.clone());
+            StubResponse::new(status)
+        })
+        .await;
+        (base_uri, requests)
+    }
+
+    fn setup_platform_data(sdk_key: &str) {
+        platform_data::set_platform_data(
+            sdk_key.to_string(),
+            PlatformData {
+                platform: "rust".to_string(),
+                platform_version: "1.0.0".to_string(),
+                sdk_type: "server".to_string(),
+                sdk_version: "1.0.0".to_string(),
+                hostname: "flush-host".to_string(),
+                device_model: "test".to_string(),
+            },
+        );
+    }
+
+    fn create_event_queue(sdk_key: &str, events_api_base_uri: &str, batch_size: i32) -> EventQueue {
+        setup_platform_data(sdk_key);
+        let options = EventQueueOptions {
+            flush_events_interval: Duration::from_secs(3600),
+            flush_events_batch_size: batch_size,
+            events_api_base_uri: events_api_base_uri.to_string(),
+            ..Default::default()
+        };
+        EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap()
+    }
+
+    fn create_test_user(user_id: &str) -> PopulatedUser {
+        let user = User {
+            user_id: user_id.to_string(),
+            email: String::new(),
+            name: String::new(),
+            language: String::new(),
+            country: String::new(),
+            ip: String::new(),
+            app_version: String::new(),
+            app_build: String::new(),
+            custom_data: HashMap::new(),
+            private_custom_data: HashMap::from([(
+                "secret".to_string(),
+                serde_json::json!("value"),
+            )]),
+            device_model: String::new(),
+            last_seen_date: Utc::now(),
+        };
+        PopulatedUser::new(
+            user,
+            Arc::new(PlatformData {
+                platform: "rust".to_string(),
+                platform_version: "1.0.0".to_string(),
+                sdk_type: "server".to_string(),
+                sdk_version: "1.0.0".to_string(),
+                hostname: "flush-host".to_string(),
+                device_model: "test".to_string(),
+            }),
+            HashMap::new(),
+        )
+    }
+
+    fn create_custom_event(target: &str) -> Event {
+        Event {
+            event_type: EventType::CustomEvent,
+            target: target.to_string(),
+            custom_type: "purchase".to_string(),
+            user_id: String::new(),
+            client_date: Utc::now(),
+            value: 1.0,
+            feature_vars: HashMap::new(),
+            meta_data: HashMap::new(),
+        }
+    }
+
+    fn create_record(user_id: &str, event_count: usize) -> UserEventsBatchRecord {
+        UserEventsBatchRecord {
+            user: create_test_user(user_id),
+            events: (0..event_count)
+                .map(|i| create_custom_event(&format!("target-{}", i)))
+                .collect(),
+        }
+    }
+
+    fn batch_event_count(body: &serde_json::Value) -> usize {
+        body["batch"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record["events"].as_array().unwrap().len())
+            .sum()
+    }
+
+    #[test]
+    fn test_construct_flush_payloads_chunks_by_batch_size() {
+        let records = vec![create_record("a", 3), create_record("b", 4)];
+        let payloads = construct_flush_payloads(records, 3);
+
+        let counts: Vec<i64> = payloads.iter().map(|p| p.event_count).collect();
+        assert_eq!(counts, vec![3, 3, 1]);
+        // User "b" is split across the second and third payloads
+        assert_eq!(payloads[1].records.len(), 1);
+        assert_eq!(payloads[1].records[0].user.user_id, "b");
+        assert_eq!(payloads[2].records[0].user.user_id, "b");
+        assert_ne!(payloads[0].payload_id, payloads[1].payload_id);
+    }
+
+    #[test]
+    fn test_construct_flush_payloads_combines_users() {
+        let records = vec![create_record("a", 1), create_record("b", 1)];
+        let payloads = construct_flush_payloads(records, 100);
+
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].event_count, 2);
+        assert_eq!(payloads[0].records.len(), 2);
+    }
+
+    #[test]
+    fn test_construct_flush_payloads_empty() {
+        assert!(construct_flush_payloads(vec![], 100).is_empty());
+        assert!(construct_flush_payloads(vec![create_record("a", 0)], 100).is_empty());
+    }
+
+    #[test]
+    fn test_generate_aggregate_event_record() {
+        let mut agg_event_queue: AggregateEventQueue = HashMap::new();
+        agg_event_queue
+            .entry(EventType::AggregateVariableEvaluated)
+            .or_default()
+            .entry("my-variable".to_string())
+            .or_default()
+            .entry("feature-id".to_string())
+            .or_default()
+            .insert(
+                "variation-id".to_string(),
+                HashMap::from([
+                    (EvaluationReason::TargetingMatch, 2),
+                    (EvaluationReason::Split, 1),
+                ]),
+            );
+        // Zero counts come from pre-populated keys and are not reported
+        agg_event_queue
+            .entry(EventType::AggregateVariableEvaluated)
+            .or_default()
+            .entry("unused-variable".to_string())
+            .or_default()
+            .entry("feature-id".to_string())
+            .or_default()
+            .insert(
+                "variation-id".to_string(),
+                HashMap::from([(EvaluationReason::TargetingMatch, 0)]),
+            );
+
+        let record =
+            generate_aggregate_event_record(&agg_event_queue, create_test_user("flush-host"))
+                .unwrap();
+        assert_eq!(record.events.len(), 1);
+
+        let event = serde_json::to_value(&record.events[0]).unwrap();
+        assert_eq!(event["type"], "aggVariableEvaluated");
+        assert_eq!(event["target"], "my-variable");
+        assert_eq!(event["value"], 3.0);
+        assert_eq!(event["metaData"]["_feature"], "feature-id");
+        assert_eq!(event["metaData"]["_variation"], "variation-id");
+        assert_eq!(event["metaData"]["eval"]["TARGETING_MATCH"], 2);
+        assert_eq!(event["metaData"]["eval"]["SPLIT"], 1);
+    }
+
+    #[test]
+    fn test_generate_aggregate_event_record_empty() {
+        let agg_event_queue: AggregateEventQueue = HashMap::new();
+        assert!(
+            generate_aggregate_event_record(&agg_event_queue, create_test_user("flush-host"))
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_posts_batches() {
+        let (base_uri, requests) = start_events_api(201).await;
+        let sdk_key = "test_flush_posts_batches";
+        let eq = create_event_queue(sdk_key, &base_uri, 2);
+
+        for key in ["var-a", "var-b", "var-c"] {
+            eq.queue_variable_evaluated_event(
+                key,
+                "feature-id",
+                "variation-id",
+                EvaluationReason::TargetingMatch,
+            )
+            .unwrap();
+        }
+
+        let reported = eq.flush_events().await.unwrap();
+        assert_eq!(reported, 3);
+        assert_eq!(eq.events_flushed.load(Ordering::Relaxed), 3);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 3);
+        assert!(eq.agg_event_queue.lock().await.is_empty());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            assert_eq!(request.path, "/v1/events/batch");
+            assert_eq!(request.header("authorization"), Some(sdk_key));
+            assert_eq!(request.json()["batch"][0]["user"]["userId"], "flush-host");
+        }
+        let total: usize = requests.iter().map(|r| batch_event_count(&r.json())).sum();
+        assert_eq!(total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_strips_private_custom_data() {
+        let (base_uri, requests) = start_events_api(201).await;
+        let eq = create_event_queue("test_flush_private_data", &base_uri, 100);
+        eq.user_event_queue.lock().await.insert(
+            "user-1".to_string(),
+            UserEventsBatchRecord {
+                user: create_test_user("user-1"),
+                events: vec![create_custom_event("checkout")],
+            },
+        );
+        eq.user_event_queue_count.store(1, Ordering::Relaxed);
+
+        assert_eq!(eq.flush_events().await.unwrap(), 1);
+        assert_eq!(eq.user_event_queue_count.load(Ordering::Relaxed), 0);
+
+        let requests = requests.lock().unwrap();
+        let body = requests[0].json();
+        let record = &body["batch"][0];
+        assert_eq!(record["user"]["userId"], "user-1");
+        assert_eq!(record["user"]["privateCustomData"], serde_json::json!({}));
+        assert_eq!(record["events"][0]["type"], "customEvent");
+        assert_eq!(record["events"][0]["customType"], "purchase");
+        assert_eq!(record["events"][0]["target"], "checkout");
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_nothing_to_send() {
+        let (base_uri, requests) = start_events_api(201).await;
+        let eq = create_event_queue("test_flush_nothing", &base_uri, 100);
+
+        assert_eq!(eq.flush_events().await.unwrap(), 0);
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_retries_server_errors() {
+        let (base_uri, requests) = start_events_api(503).await;
+        let eq = create_event_queue("test_flush_retry", &base_uri, 100);
+        eq.queue_variable_defaulted_event("var-a", "", "").unwrap();
+
+        assert!(eq.flush_events().await.is_err());
+        assert_eq!(eq.pending_payloads.lock().await.len(), 1);
+        // The event was flushed into a payload but never reported
+        assert_eq!(eq.events_flushed.load(Ordering::Relaxed), 1);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 0);
+
+        // The pending payload is sent again on the next flush, without being counted twice
+        assert!(eq.flush_events().await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert_eq!(eq.pending_payloads.lock().await.len(), 1);
+        assert_eq!(eq.events_flushed.load(Ordering::Relaxed), 1);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 0);
+        assert_eq!(eq.events_dropped.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_caps_pending_payloads() {
+        let (base_uri, requests) = start_events_api(503).await;
+        let sdk_key = "test_flush_retry_cap";
+        setup_platform_data(sdk_key);
+        let options = EventQueueOptions {
+            events_api_base_uri: base_uri,
+            flush_events_batch_size: 1,
+            max_event_queue_size: 2,
+            ..Default::default()
+        };
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+
+        for key in ["var-a", "var-b", "var-c"] {
+            eq.queue_variable_defaulted_event(key, "", "").unwrap();
+        }
+        assert!(eq.flush_events().await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+        assert_eq!(eq.pending_payloads.lock().await.len(), 2);
+        assert_eq!(eq.events_dropped.load(Ordering::Relaxed), 1);
+
+        // Failed retries keep the newest payloads within the cap
+        eq.queue_variable_defaulted_event("var-d", "", "").unwrap();
+        assert!(eq.flush_events().await.is_err());
+        assert_eq!(eq.pending_payloads.lock().await.len(), 2);
+        assert_eq!(eq.events_dropped.load(Ordering::Relaxed), 2);
+        assert_eq!(eq.events_flushed.load(Ordering::Relaxed), 4);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_drops_on_client_errors() {
+        let (base_uri, _requests) = start_events_api(400).await;
+        let eq = create_event_queue("test_flush_client_error", &base_uri, 100);
+        eq.queue_variable_defaulted_event("var-a", "", "").unwrap();
+
+        assert!(eq.flush_events().await.is_err());
+        assert!(eq.pending_payloads.lock().await.is_empty());
+        assert_eq!(eq.events_dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_process_events_flushes_on_interval() {
+        let (base_uri, requests) = start_events_api(201).await;
+        let sdk_key = "test_flush_interval";
+        setup_platform_data(sdk_key);
+        let options = EventQueueOptions {
+            flush_events_interval: Duration::from_millis(50),
+            events_api_base_uri: base_uri,
+            ..Default::default()
+        };
+        let eq = Arc::new(
+            EventQueue::new(
+                &client_manager::get_or_create_client(sdk_key).state,
+                options,
+            )
+            .unwrap(),
+        );
+        eq.start_processing();
+        eq.queue_variable_evaluated_event(
+            "var-a",
+            "feature-id",
+            "variation-id",
+            EvaluationReason::Split,
+        )
+        .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        eq.shutdown();
+
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 1);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+}
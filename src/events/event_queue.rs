@@ -1,18 +1,24 @@
+use crate::bucketing::generate_bucketed_config_from_config;
+use crate::client::ClientState;
 use crate::config::ConfigBody;
-use crate::config::client_custom_data::get_client_custom_data;
 use crate::config::platform_data::PlatformData;
-use crate::errors::DevCycleError;
+use crate::errors::{self, DevCycleError};
 use crate::events::event::*;
-use crate::generate_bucketed_config;
+use crate::events::event_flush::{self, FlushPayload};
+use crate::events::event_sink::{EventSink, HttpEventSink, PublishResult};
 use crate::user::{PopulatedUser, User};
+use chrono::Utc;
 use std::collections::HashMap;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Weak};
 use std::time::Duration;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
 
 #[derive(Clone)]
 pub struct EventQueueOptions {
+    // Where flushed batches are sent, defaults to the events API at `events_api_base_uri`
+    pub event_sink: Option<Arc<dyn EventSink>>,
     pub flush_events_interval: Duration,
     pub disable_automatic_event_logging: bool,
     pub disable_custom_event_logging: bool,
@@ -24,8 +30,8 @@ pub struct EventQueueOptions {
 }
 
 impl EventQueueOptions {
-    pub fn is_event_logging_disabled(&self, event_type: *const EventType) -> bool {
-        if event_type == &EventType::CustomEvent {
+    pub fn is_event_logging_disabled(&self, event_type: &EventType) -> bool {
+        if *event_type == EventType::CustomEvent {
             return self.disable_custom_event_logging;
         }
         return self.disable_automatic_event_logging;
@@ -35,6 +41,7 @@ impl EventQueueOptions {
 impl Default for EventQueueOptions {
     fn default() -> Self {
         EventQueueOptions {
+            event_sink: None,
             flush_events_interval: Duration::from_secs(60),
             disable_automatic_event_logging: false,
             disable_custom_event_logging: false,
@@ -51,36 +58,96 @@ pub struct EventQueue {
     pub(crate) sdk_key: String,
     pub(crate) platform_data: Arc<PlatformData>,
     pub(crate) agg_event_queue_raw_tx: mpsc::Sender<AggEventQueueRawMessage>,
-    pub(crate) agg_event_queue_raw_rx: mpsc::Receiver<AggEventQueueRawMessage>,
+    pub(crate) agg_event_queue_raw_rx: Mutex<mpsc::Receiver<AggEventQueueRawMessage>>,
     pub(crate) user_event_queue_raw_tx: mpsc::Sender<UserEventData>,
-    pub(crate) user_event_queue_raw_rx: mpsc::Receiver<UserEventData>,
-    pub(crate) agg_event_queue: AggregateEventQueue,
+    pub(crate) user_event_queue_raw_rx: Mutex<mpsc::Receiver<UserEventData>>,
+    pub(crate) agg_event_queue: Mutex<AggregateEventQueue>,
     pub(crate) user_event_queue: Mutex<UserEventQueue>, // changed to Mutex for interior mutability
     pub(crate) user_event_queue_count: AtomicI64,
     pub(crate) queue_access_mutex: tokio::sync::Mutex<()>,
+    // Events drained from the queues into payloads, whether or not they were sent yet
     pub(crate) events_flushed: AtomicI64,
     pub(crate) events_dropped: AtomicI64,
+    // Events accepted by the sink
     pub(crate) events_reported: AtomicI64,
     pub(crate) options: EventQueueOptions,
+    // Payloads that failed with a retryable error, sent again on the next flush
+    pub(crate) pending_payloads: Mutex<Vec<FlushPayload>>,
+    pub(crate) event_sink: Arc<dyn EventSink>,
+    pub(crate) shutdown_tx: watch::Sender<bool>,
+    // Flushes requested while the background task is running are run by the task, which owns
+    // the raw receivers while it waits for events
+    pub(crate) flush_request_tx: mpsc::Sender<FlushReply>,
+    pub(crate) flush_request_rx: Mutex<mpsc::Receiver<FlushReply>>,
+    pub(crate) processing_task: std::sync::Mutex<Option<JoinHandle<()>>>,
+    // The client whose config and custom data are used to bucket users of custom events
+    pub(crate) client: Weak<ClientState>,
+}
+
+/// Handle to an event queue started by `init_event_queue`, used to stop its background
+/// processing task.
+#[derive(Clone)]
+pub struct EventQueueHandle {
+    pub(crate) event_queue: Arc<EventQueue>,
+}
+
+impl EventQueueHandle {
+    pub fn sdk_key(&self) -> &str {
+        &self.event_queue.sdk_key
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.event_queue.is_processing()
+    }
+
+    /// Stop the background processing task. Queued events are kept until `close` is called.
+    pub fn shutdown(&self) {
+        self.event_queue.shutdown();
+    }
+
+    /// Stop processing, drain the queued events and publish them in a final flush.
+    pub async fn close(&self) -> Result<(), DevCycleError> {
+        if let Some(client) = self.event_queue.client.upgrade() {
+            client.remove_event_queue_if_same(&self.event_queue);
+        }
+        self.event_queue.close().await.map(|_| ())
+    }
+}
+
+type FlushReply = oneshot::Sender<Result<i64, DevCycleError>>;
+
+enum ProcessStep {
+    Shutdown,
+    Flush,
+    FlushRequest(FlushReply),
+    UserEvent(Box<UserEventData>),
+    AggregateEvent(AggEventQueueRawMessage),
 }
 
 impl EventQueue {
-    pub fn new(
-        sdk_key: String,
+    pub(crate) fn new(
+        client: &Arc<ClientState>,
         event_queue_options: EventQueueOptions,
     ) -> Result<Self, DevCycleError> {
+        let sdk_key = client.sdk_key.clone();
         let (agg_event_queue_raw_tx, agg_event_queue_raw_rx) = mpsc::channel(10000);
         let (user_event_queue_raw_tx, user_event_queue_raw_rx) = mpsc::channel(10000);
-        let platform_data = crate::config::platform_data::get_platform_data(&sdk_key)
-            .map_err(|e| DevCycleError::new(&e))?;
+        let (flush_request_tx, flush_request_rx) = mpsc::channel(16);
+        let platform_data = client.get_platform_data()?;
+        let event_sink = event_queue_options.event_sink.clone().unwrap_or_else(|| {
+            Arc::new(HttpEventSink::new(
+                &event_queue_options.events_api_base_uri,
+                &sdk_key,
+            ))
+        });
         Ok(Self {
             sdk_key,
             platform_data,
             agg_event_queue_raw_tx,
             user_event_queue_raw_tx,
-            agg_event_queue_raw_rx,
-            user_event_queue_raw_rx,
-            agg_event_queue: HashMap::new(),
+            agg_event_queue_raw_rx: Mutex::new(agg_event_queue_raw_rx),
+            user_event_queue_raw_rx: Mutex::new(user_event_queue_raw_rx),
+            agg_event_queue: Mutex::new(HashMap::new()),
             user_event_queue: Mutex::new(HashMap::new()), // wrap in Mutex
             user_event_queue_count: AtomicI64::new(0),
             queue_access_mutex: tokio::sync::Mutex::new(()),
@@ -88,45 +155,48 @@ impl EventQueue {
             events_dropped: AtomicI64::new(0),
             events_reported: AtomicI64::new(0),
             options: event_queue_options,
+            pending_payloads: Mutex::new(Vec::new()),
+            event_sink,
+            shutdown_tx: watch::channel(false).0,
+            flush_request_tx,
+            flush_request_rx: Mutex::new(flush_request_rx),
+            processing_task: std::sync::Mutex::new(None),
+            client: Arc::downgrade(client),
         })
     }
 
-    pub async fn queue_variable_evaluated_event(
+    pub fn queue_variable_evaluated_event(
         &self,
         variable_key: &str,
         feature_id: &str,
         variation_id: &str,
         eval_reason: EvaluationReason,
     ) -> Result<bool, DevCycleError> {
-        return self
-            .queue_aggregate_event_internal(
-                variable_key,
-                feature_id,
-                variation_id,
-                EventType::AggregateVariableEvaluated,
-                eval_reason,
-            )
-            .await;
+        return self.queue_aggregate_event_internal(
+            variable_key,
+            feature_id,
+            variation_id,
+            EventType::AggregateVariableEvaluated,
+            eval_reason,
+        );
     }
 
-    pub async fn queue_variable_defaulted_event(
+    pub fn queue_variable_defaulted_event(
         &self,
         variable_key: &str,
         feature_id: &str,
         variation_id: &str,
     ) -> Result<bool, DevCycleError> {
-        return self
-            .queue_aggregate_event_internal(
-                variable_key,
-                feature_id,
-                variation_id,
-                EventType::AggregateVariableDefaulted,
-                EvaluationReason::Default,
-            )
-            .await;
+        return self.queue_aggregate_event_internal(
+            variable_key,
+            feature_id,
+            variation_id,
+            EventType::AggregateVariableDefaulted,
+            EvaluationReason::Default,
+        );
     }
 
-    async fn queue_aggregate_event_internal(
+    fn queue_aggregate_event_internal(
         &self,
         variable_key: &str,
         feature_id: &str,
@@ -138,7 +208,7 @@ impl EventQueue {
             return Ok(false);
         }
         if variable_key.is_empty() {
-            return Err(DevCycleError::new(
+            return Err(errors::invalid_event(
                 "a variable key is required for aggregate events",
             ));
         }
@@ -161,63 +231,95 @@ impl EventQueue {
 
         if success.is_err() {
             self.events_dropped.fetch_add(1, Ordering::Relaxed);
-            return Err(DevCycleError::new(&format!(
-                "dropping event, queue is full: {}",
-                success.unwrap_err()
-            )));
+            return Err(errors::event_queue_full(success.unwrap_err().to_string()));
         }
         return Ok(true);
     }
 
-    pub async fn queue_event(&self, user: User, event: Event) -> Result<bool, DevCycleError> {
+    pub(crate) fn queue_custom_event(
+        &self,
+        user: User,
+        custom_event: CustomEvent,
+    ) -> Result<bool, DevCycleError> {
+        if self
+            .options
+            .is_event_logging_disabled(&EventType::CustomEvent)
+        {
+            return Ok(false);
+        }
+        if user.user_id.is_empty() {
+            return Err(errors::invalid_event(
+                "a user id is required to track events",
+            ));
+        }
+        if custom_event.custom_type.is_empty() {
+            return Err(errors::invalid_event(
+                "a type is required for custom events",
+            ));
+        }
+        if custom_event.value.is_some_and(|value| !value.is_finite()) {
+            return Err(errors::invalid_event(
+                "custom event values must be finite numbers",
+            ));
+        }
+
+        let event = Event {
+            event_type: EventType::CustomEvent,
+            target: custom_event.target,
+            custom_type: custom_event.custom_type,
+            user_id: user.user_id.clone(),
+            client_date: custom_event.date.unwrap_or_else(Utc::now),
+            value: custom_event.value.unwrap_or_default(),
+            feature_vars: HashMap::new(),
+            meta_data: custom_event.metadata,
+        };
+        self.queue_event(user, event)
+    }
+
+    pub fn queue_event(&self, user: User, event: Event) -> Result<bool, DevCycleError> {
         let success = self
             .user_event_queue_raw_tx
             .try_send(UserEventData { user, event });
 
         if success.is_err() {
             self.events_dropped.fetch_add(1, Ordering::Relaxed);
-            return Err(DevCycleError::new(&format!(
-                "dropping event, queue is full: {}",
-                success.unwrap_err()
-            )));
+            return Err(errors::event_queue_full(success.unwrap_err().to_string()));
         }
         return Ok(true);
     }
 
-    pub(crate) async fn merge_agg_event_queue_keys(&mut self, config_body: &ConfigBody) {
+    pub(crate) async fn merge_agg_event_queue_keys(&self, config_body: &ConfigBody) {
         let _guard = self.queue_access_mutex.lock().await;
+        let mut agg_event_queue = self.agg_event_queue.lock().await;
         for event_type in [
             EventType::AggregateVariableDefaulted,
             EventType::AggregateVariableEvaluated,
         ] {
-            if !self.agg_event_queue.contains_key(&event_type) {
-                self.agg_event_queue
-                    .insert(event_type.clone(), HashMap::new());
+            if !agg_event_queue.contains_key(&event_type) {
+                agg_event_queue.insert(event_type.clone(), HashMap::new());
             }
 
             for variable in config_body.variables.iter() {
-                if !self
-                    .agg_event_queue
+                if !agg_event_queue
                     .get(&event_type)
                     .unwrap()
                     .contains_key(&variable.key)
                 {
-                    self.agg_event_queue
+                    agg_event_queue
                         .get_mut(&event_type)
                         .unwrap()
                         .insert(variable.key.clone(), HashMap::new());
                 }
 
                 for feature in config_body.features.iter() {
-                    if !self
-                        .agg_event_queue
+                    if !agg_event_queue
                         .get(&event_type)
                         .unwrap()
                         .get(&variable.key)
                         .unwrap()
                         .contains_key(&feature.key)
                     {
-                        self.agg_event_queue
+                        agg_event_queue
                             .get_mut(&event_type)
                             .unwrap()
                             .get_mut(&variable.key)
@@ -226,8 +328,7 @@ impl EventQueue {
                     }
 
                     for variation in feature.variations.iter() {
-                        if !self
-                            .agg_event_queue
+                        if !agg_event_queue
                             .get(&event_type)
                             .unwrap()
                             .get(&variable.key)
@@ -236,7 +337,7 @@ impl EventQueue {
                             .unwrap()
                             .contains_key(&variation._id)
                         {
-                            self.agg_event_queue
+                            agg_event_queue
                                 .get_mut(&event_type)
                                 .unwrap()
                                 .get_mut(&variable.key)
@@ -252,9 +353,9 @@ impl EventQueue {
                             EvaluationReason::Default,
                             EvaluationReason::Disabled,
                             EvaluationReason::Error,
+                            EvaluationReason::Override,
                         ] {
-                            if !self
-                                .agg_event_queue
+                            if !agg_event_queue
                                 .get(&event_type)
                                 .unwrap()
                                 .get(&variable.key)
@@ -265,7 +366,7 @@ impl EventQueue {
                                 .unwrap()
                                 .contains_key(&reason)
                             {
-                                self.agg_event_queue
+                                agg_event_queue
                                     .get_mut(&event_type)
                                     .unwrap()
                                     .get_mut(&variable.key)
@@ -284,19 +385,30 @@ impl EventQueue {
     }
 
     async unsafe fn process_user_events(
-        &mut self,
+        &self,
         mut event: UserEventData,
     ) -> Result<bool, DevCycleError> {
-        let client_custom_data = get_client_custom_data(self.sdk_key.clone());
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => {
+                return Err(DevCycleError::ClientDropped);
+            }
+        };
+        let client_custom_data = client.get_client_custom_data();
 
         let populated_user = PopulatedUser::new(
             event.user.clone(),
             self.platform_data.clone(),
             client_custom_data.clone(),
         );
-        let bucketed_config =
-            generate_bucketed_config(&self.sdk_key, populated_user.clone(), client_custom_data)
-                .await;
+        let config = client.get_config().ok_or(errors::missing_config())?;
+        let bucketed_config = generate_bucketed_config_from_config(
+            &config,
+            populated_user.clone(),
+            client_custom_data,
+            client.get_opt_in_store().as_ref(),
+            client.now(),
+        );
         if bucketed_config.is_err() {
             return Err(bucketed_config.err().unwrap());
         }
@@ -329,10 +441,11 @@ impl EventQueue {
     }
 
     pub(crate) async fn process_aggregate_event(
-        &mut self,
+        &self,
         agg_event_queue_raw_message: AggEventQueueRawMessage,
     ) {
         let _guard = self.queue_access_mutex.lock().await;
+        let mut agg_event_queue = self.agg_event_queue.lock().await;
 
         let event_type = agg_event_queue_raw_message.event_type.clone();
         let variable_key = agg_event_queue_raw_message.variable_key;
@@ -342,8 +455,7 @@ impl EventQueue {
 
         if event_type == EventType::AggregateVariableEvaluated {
             // Get or clone the nested structure and update counts directly
-            let eval_reasons = self
-                .agg_event_queue
+            let eval_reasons = agg_event_queue
                 .entry(event_type)
                 .or_insert_with(HashMap::new)
                 .entry(variable_key)
@@ -360,8 +472,7 @@ impl EventQueue {
             // For defaulted events, use "default" as both feature_id and variation_id keys
             let default_key = "default".to_string();
 
-            let default_reasons = self
-                .agg_event_queue
+            let default_reasons = agg_event_queue
                 .entry(event_type)
                 .or_insert_with(HashMap::new)
                 .entry(variable_key)
@@ -377,39 +488,248 @@ impl EventQueue {
         }
     }
 
-    pub(crate) async fn process_events(
-        &mut self,
-        mut shutdown: tokio::sync::watch::Receiver<bool>,
-    ) {
+    // Events that can't be bucketed, such as when there is no config, are dropped and counted
+    async fn queue_user_event(&self, event: UserEventData) -> Result<(), DevCycleError> {
+        if let Err(e) = unsafe { self.process_user_events(event).await } {
+            self.events_dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Move everything waiting in the raw channels into the aggregate and user queues, returning
+    // why any user events were dropped. Only called when the background task isn't waiting on
+    // the receivers, either from the task itself or with no task running.
+    async fn drain_raw_queues(&self) -> Vec<String> {
+        {
+            let mut agg_rx = self.agg_event_queue_raw_rx.lock().await;
+            while let Ok(event) = agg_rx.try_recv() {
+                self.process_aggregate_event(event).await;
+            }
+        }
+        let mut errors = Vec::new();
+        let mut user_rx = self.user_event_queue_raw_rx.lock().await;
+        while let Ok(event) = user_rx.try_recv() {
+            if let Err(e) = self.queue_user_event(event).await {
+                errors.push(format!("Dropped user event: {}", e));
+            }
+        }
+        errors
+    }
+
+    // Flushes on the background task while it's running, otherwise flushes directly. Returns
+    // the number of events reported.
+    pub(crate) async fn flush_events(&self) -> Result<i64, DevCycleError> {
+        if self.is_processing() {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            if self.flush_request_tx.send(reply_tx).await.is_ok()
+                && let Ok(result) = reply_rx.await
+            {
+                return result;
+            }
+            // The task stopped before running the flush, so nothing holds the receivers now
+        }
+        self.flush_queued_events().await
+    }
+
+    // Builds payloads from the queued events and publishes them, along with any payloads
+    // left over from a previous failed flush. Returns the number of events reported.
+    async fn flush_queued_events(&self) -> Result<i64, DevCycleError> {
+        let mut errors = self.drain_raw_queues().await;
+        // Held for the whole flush so concurrent flushes publish each payload only once
+        let mut pending_payloads = self.pending_payloads.lock().await;
+
+        let mut records: Vec<UserEventsBatchRecord> = Vec::new();
+        {
+            let _guard = self.queue_access_mutex.lock().await;
+            let platform_user = PopulatedUser::new(
+                User {
+                    user_id: self.platform_data.hostname.clone(),
+                    email: String::new(),
+                    name: String::new(),
+                    language: String::new(),
+                    country: String::new(),
+                    ip: String::new(),
+                    app_version: String::new(),
+                    app_build: String::new(),
+                    custom_data: HashMap::new(),
+                    private_custom_data: HashMap::new(),
+                    device_model: String::new(),
+                    last_seen_date: chrono::Utc::now(),
+                },
+                self.platform_data.clone(),
+                HashMap::new(),
+            );
+            let mut agg_event_queue = self.agg_event_queue.lock().await;
+            if let Some(record) =
+                event_flush::generate_aggregate_event_record(&agg_event_queue, platform_user)
+            {
+                records.push(record);
+            }
+            agg_event_queue.clear();
+
+            let mut user_queue = self.user_event_queue.lock().await;
+            for (_, mut record) in user_queue.drain() {
+                // Private custom data is only used for bucketing and never sent to DevCycle
+                record.user.private_custom_data.clear();
+                records.push(record);
+            }
+            self.user_event_queue_count.store(0, Ordering::Relaxed);
+        }
+
+        let batch_size = self.options.flush_events_batch_size.max(1) as usize;
+        let payloads = event_flush::construct_flush_payloads(records, batch_size);
+        self.events_flushed.fetch_add(
+            payloads.iter().map(|payload| payload.event_count).sum(),
+            Ordering::Relaxed,
+        );
+        pending_payloads.extend(payloads);
+
+        let mut reported = 0;
+        for payload in std::mem::take(&mut *pending_payloads) {
+            let batch = match payload.to_event_batch() {
+                Ok(batch) => batch,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    self.events_dropped
+                        .fetch_add(payload.event_count, Ordering::Relaxed);
+                    continue;
+                }
+            };
+            match self.event_sink.publish(&batch).await {
+                PublishResult::Success => {
+                    reported += payload.event_count;
+                    self.events_reported
+                        .fetch_add(payload.event_count, Ordering::Relaxed);
+                }
+                PublishResult::RetryableFailure(e) => {
+                    errors.push(e);
+                    pending_payloads.push(payload);
+                }
+                PublishResult::Failure(e) => {
+                    errors.push(e);
+                    self.events_dropped
+                        .fetch_add(payload.event_count, Ordering::Relaxed);
+                }
+            }
+        }
+
+        self.drop_oldest_pending_payloads(&mut pending_payloads);
+
+        if !errors.is_empty() {
+            return Err(DevCycleError::EventFlushFailed(errors));
+        }
+        Ok(reported)
+    }
+
+    // Keeps the events waiting for a retry within `max_event_queue_size`, dropping the oldest
+    // payloads first so an unreachable events API can't grow the queue without bound
+    fn drop_oldest_pending_payloads(&self, pending_payloads: &mut Vec<FlushPayload>) {
+        let max_events = self.options.max_event_queue_size.max(0) as i64;
+        let mut pending_events: i64 = pending_payloads.iter().map(|p| p.event_count).sum();
+        let mut dropped = 0;
+        while pending_events > max_events && dropped < pending_payloads.len() {
+            pending_events -= pending_payloads[dropped].event_count;
+            self.events_dropped
+                .fetch_add(pending_payloads[dropped].event_count, Ordering::Relaxed);
+            dropped += 1;
+        }
+        pending_payloads.drain(..dropped);
+    }
+
+    pub(crate) async fn process_events(&self, mut shutdown: watch::Receiver<bool>) {
+        let flush_period = self
+            .options
+            .flush_events_interval
+            .max(Duration::from_millis(1));
+        let mut flush_interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + flush_period, flush_period);
         loop {
-            tokio::select! {
-                _ = shutdown.changed() => {
-                    // Context is cancelled, exit the loop
-                    return;
+            if *shutdown.borrow() {
+                return;
+            }
+            // The receiver locks are released before handling the step so flushes can drain them
+            let step = {
+                let mut agg_rx = self.agg_event_queue_raw_rx.lock().await;
+                let mut user_rx = self.user_event_queue_raw_rx.lock().await;
+                let mut flush_rx = self.flush_request_rx.lock().await;
+                tokio::select! {
+                    _ = shutdown.changed() => ProcessStep::Shutdown,
+                    _ = flush_interval.tick() => ProcessStep::Flush,
+                    Some(reply) = flush_rx.recv() => ProcessStep::FlushRequest(reply),
+                    user_event = user_rx.recv() => match user_event {
+                        Some(event) => ProcessStep::UserEvent(Box::new(event)),
+                        // Channel closed
+                        None => ProcessStep::Shutdown,
+                    },
+                    agg_event = agg_rx.recv() => match agg_event {
+                        Some(event) => ProcessStep::AggregateEvent(event),
+                        // Channel closed
+                        None => ProcessStep::Shutdown,
+                    },
                 }
-                user_event = self.user_event_queue_raw_rx.recv() => {
-                    match user_event {
-                        Some(event) => unsafe{
-                            let _ = self.process_user_events(event).await;
-                        }
-                        None => {
-                            // Channel closed
-                            return;
-                        }
+            };
+            match step {
+                ProcessStep::Shutdown => return,
+                ProcessStep::Flush => {
+                    if let Err(e) = self.flush_queued_events().await {
+                        eprintln!("Failed to flush events: {}", e);
                     }
                 }
-                agg_event = self.agg_event_queue_raw_rx.recv() => {
-                    match agg_event {
-                        Some(event) => {
-                            self.process_aggregate_event(event).await;
-                        }
-                        None => {
-                            // Channel closed
-                            return;
-                        }
+                ProcessStep::FlushRequest(reply) => {
+                    let _ = reply.send(self.flush_queued_events().await);
+                }
+                ProcessStep::UserEvent(event) => {
+                    if let Err(e) = self.queue_user_event(*event).await {
+                        eprintln!("Dropped user event: {}", e);
                     }
                 }
+                ProcessStep::AggregateEvent(event) => {
+                    self.process_aggregate_event(event).await;
+                }
             }
         }
     }
+
+    // Spawn the background task that processes queued events and flushes them on the interval
+    pub(crate) fn start_processing(self: &Arc<Self>) {
+        // There is no runtime with timers to spawn onto under WASM, events are processed when
+        // the host calls `flush_events` or `close`
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let event_queue = self.clone();
+            let shutdown = self.shutdown_tx.subscribe();
+            let task = tokio::spawn(async move { event_queue.process_events(shutdown).await });
+            *self
+                .processing_task
+                .lock()
+                .expect("Failed to acquire lock on processing task: lock poisoned") = Some(task);
+        }
+    }
+
+    pub(crate) fn is_processing(&self) -> bool {
+        self.processing_task
+            .lock()
+            .expect("Failed to acquire lock on processing task: lock poisoned")
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.shutdown_tx.send_replace(true);
+    }
+
+    // Stop the background task, then drain the raw channels and publish everything queued
+    pub(crate) async fn close(&self) -> Result<i64, DevCycleError> {
+        self.shutdown();
+        let task = self
+            .processing_task
+            .lock()
+            .expect("Failed to acquire lock on processing task: lock poisoned")
+            .take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+        self.flush_queued_events().await
+    }
 }
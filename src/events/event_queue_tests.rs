@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    use crate::client::client_manager;
     use crate::config::platform_data;
     use crate::config::platform_data::PlatformData;
     use crate::config::*;
@@ -7,9 +8,11 @@ mod tests {
     use crate::events::event::*;
     use crate::events::event_queue::{EventQueue, EventQueueOptions};
     use crate::events::event_queue_manager;
+    use crate::events::event_sink::InMemoryEventSink;
     use crate::user::User;
     use chrono::Utc;
     use std::collections::HashMap;
+    use std::sync::Arc;
     use std::sync::atomic::Ordering;
     use std::time::Duration;
     use tokio::time::sleep;
@@ -101,6 +104,7 @@ mod tests {
             name: String::new(),
             language: String::new(),
             country: String::new(),
+            ip: String::new(),
             app_version: String::new(),
             app_build: String::new(),
             custom_data: HashMap::new(),
@@ -116,7 +120,7 @@ mod tests {
             target: target.to_string(),
             custom_type: "testingtype".to_string(),
             user_id: "testing".to_string(),
-            client_date: std::time::Instant::now(),
+            client_date: Utc::now(),
             value: 0.0,
             feature_vars: HashMap::new(),
             meta_data: HashMap::new(),
@@ -135,6 +139,12 @@ mod tests {
         platform_data::set_platform_data(sdk_key.to_string(), platform_data);
     }
 
+    fn register_event_queue(sdk_key: &str, eq: EventQueue) {
+        client_manager::get_or_create_client(sdk_key)
+            .state
+            .set_event_queue(Arc::new(eq));
+    }
+
     #[tokio::test]
     async fn test_event_queue_merge_agg_event_queue_keys() {
         let sdk_key = "test_merge_agg_keys";
@@ -145,8 +155,12 @@ mod tests {
 
         let config_ref = get_config(sdk_key).unwrap();
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
@@ -165,8 +179,12 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
         // Test passes if queue exists
@@ -182,8 +200,12 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
@@ -193,9 +215,7 @@ mod tests {
         };
 
         // Queue the event using the shared queue
-        let result = event_queue
-            .queue_event(event_data.user, event_data.event)
-            .await;
+        let result = event_queue.queue_event(event_data.user, event_data.event);
         assert!(result.is_ok());
     }
 
@@ -208,8 +228,12 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         // Test that queue is accessible
         assert!(event_queue_manager::get_event_queue(sdk_key).is_some());
@@ -224,15 +248,19 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
         let user = create_test_user("testing");
         let event = create_test_event("somevariablekey");
 
-        let result = event_queue.queue_event(user, event).await;
+        let result = event_queue.queue_event(user, event);
         assert!(result.is_ok());
     }
 
@@ -248,19 +276,21 @@ mod tests {
             flush_events_interval: Duration::from_secs(3600),
             ..Default::default()
         };
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
-        let result = event_queue
-            .queue_variable_evaluated_event(
-                "somevariablekey",
-                "featureId",
-                "variationId",
-                EvaluationReason::TargetingMatch,
-            )
-            .await;
+        let result = event_queue.queue_variable_evaluated_event(
+            "somevariablekey",
+            "featureId",
+            "variationId",
+            EvaluationReason::TargetingMatch,
+        );
 
         assert!(result.is_ok());
         assert!(result.unwrap());
@@ -284,14 +314,18 @@ mod tests {
             ..Default::default()
         };
 
-        let mut eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
+        let mut eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
 
         // Replace the user event queue with a channel that can only hold 3 events
         let (tx, rx) = tokio::sync::mpsc::channel(3);
         eq.user_event_queue_raw_tx = tx;
-        eq.user_event_queue_raw_rx = rx;
+        eq.user_event_queue_raw_rx = tokio::sync::Mutex::new(rx);
 
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        register_event_queue(sdk_key, eq);
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
         let user = create_test_user("testing");
@@ -299,7 +333,7 @@ mod tests {
 
         for i in 0..=3 {
             let event = create_test_event(&format!("somevariablekey{}", i));
-            let result = event_queue.queue_event(user.clone(), event).await;
+            let result = event_queue.queue_event(user.clone(), event);
 
             // The first 3 sends should succeed, the 4th should fail
             if result.is_err() {
@@ -331,8 +365,12 @@ mod tests {
             flush_events_interval: Duration::from_secs(3600),
             ..Default::default()
         };
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
@@ -341,7 +379,7 @@ mod tests {
             let user = create_test_user(&format!("testing{}", i));
             let event = create_test_event(&format!("somevariablekey{}", i));
 
-            let result = event_queue.queue_event(user, event).await;
+            let result = event_queue.queue_event(user, event);
             if result.is_err() {
                 has_errored = true;
                 break;
@@ -365,7 +403,10 @@ mod tests {
         setup_platform_data(sdk_key);
 
         let options = EventQueueOptions::default();
-        let result = EventQueue::new(sdk_key.to_string(), options);
+        let result = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        );
 
         assert!(result.is_ok());
         let eq = result.unwrap();
@@ -401,19 +442,21 @@ mod tests {
             ..Default::default()
         };
 
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
-        let result = event_queue
-            .queue_variable_evaluated_event(
-                "somevariablekey",
-                "featureId",
-                "variationId",
-                EvaluationReason::TargetingMatch,
-            )
-            .await;
+        let result = event_queue.queue_variable_evaluated_event(
+            "somevariablekey",
+            "featureId",
+            "variationId",
+            EvaluationReason::TargetingMatch,
+        );
 
         assert!(result.is_ok());
         // Should return false when logging is disabled
@@ -429,19 +472,21 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
-        let result = event_queue
-            .queue_variable_evaluated_event(
-                "",
-                "featureId",
-                "variationId",
-                EvaluationReason::TargetingMatch,
-            )
-            .await;
+        let result = event_queue.queue_variable_evaluated_event(
+            "",
+            "featureId",
+            "variationId",
+            EvaluationReason::TargetingMatch,
+        );
 
         assert!(result.is_err());
         assert!(
@@ -461,16 +506,398 @@ mod tests {
         set_config(sdk_key, config);
 
         let options = EventQueueOptions::default();
-        let eq = EventQueue::new(sdk_key.to_string(), options).unwrap();
-        event_queue_manager::set_event_queue(sdk_key, eq);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        register_event_queue(sdk_key, eq);
 
         let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
 
-        let result = event_queue
-            .queue_variable_defaulted_event("somevariablekey", "featureId", "variationId")
-            .await;
+        let result = event_queue.queue_variable_defaulted_event(
+            "somevariablekey",
+            "featureId",
+            "variationId",
+        );
 
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
+
+    fn in_memory_sink_options(sink: Arc<InMemoryEventSink>) -> EventQueueOptions {
+        EventQueueOptions {
+            event_sink: Some(sink),
+            flush_events_interval: Duration::from_secs(3600),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_event_queue_processes_events_in_background() {
+        let sdk_key = "test_background_processing";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        assert!(handle.is_running());
+        assert_eq!(handle.sdk_key(), sdk_key);
+
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        event_queue
+            .queue_variable_evaluated_event(
+                "test_variable",
+                "feature_123",
+                "variation_123",
+                EvaluationReason::TargetingMatch,
+            )
+            .unwrap();
+        event_queue
+            .queue_event(create_test_user("testing"), create_test_event("custom"))
+            .unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        // The background task moved the events out of the raw channels without flushing
+        assert_eq!(event_queue.agg_event_queue.lock().await.len(), 1);
+        assert_eq!(
+            event_queue.user_event_queue_count.load(Ordering::Relaxed),
+            1
+        );
+        assert!(sink.batches().is_empty());
+
+        handle.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_and_flushes() {
+        let sdk_key = "test_close_drains_and_flushes";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        for i in 0..3 {
+            event_queue
+                .queue_variable_defaulted_event(&format!("variable_{}", i), "", "")
+                .unwrap();
+        }
+        event_queue
+            .queue_event(create_test_user("testing"), create_test_event("custom"))
+            .unwrap();
+
+        crate::close(sdk_key).await.unwrap();
+
+        assert!(!handle.is_running());
+        assert!(event_queue_manager::get_event_queue(sdk_key).is_none());
+        assert_eq!(sink.event_count(), 4);
+        assert_eq!(event_queue.events_reported.load(Ordering::Relaxed), 4);
+        assert!(event_queue.agg_event_queue.lock().await.is_empty());
+        assert!(event_queue.user_event_queue.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_while_background_task_is_running() {
+        let sdk_key = "test_flush_while_running";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        // Let the task settle into waiting on the raw channels
+        sleep(Duration::from_millis(50)).await;
+
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        event_queue
+            .queue_variable_defaulted_event("test_variable", "", "")
+            .unwrap();
+        event_queue
+            .queue_event(create_test_user("testing"), create_test_event("custom"))
+            .unwrap();
+
+        assert_eq!(crate::flush_events(sdk_key).await.unwrap(), 2);
+        assert!(handle.is_running());
+        assert_eq!(sink.event_count(), 2);
+        handle.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_keeps_events_until_close() {
+        let sdk_key = "test_shutdown_keeps_events";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        handle.shutdown();
+        sleep(Duration::from_millis(50)).await;
+        assert!(!handle.is_running());
+
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        event_queue
+            .queue_variable_defaulted_event("test_variable", "", "")
+            .unwrap();
+        assert!(sink.batches().is_empty());
+
+        handle.close().await.unwrap();
+        assert_eq!(sink.event_count(), 1);
+        assert!(event_queue_manager::get_event_queue(sdk_key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_flush_events_without_background_task() {
+        let sdk_key = "test_flush_without_background_task";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        // Nothing reads the raw channels, as under WASM
+        handle.shutdown();
+        sleep(Duration::from_millis(50)).await;
+
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        event_queue
+            .queue_variable_defaulted_event("test_variable", "", "")
+            .unwrap();
+        crate::track(
+            sdk_key,
+            create_test_user("flush_user"),
+            CustomEvent {
+                custom_type: "purchase".to_string(),
+                target: String::new(),
+                value: None,
+                metadata: HashMap::new(),
+                date: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(crate::flush_events(sdk_key).await.unwrap(), 2);
+        assert_eq!(sink.event_count(), 2);
+        // The queue stays registered and keeps accepting events
+        assert_eq!(crate::flush_events(sdk_key).await.unwrap(), 0);
+        event_queue
+            .queue_variable_defaulted_event("test_variable", "", "")
+            .unwrap();
+        handle.close().await.unwrap();
+        assert_eq!(sink.event_count(), 3);
+        assert!(crate::flush_events(sdk_key).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_flush_counts_user_events_dropped_without_config() {
+        let sdk_key = "test_flush_drops_without_config";
+        setup_platform_data(sdk_key);
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+        handle.shutdown();
+        sleep(Duration::from_millis(50)).await;
+
+        let event_queue = event_queue_manager::get_event_queue(sdk_key).unwrap();
+        event_queue
+            .queue_event(
+                create_test_user("no_config_user"),
+                create_test_event("somevariablekey"),
+            )
+            .unwrap();
+
+        // Nothing can be bucketed without a config, so the flush isn't clean
+        assert!(crate::flush_events(sdk_key).await.is_err());
+        assert_eq!(event_queue.events_dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(sink.event_count(), 0);
+        handle.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_init_event_queue_replaces_previous_queue() {
+        let sdk_key = "test_replace_event_queue";
+        setup_platform_data(sdk_key);
+
+        let first = crate::init_event_queue(
+            sdk_key,
+            in_memory_sink_options(Arc::new(InMemoryEventSink::new())),
+        )
+        .await
+        .unwrap();
+        let second = crate::init_event_queue(
+            sdk_key,
+            in_memory_sink_options(Arc::new(InMemoryEventSink::new())),
+        )
+        .await
+        .unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(!first.is_running());
+        assert!(second.is_running());
+        // Closing the replaced queue leaves the current one registered
+        first.close().await.unwrap();
+        assert!(event_queue_manager::get_event_queue(sdk_key).is_some());
+        second.close().await.unwrap();
+        assert!(event_queue_manager::get_event_queue(sdk_key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_without_event_queue() {
+        let result = crate::close("test_close_without_event_queue").await;
+        assert!(result.is_err());
+
+        // A client with a config but no event queue is left registered
+        let sdk_key = "test_close_keeps_client_without_event_queue";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+        assert!(matches!(
+            crate::close(sdk_key).await,
+            Err(crate::errors::DevCycleError::EventQueueNotInitialized)
+        ));
+        assert!(client_manager::get_client(sdk_key).is_some());
+        assert!(
+            crate::generate_bucketed_config_from_user(sdk_key, create_test_user("user")).is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_custom_event_is_flushed() {
+        let sdk_key = "test_track_custom_event";
+        setup_platform_data(sdk_key);
+        set_config(sdk_key, create_test_config(sdk_key));
+
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(sdk_key, in_memory_sink_options(sink.clone()))
+            .await
+            .unwrap();
+
+        let date = Utc::now() - chrono::Duration::minutes(5);
+        let mut metadata = HashMap::new();
+        metadata.insert("plan".to_string(), serde_json::json!("pro"));
+        let tracked = crate::track(
+            sdk_key,
+            create_test_user("track_user"),
+            CustomEvent {
+                custom_type: "purchase".to_string(),
+                target: "checkout".to_string(),
+                value: Some(49.99),
+                metadata,
+                date: Some(date),
+            },
+        )
+        .unwrap();
+        assert!(tracked);
+
+        handle.close().await.unwrap();
+        let batches = sink.batches();
+        assert_eq!(batches.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&batches[0].body).unwrap();
+        let record = &body["batch"][0];
+        assert_eq!(record["user"]["userId"], "track_user");
+        let event = &record["events"][0];
+        assert_eq!(event["type"], "customEvent");
+        assert_eq!(event["customType"], "purchase");
+        assert_eq!(event["target"], "checkout");
+        assert_eq!(event["user_id"], "track_user");
+        assert_eq!(event["value"], 49.99);
+        assert_eq!(event["metaData"]["plan"], "pro");
+        assert_eq!(event["clientDate"], serde_json::to_value(date).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_track_validates_input() {
+        let sdk_key = "test_track_validation";
+        setup_platform_data(sdk_key);
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            EventQueueOptions::default(),
+        )
+        .unwrap();
+        let event = CustomEvent {
+            custom_type: "purchase".to_string(),
+            ..Default::default()
+        };
+
+        assert!(
+            eq.queue_custom_event(create_test_user(""), event.clone())
+                .is_err()
+        );
+        assert!(
+            eq.queue_custom_event(create_test_user("user"), CustomEvent::default())
+                .is_err()
+        );
+        let invalid_value = CustomEvent {
+            value: Some(f64::NAN),
+            ..event.clone()
+        };
+        assert!(
+            eq.queue_custom_event(create_test_user("user"), invalid_value)
+                .is_err()
+        );
+        assert!(
+            eq.queue_custom_event(create_test_user("user"), event)
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_respects_disable_custom_event_logging() {
+        let sdk_key = "test_track_disabled";
+        setup_platform_data(sdk_key);
+        let options = EventQueueOptions {
+            disable_custom_event_logging: true,
+            ..Default::default()
+        };
+        let eq = EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap();
+        let event = CustomEvent {
+            custom_type: "purchase".to_string(),
+            ..Default::default()
+        };
+
+        let tracked = eq
+            .queue_custom_event(create_test_user("user"), event)
+            .unwrap();
+        assert!(!tracked);
+        assert_eq!(
+            eq.user_event_queue_raw_tx.max_capacity(),
+            eq.user_event_queue_raw_tx.capacity()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_without_event_queue() {
+        let event = CustomEvent {
+            custom_type: "purchase".to_string(),
+            ..Default::default()
+        };
+        let result = crate::track("test_track_no_queue", create_test_user("user"), event);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_custom_event_deserialize() {
+        let event: CustomEvent = serde_json::from_str(
+            r#"{"type":"signup","value":3,"metadata":{"source":"ads"},"date":"2024-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.custom_type, "signup");
+        assert_eq!(event.target, "");
+        assert_eq!(event.value, Some(3.0));
+        assert_eq!(event.metadata["source"], "ads");
+        assert!(event.date.is_some());
+    }
 }
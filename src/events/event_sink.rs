@@ -0,0 +1,189 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+const EVENTS_BATCH_PATH: &str = "/v1/events/batch";
+
+/// A flushed chunk of events, serialized as the events API batch request body
+/// (`{"batch": [{"user": ..., "events": [...]}]}`).
+#[derive(Clone, Debug)]
+pub struct EventBatch {
+    pub payload_id: String,
+    pub event_count: i64,
+    pub body: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PublishResult {
+    Success,
+    // The batch is kept and published again on the next flush
+    RetryableFailure(String),
+    // The batch is dropped
+    Failure(String),
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub type EventSinkFuture<'a> = Pin<Box<dyn Future<Output = PublishResult> + Send + 'a>>;
+#[cfg(target_family = "wasm")]
+pub type EventSinkFuture<'a> = Pin<Box<dyn Future<Output = PublishResult> + 'a>>;
+
+/// Destination for batches produced by the event queue flush.
+pub trait EventSink: Send + Sync {
+    fn publish<'a>(&'a self, batch: &'a EventBatch) -> EventSinkFuture<'a>;
+}
+
+/// Posts batches to the DevCycle events API. Used when no sink is configured.
+pub struct HttpEventSink {
+    client: reqwest::Client,
+    url: String,
+    sdk_key: String,
+}
+
+impl HttpEventSink {
+    pub fn new(events_api_base_uri: &str, sdk_key: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!(
+                "{}{}",
+                events_api_base_uri.trim_end_matches('/'),
+                EVENTS_BATCH_PATH
+            ),
+            sdk_key: sdk_key.to_string(),
+        }
+    }
+}
+
+impl EventSink for HttpEventSink {
+    fn publish<'a>(&'a self, batch: &'a EventBatch) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.url)
+                .header("Authorization", &self.sdk_key)
+                .header("Content-Type", "application/json")
+                .body(batch.body.clone())
+                .send()
+                .await;
+            match response {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.is_success() {
+                        PublishResult::Success
+                    } else if status.is_server_error() || status.as_u16() == 429 {
+                        PublishResult::RetryableFailure(format!("events API returned {}", status))
+                    } else {
+                        PublishResult::Failure(format!("events API returned {}", status))
+                    }
+                }
+                Err(e) => {
+                    PublishResult::RetryableFailure(format!("failed to publish events: {}", e))
+                }
+            }
+        })
+    }
+}
+
+/// Appends each batch as a single line to a newline-delimited JSON file.
+#[cfg(not(target_family = "wasm"))]
+pub struct NdjsonFileEventSink {
+    file: tokio::sync::Mutex<tokio::fs::File>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl NdjsonFileEventSink {
+    pub fn new(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            file: tokio::sync::Mutex::new(tokio::fs::File::from_std(file)),
+        })
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl EventSink for NdjsonFileEventSink {
+    fn publish<'a>(&'a self, batch: &'a EventBatch) -> EventSinkFuture<'a> {
+        use tokio::io::AsyncWriteExt;
+
+        Box::pin(async move {
+            let mut line = batch.body.clone();
+            line.push('\n');
+            let mut file = self.file.lock().await;
+            match file.write_all(line.as_bytes()).await {
+                Ok(_) => match file.flush().await {
+                    Ok(_) => PublishResult::Success,
+                    Err(e) => PublishResult::RetryableFailure(format!(
+                        "failed to flush events file: {}",
+                        e
+                    )),
+                },
+                Err(e) => {
+                    PublishResult::RetryableFailure(format!("failed to write events file: {}", e))
+                }
+            }
+        })
+    }
+}
+
+/// Keeps every published batch in memory, mainly for tests.
+#[derive(Default)]
+pub struct InMemoryEventSink {
+    batches: Mutex<Vec<EventBatch>>,
+}
+
+impl InMemoryEventSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn batches(&self) -> Vec<EventBatch> {
+        self.batches
+            .lock()
+            .expect("Failed to acquire lock on event batches: lock poisoned")
+            .clone()
+    }
+
+    pub fn event_count(&self) -> i64 {
+        self.batches
+            .lock()
+            .expect("Failed to acquire lock on event batches: lock poisoned")
+            .iter()
+            .map(|batch| batch.event_count)
+            .sum()
+    }
+}
+
+impl EventSink for InMemoryEventSink {
+    fn publish<'a>(&'a self, batch: &'a EventBatch) -> EventSinkFuture<'a> {
+        self.batches
+            .lock()
+            .expect("Failed to acquire lock on event batches: lock poisoned")
+            .push(batch.clone());
+        Box::pin(async { PublishResult::Success })
+    }
+}
+
+/// Forwards batches to a tokio channel, waiting for capacity when the channel is full.
+pub struct ChannelEventSink {
+    sender: mpsc::Sender<EventBatch>,
+}
+
+impl ChannelEventSink {
+    pub fn new(sender: mpsc::Sender<EventBatch>) -> Self {
+        Self { sender }
+    }
+}
+
+impl EventSink for ChannelEventSink {
+    fn publish<'a>(&'a self, batch: &'a EventBatch) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            match self.sender.send(batch.clone()).await {
+                Ok(_) => PublishResult::Success,
+                Err(_) => PublishResult::Failure("event channel is closed".to_string()),
+            }
+        })
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::client_manager;
+    use crate::config::platform_data::{self, PlatformData};
+    use crate::events::event::EvaluationReason;
+    use crate::events::event_queue::{EventQueue, EventQueueOptions};
+    use crate::events::event_sink::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn create_event_queue(sdk_key: &str, event_sink: Arc<dyn EventSink>) -> EventQueue {
+        platform_data::set_platform_data(
+            sdk_key.to_string(),
+            PlatformData {
+                platform: "rust".to_string(),
+                platform_version: "1.0.0".to_string(),
+                sdk_type: "server".to_string(),
+                sdk_version: "1.0.0".to_string(),
+                hostname: "sink-host".to_string(),
+                device_model: "test".to_string(),
+            },
+        );
+        let options = EventQueueOptions {
+            event_sink: Some(event_sink),
+            flush_events_interval: Duration::from_secs(3600),
+            flush_events_batch_size: 2,
+            ..Default::default()
+        };
+        EventQueue::new(
+            &client_manager::get_or_create_client(sdk_key).state,
+            options,
+        )
+        .unwrap()
+    }
+
+    async fn queue_evaluations(eq: &EventQueue, variable_keys: &[&str]) {
+        for key in variable_keys {
+            eq.queue_variable_evaluated_event(
+                key,
+                "feature-id",
+                "variation-id",
+                EvaluationReason::TargetingMatch,
+            )
+            .unwrap();
+        }
+    }
+
+    // Fails the first `failures` publishes with a retryable error, then succeeds
+    struct FlakyEventSink {
+        failures: usize,
+        attempts: AtomicUsize,
+    }
+
+    impl EventSink for FlakyEventSink {
+        fn publish<'a>(&'a self, _batch: &'a EventBatch) -> EventSinkFuture<'a> {
+            let attempt = self.attempts.fetch_add(1, Ordering::Relaxed);
+            let failures = self.failures;
+            Box::pin(async move {
+                if attempt < failures {
+                    PublishResult::RetryableFailure("sink unavailable".to_string())
+                } else {
+                    PublishResult::Success
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_sink_collects_batches() {
+        let sink = Arc::new(InMemoryEventSink::new());
+        let eq = create_event_queue("test_sink_in_memory", sink.clone());
+        queue_evaluations(&eq, &["var-a", "var-b", "var-c"]).await;
+
+        assert_eq!(eq.flush_events().await.unwrap(), 3);
+
+        let batches = sink.batches();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(sink.event_count(), 3);
+        for batch in &batches {
+            let body: serde_json::Value = serde_json::from_str(&batch.body).unwrap();
+            let events = body["batch"][0]["events"].as_array().unwrap();
+            assert_eq!(events.len() as i64, batch.event_count);
+            assert_eq!(body["batch"][0]["user"]["userId"], "sink-host");
+        }
+        assert_ne!(batches[0].payload_id, batches[1].payload_id);
+    }
+
+    #[tokio::test]
+    async fn test_channel_sink_forwards_batches() {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let eq = create_event_queue("test_sink_channel", Arc::new(ChannelEventSink::new(sender)));
+        queue_evaluations(&eq, &["var-a"]).await;
+
+        assert_eq!(eq.flush_events().await.unwrap(), 1);
+
+        let batch = receiver.try_recv().unwrap();
+        assert_eq!(batch.event_count, 1);
+        assert!(batch.body.contains("\"target\":\"var-a\""));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_channel_sink_closed_receiver_drops_batch() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(10);
+        drop(receiver);
+        let eq = create_event_queue(
+            "test_sink_channel_closed",
+            Arc::new(ChannelEventSink::new(sender)),
+        );
+        queue_evaluations(&eq, &["var-a"]).await;
+
+        assert!(eq.flush_events().await.is_err());
+        assert!(eq.pending_payloads.lock().await.is_empty());
+        assert_eq!(eq.events_dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_file_sink_appends_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "devcycle-events-{}-{}.ndjson",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let sink = Arc::new(NdjsonFileEventSink::new(&path).unwrap());
+        let eq = create_event_queue("test_sink_ndjson", sink);
+
+        queue_evaluations(&eq, &["var-a", "var-b", "var-c"]).await;
+        eq.flush_events().await.unwrap();
+        queue_evaluations(&eq, &["var-d"]).await;
+        eq.flush_events().await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            let body: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(body["batch"].is_array());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_sink_retryable_failure_is_retried() {
+        let sink = Arc::new(FlakyEventSink {
+            failures: 1,
+            attempts: AtomicUsize::new(0),
+        });
+        let eq = create_event_queue("test_sink_flaky", sink.clone());
+        queue_evaluations(&eq, &["var-a"]).await;
+
+        assert!(eq.flush_events().await.is_err());
+        assert_eq!(eq.pending_payloads.lock().await.len(), 1);
+
+        assert_eq!(eq.flush_events().await.unwrap(), 1);
+        assert!(eq.pending_payloads.lock().await.is_empty());
+        assert_eq!(sink.attempts.load(Ordering::Relaxed), 2);
+        assert_eq!(eq.events_reported.load(Ordering::Relaxed), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::DevCycleError;
 use crate::events::EventQueueOptions;
 use crate::user::{BucketedUserConfig, PopulatedUser, User};
 use once_cell::sync::Lazy;
@@ -35,6 +36,58 @@ pub enum DevCycleFFIErrorCode {
     Reserved12 = -12,
     EventQueueInitFailed = -13,
     InitSdkKeyFailed = -14,
+    MissingConfig = -15,
+    MissingVariable = -16,
+    MissingFeature = -17,
+    MissingVariation = -18,
+    MissingVariableForVariation = -19,
+    FailedToDecideVariation = -20,
+    UserNotTargeted = -21,
+    UserNotInRollout = -22,
+    PrerequisiteNotMet = -23,
+    InvalidVariableType = -24,
+    VariableTypeMismatch = -25,
+    MissingPlatformData = -26,
+    EventQueueNotInitialized = -27,
+    EventQueueFull = -28,
+    InvalidEvent = -29,
+    InvalidConfig = -30,
+    MissingTarget = -31,
+}
+
+impl From<&DevCycleError> for DevCycleFFIErrorCode {
+    fn from(err: &DevCycleError) -> Self {
+        match err {
+            DevCycleError::MissingConfig => DevCycleFFIErrorCode::MissingConfig,
+            DevCycleError::MissingVariable => DevCycleFFIErrorCode::MissingVariable,
+            DevCycleError::MissingFeature => DevCycleFFIErrorCode::MissingFeature,
+            DevCycleError::MissingTarget => DevCycleFFIErrorCode::MissingTarget,
+            DevCycleError::MissingVariation => DevCycleFFIErrorCode::MissingVariation,
+            DevCycleError::MissingVariableForVariation => {
+                DevCycleFFIErrorCode::MissingVariableForVariation
+            }
+            DevCycleError::FailedToDecideVariation => DevCycleFFIErrorCode::FailedToDecideVariation,
+            DevCycleError::UserNotTargeted => DevCycleFFIErrorCode::UserNotTargeted,
+            DevCycleError::UserNotInRollout => DevCycleFFIErrorCode::UserNotInRollout,
+            DevCycleError::PrerequisiteNotMet => DevCycleFFIErrorCode::PrerequisiteNotMet,
+            DevCycleError::InvalidVariableType => DevCycleFFIErrorCode::InvalidVariableType,
+            DevCycleError::VariableTypeMismatch => DevCycleFFIErrorCode::VariableTypeMismatch,
+            DevCycleError::MissingPlatformData { .. } => DevCycleFFIErrorCode::MissingPlatformData,
+            DevCycleError::Parse { .. } | DevCycleError::MissingField(_) => {
+                DevCycleFFIErrorCode::JsonParseFailed
+            }
+            DevCycleError::InvalidEvent(_) => DevCycleFFIErrorCode::InvalidEvent,
+            DevCycleError::InvalidConfig(_) => DevCycleFFIErrorCode::InvalidConfig,
+            DevCycleError::EventQueueNotInitialized => {
+                DevCycleFFIErrorCode::EventQueueNotInitialized
+            }
+            DevCycleError::EventQueueFull(_) => DevCycleFFIErrorCode::EventQueueFull,
+            DevCycleError::EventFlushFailed(_)
+            | DevCycleError::ConfigFetchFailed { .. }
+            | DevCycleError::ClientDropped
+            | DevCycleError::Other { .. } => DevCycleFFIErrorCode::OperationFailed,
+        }
+    }
 }
 
 thread_local! {
@@ -150,6 +203,11 @@ fn parse_user_json_tolerant(json_str: &str) -> Result<User, DevCycleFFIErrorCode
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string(),
+        ip: obj
+            .get("ip")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
         app_version: obj
             .get("appVersion")
             .or_else(|| obj.get("app_version"))
@@ -201,6 +259,13 @@ fn set_error(msg: String, code: DevCycleFFIErrorCode) {
     set_last_error_code(code);
 }
 
+// Set the last error from a crate error, returning the code it maps to
+fn set_devcycle_error(context: &str, err: &DevCycleError) -> DevCycleFFIErrorCode {
+    let code = DevCycleFFIErrorCode::from(err);
+    set_error(format!("{}: {}", context, err), code);
+    code
+}
+
 // Helper to parse SDK key C string and set appropriate errors.
 // Returns owned String so caller can safely use &str without lifetime issues.
 unsafe fn parse_sdk_key(sdk_key: *const c_char) -> Result<String, DevCycleFFIErrorCode> {
@@ -271,6 +336,71 @@ pub unsafe extern "C" fn devcycle_init_event_queue(
     }
 }
 
+/// Publish the SDK key's queued events now
+/// Returns the number of events reported on success, a negative DevCycleFFIErrorCode on error
+/// Call devcycle_get_last_error() to get detailed error message
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_flush_events(sdk_key: *const c_char) -> i64 {
+    clear_last_error();
+
+    let sdk_key_str = match parse_sdk_key(sdk_key) {
+        Ok(s) => s,
+        Err(code) => return code as i64,
+    };
+
+    let runtime = match get_runtime_or_set_error() {
+        Some(rt) => rt,
+        None => {
+            set_error(
+                "Runtime unavailable".to_string(),
+                DevCycleFFIErrorCode::RuntimeUnavailable,
+            );
+            return DevCycleFFIErrorCode::RuntimeUnavailable as i64;
+        }
+    };
+
+    match runtime.block_on(crate::flush_events(&sdk_key_str)) {
+        Ok(reported) => {
+            set_last_error_code(DevCycleFFIErrorCode::Success);
+            reported
+        }
+        Err(e) => set_devcycle_error("Failed to flush events", &e) as i64,
+    }
+}
+
+/// Stop the SDK key's event queue, publishing queued events in a final flush, and unregister
+/// the SDK key. Call before unloading the library so no queued events are lost.
+/// Returns 0 on success, non-zero on error
+/// Call devcycle_get_last_error() to get detailed error message
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_close(sdk_key: *const c_char) -> i32 {
+    clear_last_error();
+
+    let sdk_key_str = match parse_sdk_key(sdk_key) {
+        Ok(s) => s,
+        Err(code) => return code as i32,
+    };
+
+    let runtime = match get_runtime_or_set_error() {
+        Some(rt) => rt,
+        None => {
+            set_error(
+                "Runtime unavailable".to_string(),
+                DevCycleFFIErrorCode::RuntimeUnavailable,
+            );
+            return DevCycleFFIErrorCode::RuntimeUnavailable as i32;
+        }
+    };
+
+    match runtime.block_on(crate::close(&sdk_key_str)) {
+        Ok(()) => {
+            set_last_error_code(DevCycleFFIErrorCode::Success);
+            0
+        }
+        Err(e) => set_devcycle_error("Failed to close", &e) as i32,
+    }
+}
+
 /// Generate bucketed config from populated user
 /// Returns pointer to CBucketedUserConfig on success, null on error
 /// Call devcycle_get_last_error() to get detailed error message
@@ -322,28 +452,10 @@ pub unsafe extern "C" fn devcycle_generate_bucketed_config(
             }
         };
 
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
-            set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
-            );
-            return ptr::null_mut();
-        }
-    };
-
-    match runtime.block_on(crate::generate_bucketed_config(
-        &sdk_key_str,
-        populated_user,
-        client_custom_data,
-    )) {
+    match crate::generate_bucketed_config(&sdk_key_str, populated_user, client_custom_data) {
         Ok(config) => Box::into_raw(Box::new(CBucketedUserConfig(config))),
         Err(e) => {
-            set_error(
-                format!("Failed to generate bucketed config: {}", e),
-                DevCycleFFIErrorCode::OperationFailed,
-            );
+            set_devcycle_error("Failed to generate bucketed config", &e);
             ptr::null_mut()
         }
     }
@@ -377,27 +489,10 @@ pub unsafe extern "C" fn devcycle_generate_bucketed_config_from_user(
 
     let _ = client_custom_data_json; // ignored
 
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
-            set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
-            );
-            return ptr::null_mut();
-        }
-    };
-
-    match runtime.block_on(crate::generate_bucketed_config_from_user(
-        &sdk_key_str,
-        user_obj,
-    )) {
+    match crate::generate_bucketed_config_from_user(&sdk_key_str, user_obj) {
         Ok(config) => Box::into_raw(Box::new(CBucketedUserConfig(config))),
         Err(e) => {
-            set_error(
-                format!("Failed to generate bucketed config from user: {}", e),
-                DevCycleFFIErrorCode::OperationFailed,
-            );
+            set_devcycle_error("Failed to generate bucketed config from user", &e);
             ptr::null_mut()
         }
     }
@@ -461,29 +556,58 @@ pub unsafe extern "C" fn devcycle_set_config(
         }
     };
 
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
-            set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
-            );
-            return DevCycleFFIErrorCode::RuntimeUnavailable as i32;
+    match crate::set_config(&sdk_key_str, config_body) {
+        Ok(_) => {
+            set_last_error_code(DevCycleFFIErrorCode::Success);
+            0
         }
+        Err(e) => set_devcycle_error("Failed to set config", &e) as i32,
+    }
+}
+
+/// Set config from a serialized `ConfigBodyProto` message of `config_len` bytes
+/// Returns 0 on success, non-zero on error
+/// Call devcycle_get_last_error() to get detailed error message
+#[cfg(feature = "protobuf")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_set_config_from_protobuf(
+    sdk_key: *const c_char,
+    config_bytes: *const u8,
+    config_len: usize,
+) -> i32 {
+    clear_last_error();
+
+    if config_bytes.is_null() && config_len > 0 {
+        set_error(
+            "Config bytes pointer is null".to_string(),
+            DevCycleFFIErrorCode::NullPointer,
+        );
+        return DevCycleFFIErrorCode::NullPointer as i32;
+    }
+
+    let sdk_key_str = match parse_sdk_key(sdk_key) {
+        Ok(s) => s,
+        Err(code) => return code as i32,
     };
 
-    match runtime.block_on(crate::set_config(&sdk_key_str, config_body)) {
+    let bytes: &[u8] = if config_len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(config_bytes, config_len)
+    };
+    match crate::set_config_from_protobuf_bytes(&sdk_key_str, bytes) {
         Ok(_) => {
             set_last_error_code(DevCycleFFIErrorCode::Success);
             0
         }
-        Err(e) => {
+        Err(e @ DevCycleError::Parse { .. }) | Err(e @ DevCycleError::MissingField(_)) => {
             set_error(
-                format!("Failed to set config: {}", e),
-                DevCycleFFIErrorCode::OperationFailed,
+                format!("Failed to decode protobuf config: {}", e),
+                DevCycleFFIErrorCode::ConfigBodyConversionFailed,
             );
-            DevCycleFFIErrorCode::OperationFailed as i32
+            DevCycleFFIErrorCode::ConfigBodyConversionFailed as i32
         }
+        Err(e) => set_devcycle_error("Failed to set config", &e) as i32,
     }
 }
 
@@ -533,29 +657,12 @@ pub unsafe extern "C" fn devcycle_set_client_custom_data(
             }
         };
 
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
-            set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
-            );
-            return DevCycleFFIErrorCode::RuntimeUnavailable as i32;
-        }
-    };
-
-    match runtime.block_on(crate::set_client_custom_data(&sdk_key_str, custom_data)) {
+    match crate::set_client_custom_data(&sdk_key_str, custom_data) {
         Ok(_) => {
             set_last_error_code(DevCycleFFIErrorCode::Success);
             0
         }
-        Err(e) => {
-            set_error(
-                format!("Failed to set client custom data: {}", e),
-                DevCycleFFIErrorCode::OperationFailed,
-            );
-            DevCycleFFIErrorCode::OperationFailed as i32
-        }
+        Err(e) => set_devcycle_error("Failed to set client custom data", &e) as i32,
     }
 }
 
@@ -604,18 +711,7 @@ pub unsafe extern "C" fn devcycle_set_platform_data(
         }
     };
 
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
-            set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
-            );
-            return DevCycleFFIErrorCode::RuntimeUnavailable as i32;
-        }
-    };
-
-    runtime.block_on(crate::set_platform_data(&sdk_key_str, platform_data));
+    crate::set_platform_data(&sdk_key_str, platform_data);
     set_last_error_code(DevCycleFFIErrorCode::Success);
     0
 }
@@ -815,26 +911,193 @@ pub unsafe extern "C" fn devcycle_variable_for_user(
         }
     };
     let populated_user = (*user).0.clone();
-    let runtime = match get_runtime_or_set_error() {
-        Some(rt) => rt,
-        None => {
+    match crate::variable_for_user(
+        &sdk_key_str,
+        populated_user,
+        variable_key_str,
+        variable_type_str,
+    ) {
+        Ok(result) => Box::into_raw(Box::new(CVariableForUserResult(result))),
+        Err(e) => {
+            set_devcycle_error("Failed to get variable for user", &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Generate bucketed config from populated user along with a trace of how every feature
+/// was evaluated
+/// Returns a JSON C string that must be freed with devcycle_free_string, null on error
+/// Call devcycle_get_last_error() to get detailed error message
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_explain_bucketed_config(
+    sdk_key: *const c_char,
+    user: *const CPopulatedUser,
+    client_custom_data_json: *const c_char,
+) -> *mut c_char {
+    clear_last_error();
+
+    if user.is_null() {
+        set_error(
+            "User pointer is null".to_string(),
+            DevCycleFFIErrorCode::NullPointer,
+        );
+        return ptr::null_mut();
+    }
+
+    let sdk_key_str = match parse_sdk_key(sdk_key) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let populated_user = (*user).0.clone();
+
+    let client_custom_data: HashMap<String, serde_json::Value> =
+        if client_custom_data_json.is_null() {
+            HashMap::new()
+        } else {
+            match CStr::from_ptr(client_custom_data_json).to_str() {
+                Ok(json_str) => match serde_json::from_str(json_str) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        set_error(
+                            format!("Failed to parse client custom data JSON: {}", e),
+                            DevCycleFFIErrorCode::JsonParseFailed,
+                        );
+                        return ptr::null_mut();
+                    }
+                },
+                Err(e) => {
+                    set_error(
+                        format!("Failed to convert client custom data from C string: {}", e),
+                        DevCycleFFIErrorCode::InputStringConversionFailed,
+                    );
+                    return ptr::null_mut();
+                }
+            }
+        };
+
+    match crate::explain_bucketed_config(&sdk_key_str, populated_user, client_custom_data) {
+        Ok(explanation) => json_to_c_string(&explanation),
+        Err(e) => {
+            set_devcycle_error("Failed to explain bucketed config", &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Evaluate a variable for a user along with a trace of how its feature was evaluated,
+/// without queueing events
+/// Returns a JSON C string that must be freed with devcycle_free_string, null on error
+/// Call devcycle_get_last_error() to get detailed error message
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_explain_variable_for_user(
+    sdk_key: *const c_char,
+    user: *const CPopulatedUser,
+    variable_key: *const c_char,
+    variable_type: *const c_char,
+) -> *mut c_char {
+    clear_last_error();
+    if user.is_null() || variable_key.is_null() || variable_type.is_null() {
+        set_error(
+            "User, variable key, or variable type pointer is null".to_string(),
+            DevCycleFFIErrorCode::NullPointer,
+        );
+        return ptr::null_mut();
+    }
+    let sdk_key_str = match parse_sdk_key(sdk_key) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+    let variable_key_str = match CStr::from_ptr(variable_key).to_str() {
+        Ok(s) => s,
+        Err(e) => {
             set_error(
-                "Runtime unavailable".to_string(),
-                DevCycleFFIErrorCode::RuntimeUnavailable,
+                format!("Failed to convert variable key from C string: {}", e),
+                DevCycleFFIErrorCode::InputStringConversionFailed,
+            );
+            return ptr::null_mut();
+        }
+    };
+    let variable_type_str = match CStr::from_ptr(variable_type).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_error(
+                format!("Failed to convert variable type from C string: {}", e),
+                DevCycleFFIErrorCode::InputStringConversionFailed,
             );
             return ptr::null_mut();
         }
     };
-    match runtime.block_on(crate::variable_for_user(
+    let populated_user = (*user).0.clone();
+    match crate::explain_variable_for_user(
         &sdk_key_str,
         populated_user,
         variable_key_str,
         variable_type_str,
-    )) {
-        Ok(result) => Box::into_raw(Box::new(CVariableForUserResult(result))),
+    ) {
+        Ok(explanation) => json_to_c_string(&explanation),
+        Err(e) => {
+            set_devcycle_error("Failed to explain variable for user", &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Run a conformance vector file (see tests/conformance) against this library's bucketing
+/// Returns the report as a JSON C string that must be freed with devcycle_free_string, null
+/// when the file can't be run. A report with failures is still returned.
+/// Call devcycle_get_last_error() to get detailed error message
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn devcycle_run_conformance_vectors(
+    vector_json: *const c_char,
+) -> *mut c_char {
+    clear_last_error();
+    if vector_json.is_null() {
+        set_error(
+            "Vector JSON pointer is null".to_string(),
+            DevCycleFFIErrorCode::NullPointer,
+        );
+        return ptr::null_mut();
+    }
+    let vector_json_str = match CStr::from_ptr(vector_json).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_error(
+                format!("Failed to convert vector JSON from C string: {}", e),
+                DevCycleFFIErrorCode::InputStringConversionFailed,
+            );
+            return ptr::null_mut();
+        }
+    };
+
+    match crate::run_conformance_vectors(vector_json_str) {
+        Ok(report) => json_to_c_string(&report),
+        Err(e) => {
+            set_devcycle_error("Failed to run conformance vectors", &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+fn json_to_c_string<T: serde::Serialize>(value: &T) -> *mut c_char {
+    match serde_json::to_string(value) {
+        Ok(json) => match CString::new(json) {
+            Ok(c_str) => {
+                set_last_error_code(DevCycleFFIErrorCode::Success);
+                c_str.into_raw()
+            }
+            Err(e) => {
+                set_error(
+                    format!("Failed to convert JSON to C string: {}", e),
+                    DevCycleFFIErrorCode::OperationFailed,
+                );
+                ptr::null_mut()
+            }
+        },
         Err(e) => {
             set_error(
-                format!("Failed to get variable for user: {}", e),
+                format!("Failed to serialize explanation to JSON: {}", e),
                 DevCycleFFIErrorCode::OperationFailed,
             );
             ptr::null_mut()
@@ -1242,7 +1505,7 @@ pub unsafe extern "C" fn devcycle_variable_result_get_error(
         }
         Err(e) => match CString::new(e.to_string()) {
             Ok(c_str) => {
-                set_last_error_code(DevCycleFFIErrorCode::OperationFailed);
+                set_last_error_code(DevCycleFFIErrorCode::from(e));
                 c_str.into_raw()
             }
             Err(err2) => {
@@ -1280,6 +1543,8 @@ pub unsafe extern "C" fn devcycle_variable_result_to_full_json(
         "featureId": (*result).0.feature_id,
         "variationId": (*result).0.variation_id,
         "evaluationReason": eval_reason_str,
+        "evaluationDetails": (*result).0.eval_details,
+        "targetId": (*result).0.target_id,
         "isError": is_error,
         "errorMessage": error_message,
     });
@@ -1324,6 +1589,7 @@ fn parse_event_type(s: &str) -> Option<crate::events::event::EventType> {
 /// Queue a manual event for a raw user JSON. The user will be populated internally using platform & client custom data.
 /// Returns 0 on success, non-zero (DevCycleFFIErrorCode) on error. Use devcycle_get_last_error() for message.
 /// event_type: one of the supported EventType strings (e.g., "CustomEvent"). For manual custom events use "CustomEvent".
+/// Custom events are validated and queued the same way as `track`.
 /// meta_data_json: JSON object string for metadata (may be null for empty)
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn devcycle_queue_event(
@@ -1448,6 +1714,23 @@ pub unsafe extern "C" fn devcycle_queue_event(
         }
     };
 
+    if event_type == crate::events::event::EventType::CustomEvent {
+        let custom_event = crate::events::event::CustomEvent {
+            custom_type: custom_type_str.to_string(),
+            target: target_str.to_string(),
+            value: Some(value),
+            metadata: meta_data,
+            date: None,
+        };
+        return match crate::track(&sdk_key_str, user, custom_event) {
+            Ok(_) => {
+                set_last_error_code(DevCycleFFIErrorCode::Success);
+                0
+            }
+            Err(e) => set_devcycle_error("Failed to track custom event", &e) as i32,
+        };
+    }
+
     // Obtain runtime
     let runtime = match get_runtime_or_set_error() {
         Some(rt) => rt,
@@ -1478,7 +1761,7 @@ pub unsafe extern "C" fn devcycle_queue_event(
         target: target_str.to_string(),
         custom_type: custom_type_str.to_string(),
         user_id: String::new(),
-        client_date: std::time::Instant::now(),
+        client_date: chrono::Utc::now(),
         value,
         feature_vars: HashMap::new(),
         meta_data,
@@ -1493,14 +1776,10 @@ pub unsafe extern "C" fn devcycle_queue_event(
         let platform_data = match crate::config::platform_data::get_platform_data(&sdk_key_str) {
             Ok(pd) => pd,
             Err(e) => {
-                set_error(
-                    format!(
-                        "Failed to get platform data for SDK key {}: {}",
-                        sdk_key_str, e
-                    ),
-                    DevCycleFFIErrorCode::OperationFailed,
-                );
-                return Err(DevCycleFFIErrorCode::OperationFailed);
+                return Err(set_devcycle_error(
+                    &format!("Failed to get platform data for SDK key {}", sdk_key_str),
+                    &e,
+                ));
             }
         };
         let populated_user = crate::user::PopulatedUser::new(
@@ -1513,9 +1792,7 @@ pub unsafe extern "C" fn devcycle_queue_event(
             &sdk_key_str,
             populated_user.clone(),
             client_custom_data,
-        )
-        .await
-        {
+        ) {
             Ok(bucketed) => {
                 event.feature_vars = bucketed.feature_variation_map;
                 if event.event_type == crate::events::event::EventType::CustomEvent {
@@ -1539,7 +1816,10 @@ pub unsafe extern "C" fn devcycle_queue_event(
                     .fetch_add(1, Ordering::Relaxed);
                 Ok(())
             }
-            Err(_) => Err(DevCycleFFIErrorCode::OperationFailed),
+            Err(e) => Err(set_devcycle_error(
+                "Failed to bucket config while queueing event",
+                &e,
+            )),
         }
     });
 
@@ -1548,15 +1828,7 @@ pub unsafe extern "C" fn devcycle_queue_event(
             set_last_error_code(DevCycleFFIErrorCode::Success);
             0
         }
-        Err(code) => {
-            if code == DevCycleFFIErrorCode::OperationFailed {
-                set_error(
-                    "Failed to bucket config while queueing event".to_string(),
-                    DevCycleFFIErrorCode::OperationFailed,
-                );
-            }
-            code as i32
-        }
+        Err(code) => code as i32,
     }
 }
 
@@ -1625,10 +1897,83 @@ mod ffi_tests {
             panic!("queue_event failed rc={}, err={}", queue_rc, err);
         }
         let eq = crate::events::event_queue_manager::get_event_queue("test-sdk-key").unwrap();
-        // Access underlying struct for count (Arc deref)
+        // Custom events go through the raw channel and are processed by the background task
+        for _ in 0..100 {
+            if eq.user_event_queue_count.load(Ordering::Relaxed) > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
         assert!(
             eq.user_event_queue_count.load(Ordering::Relaxed) > 0,
             "Expected user_event_queue_count > 0 after queueing event"
         );
+
+        // Custom events are validated like `track`
+        let empty_type = CString::new("").unwrap();
+        let invalid_rc = unsafe {
+            devcycle_queue_event(
+                sdk_key.as_ptr(),
+                user_json.as_ptr(),
+                event_type.as_ptr(),
+                empty_type.as_ptr(),
+                target.as_ptr(),
+                1.0,
+                meta.as_ptr(),
+            )
+        };
+        assert_eq!(invalid_rc, DevCycleFFIErrorCode::InvalidEvent as i32);
+    }
+
+    #[test]
+    fn test_flush_and_close_without_event_queue() {
+        let sdk_key = CString::new("test-sdk-key-ffi-no-event-queue").unwrap();
+        assert_eq!(
+            unsafe { devcycle_flush_events(sdk_key.as_ptr()) },
+            DevCycleFFIErrorCode::EventQueueNotInitialized as i64
+        );
+        assert_eq!(
+            unsafe { devcycle_close(sdk_key.as_ptr()) },
+            DevCycleFFIErrorCode::EventQueueNotInitialized as i32
+        );
+        assert_eq!(
+            unsafe { devcycle_close(ptr::null()) },
+            DevCycleFFIErrorCode::NullPointer as i32
+        );
+    }
+
+    #[test]
+    fn test_bucketing_errors_map_to_ffi_codes() {
+        let sdk_key = CString::new("test-sdk-key-ffi-missing-config").unwrap();
+        let user_json = CString::new("{\"userId\":\"user-1\"}").unwrap();
+        let user = unsafe { devcycle_user_from_json(user_json.as_ptr()) };
+        assert!(!user.is_null());
+
+        let config = unsafe {
+            devcycle_generate_bucketed_config_from_user(sdk_key.as_ptr(), user, ptr::null())
+        };
+        assert!(config.is_null());
+        assert_eq!(
+            unsafe { devcycle_get_last_error_code() },
+            DevCycleFFIErrorCode::MissingConfig
+        );
+        unsafe { devcycle_free_user(user) };
+
+        assert_eq!(
+            DevCycleFFIErrorCode::from(&crate::errors::failed_user_does_not_qualify_for_rollouts()),
+            DevCycleFFIErrorCode::UserNotInRollout
+        );
+        assert_eq!(
+            DevCycleFFIErrorCode::from(&DevCycleError::InvalidConfig(Vec::new())),
+            DevCycleFFIErrorCode::InvalidConfig
+        );
+        assert_eq!(
+            DevCycleFFIErrorCode::from(&crate::errors::missing_target()),
+            DevCycleFFIErrorCode::MissingTarget
+        );
+        assert_eq!(
+            DevCycleFFIErrorCode::from(&DevCycleError::new("other")),
+            DevCycleFFIErrorCode::OperationFailed
+        );
     }
 }
@@ -1,15 +1,21 @@
 use crate::errors::DevCycleError;
 use crate::user::{BucketedUserConfig, User};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 // Module declarations - now organized with mod.rs files in each folder
 pub(crate) mod bucketing;
+pub(crate) mod client;
 pub(crate) mod config;
 pub(crate) mod events;
 pub(crate) mod segmentation;
 pub mod user;
 pub(crate) mod util;
 
+// Binary config ingestion, see src/protobuf/config.proto for the schema
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+
 // FFI bindings for C library support (exclude when building for WASM)
 #[cfg(not(feature = "wasm"))]
 pub mod ffi;
@@ -17,25 +23,66 @@ pub mod ffi;
 // WASM bindings for WebAssembly support
 #[cfg(feature = "wasm")]
 pub mod wasm;
+#[cfg(all(test, feature = "wasm"))]
+mod wasm_tests;
 
 // Internal re-exports for convenience within the crate
 pub(crate) use config::configmanager;
 pub(crate) use config::feature;
 // Re-export only what's needed for the public API
+// Only for src/bucketing/bucketing_benches.rs
+#[cfg(feature = "bench")]
+pub use bucketing::bench_support;
+pub use bucketing::conformance::{
+    CONFORMANCE_VECTOR_VERSION, ConformanceCase, ConformanceFailure, ConformanceReport,
+    ConformanceUser, ConformanceVectorFile, ExpectedBucketing, ExpectedFeature, ExpectedVariable,
+    HashVector, conformance_platform_data, run_conformance_vectors,
+};
+pub use bucketing::explain::{
+    AudienceTrace, BucketedConfigExplanation, DistributionBucket, FeatureTrace, FilterTrace,
+    OperatorTrace, PrerequisiteTrace, RolloutTrace, TargetTrace, VariableExplanation,
+};
+pub use bucketing::rollout_timeline::{RolloutTimeline, RolloutTimelinePoint};
+pub use bucketing::simulation::{
+    DistributionCount, RolloutSimulation, SimulatedUsers, TargetSimulation, TrafficSimulation,
+    VariationCount, simulate_traffic,
+};
+pub use client::DevCycleLocalBucketing;
+pub use config::config_diff::{
+    ConfigDiff, DistributionChange, FeatureDiff, TargetDiff, ValueChange, VariableSummary,
+    VariableTypeChange, VariationDiff, VariationValueChange, diff_config_bodies, diff_full_configs,
+};
+pub use config::config_lint::{
+    ConfigLintFinding, ConfigLintRule, ConfigLintSeverity, lint_config, lint_full_config,
+};
+pub use config::config_listeners::{
+    ConfigChangeEvent, ConfigChangeListener, ConfigListenerId, UserVariableChanges,
+    VariableValueChange,
+};
+pub use config::config_source::{ConfigPollerHandle, ConfigSourceOptions};
 pub use config::platform_data::*;
-pub use events::EventQueueOptions;
-pub use events::event::{DefaultReason, EvalDetails, EvaluationReason};
+pub use events::event::{CustomEvent, DefaultReason, EvalDetails, EvaluationReason};
+#[cfg(not(target_family = "wasm"))]
+pub use events::event_sink::NdjsonFileEventSink;
+pub use events::event_sink::{
+    ChannelEventSink, EventBatch, EventSink, EventSinkFuture, HttpEventSink, InMemoryEventSink,
+    PublishResult,
+};
+pub use events::{EventQueueHandle, EventQueueOptions};
 pub(crate) use segmentation::filters;
+pub use segmentation::optin::{InMemoryOptInStore, OptInStore, get_opt_in_store};
 pub(crate) use segmentation::target;
 pub use user::BucketedUserConfig as BucketedConfig;
 pub use user::PopulatedUser;
 pub use user::User as DevCycleUser;
+pub use util::clock::{Clock, ManualClock, SystemClock};
 pub(crate) use util::constants;
 pub(crate) use util::errors;
 pub use util::errors::DevCycleError as Error;
 pub(crate) use util::murmurhash;
 
 use crate::bucketing::bucketing::VariableForUserResult;
+use crate::client::client_manager;
 use crate::config::ConfigBody;
 use crate::config::client_custom_data::get_client_custom_data;
 
@@ -46,49 +93,60 @@ pub async fn init_sdk_key(
     client_custom_data: HashMap<String, serde_json::Value>,
     platform_data: PlatformData,
 ) -> Result<(), DevCycleError> {
-    set_platform_data(sdk_key, platform_data).await;
-    set_client_custom_data(sdk_key, client_custom_data).await?;
-    set_config(sdk_key, config_body).await?;
+    set_platform_data(sdk_key, platform_data);
+    set_client_custom_data(sdk_key, client_custom_data)?;
+    set_config(sdk_key, config_body)?;
     init_event_queue(sdk_key, event_queue_options).await?;
 
     Ok(())
 }
 
-pub async fn set_config(sdk_key: &str, config_body: ConfigBody) -> Result<(), DevCycleError> {
+pub fn set_config(sdk_key: &str, config_body: ConfigBody) -> Result<(), DevCycleError> {
     Ok(configmanager::set_config(sdk_key, config_body))
 }
 
+// Rejects the config with `Error::InvalidConfig` when linting it finds errors, warnings
+// are ignored
+pub fn set_config_strict(sdk_key: &str, config_body: ConfigBody) -> Result<(), DevCycleError> {
+    client_manager::get_or_create_client(sdk_key).set_config_strict(config_body)
+}
+
 #[cfg(feature = "protobuf")]
-pub async fn set_config_from_protobuf(
+pub fn set_config_from_protobuf(
     sdk_key: &str,
     proto_config: protobuf::proto::ConfigBodyProto,
 ) -> Result<(), DevCycleError> {
-    let config_body = protobuf::convert_proto_to_config_body(proto_config)?;
-    Ok(configmanager::set_config(sdk_key, config_body))
+    set_config(
+        sdk_key,
+        protobuf::convert_proto_to_config_body(proto_config)?,
+    )
+}
+
+// Decodes a serialized `ConfigBodyProto`, skipping JSON parsing entirely
+#[cfg(feature = "protobuf")]
+pub fn set_config_from_protobuf_bytes(sdk_key: &str, bytes: &[u8]) -> Result<(), DevCycleError> {
+    set_config(sdk_key, protobuf::decode_config_body(bytes)?)
 }
 
-pub async fn generate_bucketed_config(
+pub fn generate_bucketed_config(
     sdk_key: &str,
     user: PopulatedUser,
     client_custom_data: HashMap<String, serde_json::Value>,
 ) -> Result<BucketedUserConfig, DevCycleError> {
-    bucketing::generate_bucketed_config(sdk_key.to_string(), user, client_custom_data).await
+    bucketing::generate_bucketed_config(sdk_key.to_string(), user, client_custom_data)
 }
 
-pub async fn generate_bucketed_config_from_user(
+pub fn generate_bucketed_config_from_user(
     sdk_key: &str,
     user: User,
 ) -> Result<BucketedUserConfig, DevCycleError> {
-    let populated_user = user.get_populated_user(sdk_key);
-    bucketing::generate_bucketed_config(
-        sdk_key.to_string(),
-        populated_user,
-        get_client_custom_data(sdk_key.to_string()),
-    )
-    .await
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.generate_bucketed_config_from_user(user),
+        None => Err(errors::missing_config()),
+    }
 }
 
-pub async fn variable_for_user(
+pub fn variable_for_user(
     sdk_key: &str,
     user: PopulatedUser,
     variable_key: &str,
@@ -101,24 +159,162 @@ pub async fn variable_for_user(
         variable_type,
         get_client_custom_data(sdk_key.to_string()),
     )
-    .await
 }
 
+// Like generate_bucketed_config, with rollouts evaluated as of `at`
+pub fn generate_bucketed_config_at(
+    sdk_key: &str,
+    user: PopulatedUser,
+    client_custom_data: HashMap<String, serde_json::Value>,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<BucketedUserConfig, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.generate_bucketed_config_at(user, client_custom_data, at),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// Like variable_for_user, with rollouts evaluated as of `at`. No evaluation events are queued.
+pub fn variable_for_user_at(
+    sdk_key: &str,
+    user: PopulatedUser,
+    variable_key: &str,
+    variable_type: &str,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<VariableForUserResult, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.variable_for_user_at(user, variable_key, variable_type, at),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// Like generate_bucketed_config, with a trace of how every feature was evaluated
+pub fn explain_bucketed_config(
+    sdk_key: &str,
+    user: PopulatedUser,
+    client_custom_data: HashMap<String, serde_json::Value>,
+) -> Result<BucketedConfigExplanation, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.explain_bucketed_config(user, client_custom_data),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// Like variable_for_user, with a trace of how the variable's feature was evaluated. No
+// evaluation events are queued.
+pub fn explain_variable_for_user(
+    sdk_key: &str,
+    user: PopulatedUser,
+    variable_key: &str,
+    variable_type: &str,
+) -> Result<VariableExplanation, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.explain_variable_for_user(user, variable_key, variable_type),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// The rollout percentage timeline of a feature's target, None when the target has no rollout
+pub fn rollout_timeline(
+    sdk_key: &str,
+    feature_key: &str,
+    target_id: &str,
+) -> Result<Option<RolloutTimeline>, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.rollout_timeline(feature_key, target_id),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// When a rollout could next change the user's bucketed config, for use as a cache expiry
+pub fn next_assignment_change(
+    sdk_key: &str,
+    user: &PopulatedUser,
+    client_custom_data: &HashMap<String, serde_json::Value>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.next_assignment_change(user, client_custom_data),
+        None => Err(errors::missing_config()),
+    }
+}
+
+// Starts a background task that processes queued events and flushes them on the configured
+// interval. A queue previously initialized for the SDK key is stopped without flushing.
 pub async fn init_event_queue(
     sdk_key: &str,
     event_queue_options: EventQueueOptions,
-) -> Result<(), DevCycleError> {
-    let eq = events::event_queue::EventQueue::new(sdk_key.to_string(), event_queue_options);
-    match eq {
-        Ok(queue) => {
-            events::event_queue_manager::set_event_queue(sdk_key, queue);
-            Ok(())
-        }
-        Err(e) => Err(e),
+) -> Result<EventQueueHandle, DevCycleError> {
+    client_manager::get_or_create_client(sdk_key)
+        .init_event_queue(event_queue_options)
+        .await
+}
+
+// Publishes the SDK key's queued events now. Without a background task to process them, as
+// under WASM, this must be called on the flush interval or the queue fills up.
+pub async fn flush_events(sdk_key: &str) -> Result<i64, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.flush_events().await,
+        None => Err(errors::event_queue_not_initialized()),
     }
 }
 
-pub async fn set_client_custom_data(
+// Stops the SDK key's event queue, draining queued events and publishing them in a final flush.
+// The SDK key's client is unregistered along with its config and data. Without an event queue
+// this returns `EventQueueNotInitialized` and leaves the client registered.
+pub async fn close(sdk_key: &str) -> Result<(), DevCycleError> {
+    let has_event_queue = client_manager::get_client(sdk_key)
+        .is_some_and(|client| client.state.get_event_queue().is_some());
+    if !has_event_queue {
+        return Err(errors::event_queue_not_initialized());
+    }
+    match client_manager::remove_client(sdk_key) {
+        Some(client) => client.close().await,
+        None => Err(errors::event_queue_not_initialized()),
+    }
+}
+
+// Fetches the SDK key's config from the CDN, then keeps it up to date by polling in the
+// background. A poller previously started for the SDK key is stopped.
+pub async fn init_config_poller(
+    sdk_key: &str,
+    options: ConfigSourceOptions,
+) -> Result<ConfigPollerHandle, DevCycleError> {
+    client_manager::get_or_create_client(sdk_key)
+        .init_config_poller(options)
+        .await
+}
+
+// Registers a callback invoked with what changed every time the SDK key's config is replaced
+pub fn on_config_change(
+    sdk_key: &str,
+    listener: impl Fn(&ConfigChangeEvent) + Send + Sync + 'static,
+) -> ConfigListenerId {
+    client_manager::get_or_create_client(sdk_key).on_config_change(listener)
+}
+
+pub fn remove_config_listener(sdk_key: &str, id: ConfigListenerId) -> bool {
+    client_manager::get_client(sdk_key).is_some_and(|client| client.remove_config_listener(id))
+}
+
+// Re-evaluates the user on every config change, reporting their changed variable values to
+// the config change listeners
+pub fn watch_user(sdk_key: &str, user: PopulatedUser) {
+    client_manager::get_or_create_client(sdk_key).watch_user(user);
+}
+
+pub fn unwatch_user(sdk_key: &str, user_id: &str) -> bool {
+    client_manager::get_client(sdk_key).is_some_and(|client| client.unwatch_user(user_id))
+}
+
+// Queues a custom event for the user, returns false when custom event logging is disabled
+pub fn track(sdk_key: &str, user: User, custom_event: CustomEvent) -> Result<bool, DevCycleError> {
+    match client_manager::get_client(sdk_key) {
+        Some(client) => client.track(user, custom_event),
+        None => Err(errors::event_queue_not_initialized()),
+    }
+}
+
+pub fn set_client_custom_data(
     sdk_key: &str,
     client_custom_data: HashMap<String, serde_json::Value>,
 ) -> Result<(), DevCycleError> {
@@ -127,6 +323,14 @@ pub async fn set_client_custom_data(
     Ok(())
 }
 
-pub async fn set_platform_data(sdk_key: &str, platform_data: PlatformData) {
+pub fn set_platform_data(sdk_key: &str, platform_data: PlatformData) {
     config::platform_data::set_platform_data(sdk_key.to_string(), platform_data);
 }
+
+pub fn set_opt_in_store(sdk_key: &str, opt_in_store: Arc<dyn OptInStore>) {
+    segmentation::optin::set_opt_in_store(sdk_key, opt_in_store);
+}
+
+pub fn set_clock(sdk_key: &str, clock: Arc<dyn Clock>) {
+    client_manager::get_or_create_client(sdk_key).set_clock(clock);
+}
@@ -0,0 +1,493 @@
+pub mod proto;
+
+#[cfg(test)]
+mod protobuf_tests;
+
+use crate::config::{
+    ConfigBody, EdgeDBSettings, Environment, FullConfig, ObfuscationSettings, OptInColors,
+    OptInSettings, Project, ProjectSettings, SSE, Variable,
+};
+use crate::errors::DevCycleError;
+use crate::feature::{
+    ConfigFeature, FeatureConfiguration, FeaturePrerequisites, FeatureVariation, Variation,
+    VariationVariable,
+};
+use crate::filters::{AudienceOperator, Filter, NoIdAudience};
+use crate::target::{Rollout, RolloutStage, Target, TargetDistribution};
+use chrono::{DateTime, Utc};
+use prost::Message;
+use proto::json_value::Kind;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const DEFAULT_BUCKETING_KEY: &str = "user_id";
+
+/// Decode a config serialized as a `ConfigBodyProto` message.
+pub fn decode_config_body(bytes: &[u8]) -> Result<ConfigBody, DevCycleError> {
+    let proto_config = proto::ConfigBodyProto::decode(bytes).map_err(|e| DevCycleError::Parse {
+        message: format!("failed to decode protobuf config: {}", e),
+        source: Some(std::sync::Arc::new(e)),
+    })?;
+    convert_proto_to_config_body(proto_config)
+}
+
+/// Encode a JSON config as a `ConfigBodyProto` message.
+pub fn encode_full_config(full_config: &FullConfig) -> Result<Vec<u8>, DevCycleError> {
+    Ok(convert_full_config_to_proto(full_config)?.encode_to_vec())
+}
+
+pub fn convert_proto_to_config_body(
+    proto_config: proto::ConfigBodyProto,
+) -> Result<ConfigBody, DevCycleError> {
+    let project = proto_config
+        .project
+        .ok_or_else(|| missing_field("project"))?;
+    let environment = proto_config
+        .environment
+        .ok_or_else(|| missing_field("environment"))?;
+    let audiences = proto_config
+        .audiences
+        .into_iter()
+        .map(|(id, audience)| {
+            let audience = audience_from_proto(Some(audience), &format!("audiences[{}]", id))?;
+            Ok((id, audience))
+        })
+        .collect::<Result<HashMap<_, _>, DevCycleError>>()?;
+    let features = proto_config
+        .features
+        .into_iter()
+        .map(feature_from_proto)
+        .collect::<Result<Vec<_>, _>>()?;
+    let variables = proto_config
+        .variables
+        .into_iter()
+        .map(|variable| Variable {
+            _id: variable.id,
+            _type: variable.r#type,
+            key: variable.key,
+        })
+        .collect();
+
+    ConfigBody::from_parts(
+        project_from_proto(project),
+        audiences,
+        Environment {
+            _id: environment.id,
+            key: environment.key,
+        },
+        features,
+        variables,
+        proto_config.sse.map(|sse| SSE {
+            hostname: sse.hostname,
+            path: sse.path,
+        }),
+    )
+    .map_err(|message| DevCycleError::Parse {
+        message,
+        source: None,
+    })
+}
+
+pub fn convert_full_config_to_proto(
+    full_config: &FullConfig,
+) -> Result<proto::ConfigBodyProto, DevCycleError> {
+    let audiences = full_config
+        .audiences
+        .iter()
+        .map(|(id, value)| {
+            let audience: NoIdAudience = serde_json::from_value(value.clone())?;
+            Ok((id.clone(), audience_to_proto(&audience)))
+        })
+        .collect::<Result<HashMap<_, _>, DevCycleError>>()?;
+
+    Ok(proto::ConfigBodyProto {
+        project: Some(project_to_proto(&full_config.project)),
+        environment: Some(proto::Environment {
+            id: full_config.environment._id.clone(),
+            key: full_config.environment.key.clone(),
+        }),
+        features: full_config.features.iter().map(feature_to_proto).collect(),
+        variables: full_config
+            .variables
+            .iter()
+            .map(|variable| proto::Variable {
+                id: variable._id.clone(),
+                r#type: variable._type.clone(),
+                key: variable.key.clone(),
+            })
+            .collect(),
+        variable_hashes: full_config.variable_hashes.clone(),
+        audiences,
+        sse: full_config.sse.as_ref().map(|sse| proto::Sse {
+            hostname: sse.hostname.clone(),
+            path: sse.path.clone(),
+        }),
+    })
+}
+
+fn missing_field(path: &str) -> DevCycleError {
+    DevCycleError::MissingField(path.to_string())
+}
+
+fn timestamp_from_millis(millis: i64, path: &str) -> Result<DateTime<Utc>, DevCycleError> {
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| DevCycleError::Parse {
+        message: format!("{} is not a valid timestamp: {}", path, millis),
+        source: None,
+    })
+}
+
+fn project_from_proto(project: proto::Project) -> Project {
+    let settings = project.settings.unwrap_or_default();
+    let opt_in = settings.opt_in.unwrap_or_default();
+    let colors = opt_in.colors.unwrap_or_default();
+    Project {
+        _id: project.id,
+        key: project.key,
+        a0_organization: project.a0_organization,
+        settings: ProjectSettings {
+            edgedb: EdgeDBSettings {
+                enabled: settings.edge_db.is_some_and(|edge_db| edge_db.enabled),
+            },
+            optin: OptInSettings {
+                enabled: opt_in.enabled,
+                title: opt_in.title,
+                description: opt_in.description,
+                image_url: opt_in.image_url,
+                colors: OptInColors {
+                    primary: colors.primary,
+                    secondary: colors.secondary,
+                },
+            },
+            disable_passthrough_rollouts: settings.disable_passthrough_rollouts,
+            obfuscation: settings.obfuscation.map(|obfuscation| ObfuscationSettings {
+                required: obfuscation.required,
+                enabled: obfuscation.enabled,
+            }),
+        },
+    }
+}
+
+fn project_to_proto(project: &Project) -> proto::Project {
+    let settings = &project.settings;
+    proto::Project {
+        id: project._id.clone(),
+        key: project.key.clone(),
+        a0_organization: project.a0_organization.clone(),
+        settings: Some(proto::ProjectSettings {
+            edge_db: Some(proto::EdgeDbSettings {
+                enabled: settings.edgedb.enabled,
+            }),
+            opt_in: Some(proto::OptInSettings {
+                enabled: settings.optin.enabled,
+                title: settings.optin.title.clone(),
+                description: settings.optin.description.clone(),
+                image_url: settings.optin.image_url.clone(),
+                colors: Some(proto::OptInColors {
+                    primary: settings.optin.colors.primary.clone(),
+                    secondary: settings.optin.colors.secondary.clone(),
+                }),
+            }),
+            disable_passthrough_rollouts: settings.disable_passthrough_rollouts,
+            obfuscation: settings.obfuscation.as_ref().map(|obfuscation| {
+                proto::ObfuscationSettings {
+                    required: obfuscation.required,
+                    enabled: obfuscation.enabled,
+                }
+            }),
+        }),
+    }
+}
+
+fn feature_from_proto(feature: proto::Feature) -> Result<ConfigFeature, DevCycleError> {
+    let path = format!("features[{}]", feature.key);
+    let configuration = feature
+        .configuration
+        .ok_or_else(|| missing_field(&format!("{}.configuration", path)))?;
+    let targets = configuration
+        .targets
+        .into_iter()
+        .map(|target| target_from_proto(target, &path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ConfigFeature {
+        _id: feature.id,
+        _type: feature.r#type,
+        key: feature.key,
+        variations: feature
+            .variations
+            .into_iter()
+            .map(|variation| Variation {
+                _id: variation.id,
+                name: variation.name,
+                key: variation.key,
+                variables: variation
+                    .variables
+                    .into_iter()
+                    .map(|variable| VariationVariable {
+                        _var: variable.var,
+                        value: json_from_proto(variable.value),
+                    })
+                    .collect(),
+            })
+            .collect(),
+        configuration: FeatureConfiguration {
+            _id: configuration.id,
+            prerequisites: configuration
+                .prerequisites
+                .into_iter()
+                .map(|prerequisite| FeaturePrerequisites {
+                    _feature: prerequisite.feature,
+                    comparator: prerequisite.comparator,
+                })
+                .collect(),
+            winning_variation: configuration
+                .winning_variation
+                .map(|winning| FeatureVariation {
+                    _feature: winning.feature,
+                    _variation: winning.variation,
+                }),
+            forced_users: configuration.forced_users,
+            targets,
+        },
+        settings: feature.settings,
+        tags: feature.tags,
+    })
+}
+
+fn feature_to_proto(feature: &ConfigFeature) -> proto::Feature {
+    let configuration = &feature.configuration;
+    proto::Feature {
+        id: feature._id.clone(),
+        r#type: feature._type.clone(),
+        key: feature.key.clone(),
+        variations: feature
+            .variations
+            .iter()
+            .map(|variation| proto::Variation {
+                id: variation._id.clone(),
+                name: variation.name.clone(),
+                key: variation.key.clone(),
+                variables: variation
+                    .variables
+                    .iter()
+                    .map(|variable| proto::VariationVariable {
+                        var: variable._var.clone(),
+                        value: Some(json_to_proto(&variable.value)),
+                    })
+                    .collect(),
+            })
+            .collect(),
+        configuration: Some(proto::FeatureConfiguration {
+            id: configuration._id.clone(),
+            prerequisites: configuration
+                .prerequisites
+                .iter()
+                .map(|prerequisite| proto::FeaturePrerequisite {
+                    feature: prerequisite._feature.clone(),
+                    comparator: prerequisite.comparator.clone(),
+                })
+                .collect(),
+            winning_variation: configuration.winning_variation.as_ref().map(|winning| {
+                proto::FeatureVariation {
+                    feature: winning._feature.clone(),
+                    variation: winning._variation.clone(),
+                }
+            }),
+            forced_users: configuration.forced_users.clone(),
+            targets: configuration.targets.iter().map(target_to_proto).collect(),
+        }),
+        settings: feature.settings.clone(),
+        tags: feature.tags.clone(),
+    }
+}
+
+fn target_from_proto(target: proto::Target, feature_path: &str) -> Result<Target, DevCycleError> {
+    let path = format!("{}.targets[{}]", feature_path, target.id);
+    let rollout = match target.rollout {
+        Some(rollout) => Some(rollout_from_proto(rollout, &path)?),
+        None => None,
+    };
+    Ok(Target {
+        audience: audience_from_proto(target.audience, &format!("{}._audience", path))?,
+        _id: target.id,
+        rollout,
+        distribution: target
+            .distribution
+            .into_iter()
+            .map(|distribution| TargetDistribution {
+                variation: distribution.variation,
+                percentage: distribution.percentage,
+            })
+            .collect(),
+        bucketingkey: if target.bucketing_key.is_empty() {
+            DEFAULT_BUCKETING_KEY.to_string()
+        } else {
+            target.bucketing_key
+        },
+        compiled_audience: None,
+        compiled_rollout: None,
+    })
+}
+
+fn target_to_proto(target: &Target) -> proto::Target {
+    proto::Target {
+        id: target._id.clone(),
+        audience: Some(audience_to_proto(&target.audience)),
+        rollout: target.rollout.as_ref().map(|rollout| proto::Rollout {
+            r#type: rollout._type.clone(),
+            start_percentage: rollout.start_percentage,
+            start_date: rollout.start_date.timestamp_millis(),
+            stages: rollout
+                .stages
+                .iter()
+                .map(|stage| proto::RolloutStage {
+                    r#type: stage._type.clone(),
+                    date: stage.date.timestamp_millis(),
+                    percentage: stage.percentage,
+                })
+                .collect(),
+        }),
+        distribution: target
+            .distribution
+            .iter()
+            .map(|distribution| proto::TargetDistribution {
+                variation: distribution.variation.clone(),
+                percentage: distribution.percentage,
+            })
+            .collect(),
+        bucketing_key: target.bucketingkey.clone(),
+    }
+}
+
+fn rollout_from_proto(
+    rollout: proto::Rollout,
+    target_path: &str,
+) -> Result<Rollout, DevCycleError> {
+    let path = format!("{}.rollout", target_path);
+    Ok(Rollout {
+        _type: rollout.r#type,
+        start_percentage: rollout.start_percentage,
+        start_date: timestamp_from_millis(rollout.start_date, &format!("{}.startDate", path))?,
+        stages: rollout
+            .stages
+            .into_iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                Ok(RolloutStage {
+                    _type: stage.r#type,
+                    date: timestamp_from_millis(
+                        stage.date,
+                        &format!("{}.stages[{}].date", path, index),
+                    )?,
+                    percentage: stage.percentage,
+                })
+            })
+            .collect::<Result<Vec<_>, DevCycleError>>()?,
+    })
+}
+
+fn audience_from_proto(
+    audience: Option<proto::Audience>,
+    path: &str,
+) -> Result<NoIdAudience, DevCycleError> {
+    let filters = audience
+        .and_then(|audience| audience.filters)
+        .ok_or_else(|| missing_field(&format!("{}.filters", path)))?;
+    Ok(NoIdAudience {
+        filters: AudienceOperator {
+            operator: filters.operator,
+            filters: filters.filters.into_iter().map(filter_from_proto).collect(),
+        },
+    })
+}
+
+fn audience_to_proto(audience: &NoIdAudience) -> proto::Audience {
+    proto::Audience {
+        filters: Some(proto::AudienceOperator {
+            operator: audience.filters.operator.clone(),
+            filters: audience
+                .filters
+                .filters
+                .iter()
+                .map(filter_to_proto)
+                .collect(),
+        }),
+    }
+}
+
+fn filter_from_proto(filter: proto::Filter) -> Filter {
+    Filter {
+        _type: filter.r#type,
+        sub_type: filter.sub_type,
+        comparator: filter.comparator,
+        values: filter
+            .values
+            .into_iter()
+            .map(|value| json_from_proto(Some(value)))
+            .collect(),
+        filters: filter.filters.into_iter().map(filter_from_proto).collect(),
+        operator: filter.operator,
+        _audiences: filter.audiences,
+    }
+}
+
+fn filter_to_proto(filter: &Filter) -> proto::Filter {
+    proto::Filter {
+        r#type: filter._type.clone(),
+        sub_type: filter.sub_type.clone(),
+        comparator: filter.comparator.clone(),
+        values: filter.values.iter().map(json_to_proto).collect(),
+        filters: filter.filters.iter().map(filter_to_proto).collect(),
+        operator: filter.operator.clone(),
+        audiences: filter._audiences.clone(),
+    }
+}
+
+// A value without a kind set decodes as null
+fn json_from_proto(value: Option<proto::JsonValue>) -> Value {
+    match value.and_then(|value| value.kind) {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(value)) => Value::Bool(value),
+        Some(Kind::IntegerValue(value)) => Value::from(value),
+        Some(Kind::NumberValue(value)) => {
+            serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+        }
+        Some(Kind::StringValue(value)) => Value::String(value),
+        Some(Kind::ObjectValue(object)) => Value::Object(
+            object
+                .fields
+                .into_iter()
+                .map(|(key, value)| (key, json_from_proto(Some(value))))
+                .collect(),
+        ),
+        Some(Kind::ArrayValue(array)) => Value::Array(
+            array
+                .values
+                .into_iter()
+                .map(|value| json_from_proto(Some(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn json_to_proto(value: &Value) -> proto::JsonValue {
+    let kind = match value {
+        Value::Null => Kind::NullValue(true),
+        Value::Bool(value) => Kind::BoolValue(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => Kind::IntegerValue(integer),
+            None => Kind::NumberValue(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => Kind::StringValue(value.clone()),
+        Value::Object(object) => Kind::ObjectValue(proto::JsonObject {
+            fields: object
+                .iter()
+                .map(|(key, value)| (key.clone(), json_to_proto(value)))
+                .collect(),
+        }),
+        Value::Array(values) => Kind::ArrayValue(proto::JsonArray {
+            values: values.iter().map(json_to_proto).collect(),
+        }),
+    };
+    proto::JsonValue { kind: Some(kind) }
+}
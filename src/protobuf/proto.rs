@@ -0,0 +1,2 @@
+// Message types for config.proto, generated by build.rs with prost-build
+include!(concat!(env!("OUT_DIR"), "/devcycle.config.v1.rs"));
@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use crate::client::DevCycleLocalBucketing;
+    use crate::config::platform_data::PlatformData;
+    use crate::config::{ConfigBody, FullConfig};
+    use crate::errors::DevCycleError;
+    use crate::protobuf::{
+        convert_full_config_to_proto, convert_proto_to_config_body, decode_config_body,
+        encode_full_config, proto,
+    };
+    use crate::user::User;
+    use chrono::Utc;
+    use prost::Message;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    fn load_full_config(json: &str) -> FullConfig {
+        serde_json::from_str(json).expect("Failed to parse test config")
+    }
+
+    // Everything bucketing reads from a config, in a comparable form
+    fn comparable(config: &ConfigBody) -> Value {
+        let mut audiences: Vec<(&String, Value)> = config
+            .audiences
+            .iter()
+            .map(|(id, audience)| (id, serde_json::to_value(audience).unwrap()))
+            .collect();
+        audiences.sort_by(|a, b| a.0.cmp(b.0));
+        json!({
+            "project": config.project,
+            "environment": config.environment,
+            "features": config.features,
+            "variables": config.variables,
+            "sse": config.sse,
+            "audiences": audiences,
+        })
+    }
+
+    fn assert_round_trip(json: &str) {
+        let full_config = load_full_config(json);
+        let bytes = encode_full_config(&full_config).unwrap();
+        let decoded = decode_config_body(&bytes).unwrap();
+        let expected = ConfigBody::from_full_config(full_config).unwrap();
+        assert_eq!(comparable(&decoded), comparable(&expected));
+    }
+
+    #[test]
+    fn test_configs_round_trip_through_protobuf() {
+        assert_round_trip(include_str!("../../tests/resources/test_config.json"));
+        assert_round_trip(include_str!(
+            "../../tests/resources/fixture_test_config.json"
+        ));
+        assert_round_trip(include_str!(
+            "../../tests/resources/fixture_test_v2_config.json"
+        ));
+    }
+
+    #[test]
+    fn test_json_values_keep_their_types() {
+        let mut json: Value =
+            serde_json::from_str(include_str!("../../tests/resources/test_config.json")).unwrap();
+        json["features"][0]["variations"][0]["variables"][4]["value"] =
+            json!({"count": 3, "ratio": 0.25, "nested": [null, true, "text"]});
+        let full_config: FullConfig = serde_json::from_value(json).unwrap();
+
+        let config =
+            convert_proto_to_config_body(convert_full_config_to_proto(&full_config).unwrap())
+                .unwrap();
+        let values: HashMap<&str, &Value> = config.features[0].variations[0]
+            .variables
+            .iter()
+            .map(|variable| (variable._var.as_str(), &variable.value))
+            .collect();
+        assert_eq!(values["64de2b2486d4b575121589db"], &json!(123));
+        assert!(values["64de2b2486d4b575121589db"].is_i64());
+        assert_eq!(values["64de2b9486d4b275121589d1"], &json!(4.56));
+        assert_eq!(
+            values["64de88bcc99ba02630f3df80"],
+            &json!({"count": 3, "ratio": 0.25, "nested": [null, true, "text"]})
+        );
+    }
+
+    #[test]
+    fn test_protobuf_config_buckets_like_json_config() {
+        let config_json = include_str!("../../tests/resources/test_config.json");
+        let bytes = encode_full_config(&load_full_config(config_json)).unwrap();
+        let platform_data = PlatformData {
+            sdk_type: "server".to_string(),
+            sdk_version: "1.0.0".to_string(),
+            platform_version: "1.0.0".to_string(),
+            device_model: "test-device".to_string(),
+            platform: "test".to_string(),
+            hostname: "test-host".to_string(),
+        };
+
+        let from_json = DevCycleLocalBucketing::new("dvc_server_protobuf_json");
+        from_json.set_platform_data(platform_data.clone());
+        from_json
+            .set_config(ConfigBody::from_full_config(load_full_config(config_json)).unwrap())
+            .unwrap();
+        let from_protobuf = DevCycleLocalBucketing::new("dvc_server_protobuf_bytes");
+        from_protobuf.set_platform_data(platform_data);
+        from_protobuf
+            .set_config_from_protobuf_bytes(&bytes)
+            .unwrap();
+
+        for user_id in ["user-1", "user-2", "user-3", "user-4"] {
+            let user = User {
+                user_id: user_id.to_string(),
+                email: String::new(),
+                name: String::new(),
+                language: String::new(),
+                country: String::new(),
+                ip: String::new(),
+                app_version: String::new(),
+                app_build: String::new(),
+                custom_data: HashMap::new(),
+                private_custom_data: HashMap::new(),
+                device_model: String::new(),
+                last_seen_date: Utc::now(),
+            };
+            let expected = from_json
+                .generate_bucketed_config_from_user(user.clone())
+                .unwrap();
+            let actual = from_protobuf
+                .generate_bucketed_config_from_user(user)
+                .unwrap();
+            assert_eq!(
+                serde_json::to_value(&actual.variables).unwrap(),
+                serde_json::to_value(&expected.variables).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_messages_are_rejected() {
+        match decode_config_body(&[0xff, 0xff, 0xff]) {
+            Err(err @ DevCycleError::Parse { .. }) => {
+                assert!(err.to_string().contains("failed to decode protobuf config"))
+            }
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("expected decoding to fail"),
+        }
+
+        let mut proto_config = convert_full_config_to_proto(&load_full_config(include_str!(
+            "../../tests/resources/test_config.json"
+        )))
+        .unwrap();
+        proto_config.features[0]
+            .configuration
+            .as_mut()
+            .unwrap()
+            .targets[0]
+            .audience = None;
+        match decode_config_body(&proto_config.encode_to_vec()) {
+            Err(DevCycleError::MissingField(field)) => assert_eq!(
+                field,
+                "features[test].targets[621642332ea68943c8833c4d]._audience.filters"
+            ),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("expected decoding to fail"),
+        }
+
+        proto_config.project = None;
+        assert!(matches!(
+            decode_config_body(&proto_config.encode_to_vec()),
+            Err(DevCycleError::MissingField(field)) if field == "project"
+        ));
+    }
+
+    #[test]
+    fn test_empty_bucketing_key_defaults_to_user_id() {
+        let mut proto_config = convert_full_config_to_proto(&load_full_config(include_str!(
+            "../../tests/resources/test_config.json"
+        )))
+        .unwrap();
+        proto_config.features[0]
+            .configuration
+            .as_mut()
+            .unwrap()
+            .targets[0]
+            .bucketing_key = String::new();
+
+        let config = convert_proto_to_config_body(proto_config).unwrap();
+        assert_eq!(
+            config.features[0].configuration.targets[0].bucketingkey,
+            "user_id"
+        );
+        assert!(
+            proto::ConfigBodyProto::decode(&[][..])
+                .unwrap()
+                .project
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,667 @@
+use crate::bucketing::explain::{AudienceTrace, FilterTrace, OperatorTrace};
+use crate::constants;
+use crate::segmentation::filters::{
+    AudienceOperator, Filter, FilterSubType, FilterType, NoIdAudience,
+};
+use crate::segmentation::ipcompare;
+use crate::segmentation::optin::OptInContext;
+use crate::user::PopulatedUser;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+// `=` and `!=` string lists longer than this are matched through a HashSet
+const STRING_SET_THRESHOLD: usize = 8;
+
+static VERSION_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+\.)*\d+").unwrap());
+
+/// An `AudienceOperator` lowered into typed filters with pre-parsed values, built once per
+/// config by `ConfigBody::compile` and evaluated for every user.
+#[derive(Debug)]
+pub(crate) struct CompiledOperator {
+    kind: OperatorKind,
+    filters: Vec<CompiledFilter>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OperatorKind {
+    And,
+    Or,
+    Unknown,
+}
+
+#[derive(Debug)]
+pub(crate) enum CompiledFilter {
+    All,
+    User(UserFilterPlan),
+    OptIn,
+    AudienceMatch {
+        audiences: Vec<Arc<CompiledOperator>>,
+        negate: bool,
+    },
+    // Filters with an unknown type but nested filters
+    Nested(CompiledOperator),
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Exist,
+    NotExist,
+    Contain,
+    NotContain,
+    StartWith,
+    NotStartWith,
+    EndWith,
+    NotEndWith,
+    Unknown,
+}
+
+#[derive(Debug)]
+pub(crate) struct UserFilterPlan {
+    // None for subtypes this SDK doesn't know, which never have a user value
+    sub_type: Option<FilterSubType>,
+    comparator: Comparator,
+    // customData filters read the key from their first value
+    custom_data_key: Option<String>,
+    has_values: bool,
+    matcher: ValueMatcher,
+}
+
+#[derive(Debug)]
+enum ValueMatcher {
+    Version(Vec<Option<ParsedVersion>>),
+    Ip {
+        addresses: Vec<IpAddr>,
+        blocks: Vec<(IpAddr, u8)>,
+    },
+    Json(CompiledValues),
+}
+
+// Filter values split by JSON type, so comparisons never have to look at the others
+#[derive(Debug, Default)]
+struct CompiledValues {
+    strings: Vec<String>,
+    string_set: Option<HashSet<String>>,
+    numbers: Vec<(Number, f64)>,
+    booleans: Vec<bool>,
+    others: Vec<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ParsedVersion {
+    parts: Vec<u32>,
+    prefix: String,
+    suffix: String,
+}
+
+#[derive(Clone, Copy)]
+enum UserValue<'a> {
+    Str(&'a str),
+    Json(&'a Value),
+}
+
+/// Lowers filters into plans, compiling each referenced audience once. A reference back to an
+/// audience that is still being compiled would be a cycle, and is dropped so it never matches.
+pub(crate) struct FilterCompiler<'a> {
+    audiences: &'a HashMap<String, NoIdAudience>,
+    compiled: HashMap<&'a str, Arc<CompiledOperator>>,
+    in_progress: HashSet<&'a str>,
+}
+
+impl<'a> FilterCompiler<'a> {
+    pub(crate) fn new(audiences: &'a HashMap<String, NoIdAudience>) -> Self {
+        FilterCompiler {
+            audiences,
+            compiled: HashMap::new(),
+            in_progress: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn compile_operator(&mut self, operator: &AudienceOperator) -> CompiledOperator {
+        CompiledOperator {
+            kind: OperatorKind::parse(&operator.operator),
+            filters: operator
+                .filters
+                .iter()
+                .map(|filter| self.compile_filter(filter))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn compile_filter(&mut self, filter: &Filter) -> CompiledFilter {
+        match FilterType::parse(&filter._type) {
+            Some(FilterType::All) => CompiledFilter::All,
+            Some(FilterType::User) => match &filter.sub_type {
+                Some(sub_type) => CompiledFilter::User(UserFilterPlan::new(filter, sub_type)),
+                None => CompiledFilter::Never,
+            },
+            Some(FilterType::OptIn) => CompiledFilter::OptIn,
+            Some(FilterType::AudienceMatch) => CompiledFilter::AudienceMatch {
+                audiences: filter
+                    ._audiences
+                    .iter()
+                    .filter_map(|id| self.compile_audience(id))
+                    .collect(),
+                negate: Comparator::parse(filter.comparator.as_deref()) == Comparator::NotEqual,
+            },
+            None if filter.filters.is_empty() => CompiledFilter::Never,
+            None => CompiledFilter::Nested(CompiledOperator {
+                kind: OperatorKind::parse(
+                    filter
+                        .operator
+                        .as_deref()
+                        .unwrap_or(constants::OPERATOR_AND),
+                ),
+                filters: filter
+                    .filters
+                    .iter()
+                    .map(|filter| self.compile_filter(filter))
+                    .collect(),
+            }),
+        }
+    }
+
+    fn compile_audience(&mut self, id: &str) -> Option<Arc<CompiledOperator>> {
+        let audiences = self.audiences;
+        let (id, audience) = audiences.get_key_value(id)?;
+        if let Some(compiled) = self.compiled.get(id.as_str()) {
+            return Some(compiled.clone());
+        }
+        if !self.in_progress.insert(id) {
+            return None;
+        }
+        let compiled = Arc::new(self.compile_operator(&audience.filters));
+        self.in_progress.remove(id.as_str());
+        self.compiled.insert(id, compiled.clone());
+        Some(compiled)
+    }
+
+    // Explaining compiles the same plans and reports each node's result with the inputs it saw
+    pub(crate) fn explain_operator(
+        &mut self,
+        operator: &AudienceOperator,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> OperatorTrace {
+        let compiled = self.compile_operator(operator);
+        OperatorTrace {
+            operator: operator.operator.clone(),
+            passed: compiled.evaluate(user, client_custom_data, opt_in),
+            filters: self.explain_filters(&operator.filters, user, client_custom_data, opt_in),
+        }
+    }
+
+    fn explain_filters(
+        &mut self,
+        filters: &[Filter],
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> Vec<FilterTrace> {
+        filters
+            .iter()
+            .map(|filter| self.explain_filter(filter, user, client_custom_data, opt_in))
+            .collect()
+    }
+
+    pub(crate) fn explain_filter(
+        &mut self,
+        filter: &Filter,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> FilterTrace {
+        let compiled = self.compile_filter(filter);
+        let passed = compiled.evaluate(user, client_custom_data, opt_in);
+        let audiences = match compiled {
+            CompiledFilter::AudienceMatch { .. } => filter
+                ._audiences
+                .iter()
+                .map(|id| self.explain_audience(id, user, client_custom_data, opt_in))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let filters = match compiled {
+            CompiledFilter::Nested(_) => Some(OperatorTrace {
+                operator: filter
+                    .operator
+                    .clone()
+                    .unwrap_or_else(|| constants::OPERATOR_AND.to_string()),
+                passed,
+                filters: self.explain_filters(&filter.filters, user, client_custom_data, opt_in),
+            }),
+            _ => None,
+        };
+        FilterTrace {
+            filter_type: filter._type.clone(),
+            sub_type: filter.sub_type.clone(),
+            comparator: filter.comparator.clone(),
+            values: filter.values.clone(),
+            user_value: compiled.user_value(user, client_custom_data),
+            passed,
+            audiences,
+            filters,
+        }
+    }
+
+    fn explain_audience(
+        &mut self,
+        id: &str,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> AudienceTrace {
+        let audiences = self.audiences;
+        let filters = match audiences.get_key_value(id) {
+            Some((id, audience)) if self.in_progress.insert(id) => {
+                let filters =
+                    self.explain_operator(&audience.filters, user, client_custom_data, opt_in);
+                self.in_progress.remove(id.as_str());
+                Some(filters)
+            }
+            _ => None,
+        };
+        AudienceTrace {
+            audience_id: id.to_string(),
+            passed: filters.as_ref().is_some_and(|filters| filters.passed),
+            filters,
+        }
+    }
+}
+
+impl CompiledOperator {
+    pub(crate) fn evaluate(
+        &self,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> bool {
+        // An empty AND passes and an empty OR fails
+        match self.kind {
+            OperatorKind::And => self
+                .filters
+                .iter()
+                .all(|filter| filter.evaluate(user, client_custom_data, opt_in)),
+            OperatorKind::Or => self
+                .filters
+                .iter()
+                .any(|filter| filter.evaluate(user, client_custom_data, opt_in)),
+            OperatorKind::Unknown => false,
+        }
+    }
+}
+
+impl OperatorKind {
+    fn parse(operator: &str) -> Self {
+        match operator {
+            constants::OPERATOR_AND => OperatorKind::And,
+            constants::OPERATOR_OR => OperatorKind::Or,
+            _ => OperatorKind::Unknown,
+        }
+    }
+}
+
+impl CompiledFilter {
+    pub(crate) fn evaluate(
+        &self,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+        opt_in: Option<&OptInContext>,
+    ) -> bool {
+        match self {
+            CompiledFilter::All => true,
+            CompiledFilter::User(plan) => plan.evaluate(user, client_custom_data),
+            // optIn filter fails unless opt-in is enabled and the user opted into this feature
+            CompiledFilter::OptIn => {
+                opt_in.is_some_and(|opt_in| opt_in.is_user_opted_in(&user.user_id))
+            }
+            CompiledFilter::AudienceMatch { audiences, negate } => {
+                let matches_any = audiences
+                    .iter()
+                    .any(|audience| audience.evaluate(user, client_custom_data, opt_in));
+                matches_any != *negate
+            }
+            CompiledFilter::Nested(operator) => operator.evaluate(user, client_custom_data, opt_in),
+            CompiledFilter::Never => false,
+        }
+    }
+
+    // The user value a user filter compares with
+    pub(crate) fn user_value(
+        &self,
+        user: &PopulatedUser,
+        client_custom_data: &HashMap<String, Value>,
+    ) -> Option<Value> {
+        match self {
+            CompiledFilter::User(plan) => match plan.user_value(user, client_custom_data)? {
+                UserValue::Str(value) => Some(Value::String(value.to_string())),
+                UserValue::Json(value) => Some(value.clone()),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Comparator {
+    // Filters without a comparator compare with "="
+    fn parse(comparator: Option<&str>) -> Self {
+        match comparator.unwrap_or(constants::COMPARATOR_EQUAL) {
+            constants::COMPARATOR_EQUAL => Comparator::Equal,
+            constants::COMPARATOR_NOT_EQUAL => Comparator::NotEqual,
+            constants::COMPARATOR_GREATER => Comparator::Greater,
+            constants::COMPARATOR_GREATER_EQUAL => Comparator::GreaterEqual,
+            constants::COMPARATOR_LESS => Comparator::Less,
+            constants::COMPARATOR_LESS_EQUAL => Comparator::LessEqual,
+            constants::COMPARATOR_EXIST => Comparator::Exist,
+            constants::COMPARATOR_NOT_EXIST => Comparator::NotExist,
+            constants::COMPARATOR_CONTAIN => Comparator::Contain,
+            constants::COMPARATOR_NOT_CONTAIN => Comparator::NotContain,
+            constants::COMPARATOR_START_WITH => Comparator::StartWith,
+            constants::COMPARATOR_NOT_START_WITH => Comparator::NotStartWith,
+            constants::COMPARATOR_END_WITH => Comparator::EndWith,
+            constants::COMPARATOR_NOT_END_WITH => Comparator::NotEndWith,
+            _ => Comparator::Unknown,
+        }
+    }
+
+    // Whether an ordering of user value to filter value satisfies >, >=, < or <=
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Comparator::Greater => ordering.is_gt(),
+            Comparator::GreaterEqual => ordering.is_ge(),
+            Comparator::Less => ordering.is_lt(),
+            Comparator::LessEqual => ordering.is_le(),
+            _ => false,
+        }
+    }
+}
+
+impl UserFilterPlan {
+    fn new(filter: &Filter, sub_type: &str) -> Self {
+        let sub_type = FilterSubType::parse(sub_type);
+        let string_values = || filter.values.iter().filter_map(Value::as_str);
+        let matcher = match sub_type {
+            Some(FilterSubType::AppVersion) | Some(FilterSubType::PlatformVersion) => {
+                ValueMatcher::Version(string_values().map(ParsedVersion::parse).collect())
+            }
+            Some(FilterSubType::IP) => ValueMatcher::Ip {
+                addresses: string_values().filter_map(ipcompare::parse_ip).collect(),
+                blocks: string_values().filter_map(ipcompare::parse_cidr).collect(),
+            },
+            _ => ValueMatcher::Json(CompiledValues::new(&filter.values)),
+        };
+        let custom_data_key = match sub_type {
+            Some(FilterSubType::CustomData) => filter
+                .values
+                .first()
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        };
+
+        UserFilterPlan {
+            sub_type,
+            comparator: Comparator::parse(filter.comparator.as_deref()),
+            custom_data_key,
+            has_values: !filter.values.is_empty(),
+            matcher,
+        }
+    }
+
+    fn evaluate(&self, user: &PopulatedUser, client_custom_data: &HashMap<String, Value>) -> bool {
+        let user_value = self.user_value(user, client_custom_data);
+        match self.comparator {
+            // An empty string counts as missing
+            Comparator::Exist => user_value.is_some_and(|value| !value.is_empty_string()),
+            Comparator::NotExist => user_value.is_none_or(|value| value.is_empty_string()),
+            _ if !self.has_values => false,
+            comparator => match user_value {
+                Some(value) => self.matcher.matches(comparator, value),
+                // A missing user value only passes "!="
+                None => comparator == Comparator::NotEqual,
+            },
+        }
+    }
+
+    fn user_value<'u>(
+        &self,
+        user: &'u PopulatedUser,
+        client_custom_data: &'u HashMap<String, Value>,
+    ) -> Option<UserValue<'u>> {
+        let value = match self.sub_type.as_ref()? {
+            FilterSubType::UserId => &user.user_id,
+            FilterSubType::Email => &user.email,
+            FilterSubType::IP => &user.ip,
+            FilterSubType::Country => &user.country,
+            FilterSubType::Platform => &user.platform_data.platform,
+            FilterSubType::PlatformVersion => &user.platform_data.platform_version,
+            FilterSubType::AppVersion => &user.app_version,
+            FilterSubType::DeviceModel => &user.device_model,
+            FilterSubType::CustomData => {
+                let key = self.custom_data_key.as_deref()?;
+                return user
+                    .custom_data
+                    .get(key)
+                    .or_else(|| client_custom_data.get(key))
+                    .or_else(|| user.private_custom_data.get(key))
+                    .map(UserValue::Json);
+            }
+        };
+        Some(UserValue::Str(value))
+    }
+}
+
+impl ValueMatcher {
+    fn matches(&self, comparator: Comparator, user_value: UserValue) -> bool {
+        match (self, user_value.as_str()) {
+            (ValueMatcher::Version(versions), Some(user_version)) => {
+                matches_version(versions, comparator, user_version)
+            }
+            (ValueMatcher::Ip { addresses, blocks }, Some(user_ip)) => {
+                matches_ip(addresses, blocks, comparator, user_ip)
+            }
+            (ValueMatcher::Json(values), _) => values.matches(comparator, user_value),
+            _ => false,
+        }
+    }
+}
+
+fn matches_version(
+    versions: &[Option<ParsedVersion>],
+    comparator: Comparator,
+    user_version: &str,
+) -> bool {
+    let user_version = ParsedVersion::parse(user_version);
+    let equals_any = || {
+        versions
+            .iter()
+            .any(|version| ParsedVersion::equal(&user_version, version))
+    };
+    match comparator {
+        Comparator::Equal => equals_any(),
+        Comparator::NotEqual => !equals_any(),
+        Comparator::Greater
+        | Comparator::GreaterEqual
+        | Comparator::Less
+        | Comparator::LessEqual => versions.iter().any(|version| {
+            ParsedVersion::compare(&user_version, version)
+                .is_some_and(|ordering| comparator.accepts(ordering))
+        }),
+        _ => false,
+    }
+}
+
+// IP filters compare parsed addresses rather than strings: "=" and "!=" match exact
+// addresses, "contain" and "!contain" test membership of CIDR blocks, and the ordering
+// comparators bound address ranges within the same address family.
+fn matches_ip(
+    addresses: &[IpAddr],
+    blocks: &[(IpAddr, u8)],
+    comparator: Comparator,
+    user_ip: &str,
+) -> bool {
+    let user_ip = match ipcompare::parse_ip(user_ip) {
+        Some(ip) => ip,
+        // An unparsable address matches no value, so only the negated comparators pass
+        None => return matches!(comparator, Comparator::NotEqual | Comparator::NotContain),
+    };
+    let in_any_block = || {
+        blocks
+            .iter()
+            .any(|&(network, prefix_len)| ipcompare::block_contains(network, prefix_len, &user_ip))
+    };
+    match comparator {
+        Comparator::Equal => addresses.contains(&user_ip),
+        Comparator::NotEqual => !addresses.contains(&user_ip),
+        Comparator::Contain => in_any_block(),
+        Comparator::NotContain => !in_any_block(),
+        Comparator::Greater
+        | Comparator::GreaterEqual
+        | Comparator::Less
+        | Comparator::LessEqual => addresses.iter().any(|ip| {
+            ipcompare::compare_ips(&user_ip, ip)
+                .is_some_and(|ordering| comparator.accepts(ordering))
+        }),
+        _ => false,
+    }
+}
+
+impl CompiledValues {
+    fn new(values: &[Value]) -> Self {
+        let mut compiled = CompiledValues::default();
+        for value in values {
+            match value {
+                Value::String(s) => compiled.strings.push(s.clone()),
+                Value::Number(n) => compiled
+                    .numbers
+                    .push((n.clone(), n.as_f64().unwrap_or(0.0))),
+                Value::Bool(b) => compiled.booleans.push(*b),
+                other => compiled.others.push(other.clone()),
+            }
+        }
+        if compiled.strings.len() > STRING_SET_THRESHOLD {
+            compiled.string_set = Some(compiled.strings.iter().cloned().collect());
+        }
+        compiled
+    }
+
+    // JSON equality against any value, numbers compare as serde_json does so 1 != 1.0
+    fn contains(&self, user_value: UserValue) -> bool {
+        if let Some(s) = user_value.as_str() {
+            return match &self.string_set {
+                Some(set) => set.contains(s),
+                None => self.strings.iter().any(|value| value == s),
+            };
+        }
+        match user_value {
+            UserValue::Str(_) => false,
+            UserValue::Json(Value::Number(n)) => self.numbers.iter().any(|(value, _)| value == n),
+            UserValue::Json(Value::Bool(b)) => self.booleans.contains(b),
+            UserValue::Json(other) => self.others.contains(other),
+        }
+    }
+
+    fn matches(&self, comparator: Comparator, user_value: UserValue) -> bool {
+        // String comparators ignore empty filter values and never match non-string user values
+        let any_string = |test: fn(&str, &str) -> bool| {
+            user_value.as_str().is_some_and(|user_str| {
+                self.strings
+                    .iter()
+                    .any(|value| !value.is_empty() && test(user_str, value))
+            })
+        };
+        match comparator {
+            Comparator::Equal => self.contains(user_value),
+            Comparator::NotEqual => !self.contains(user_value),
+            Comparator::Contain => any_string(|user, value| user.contains(value)),
+            Comparator::NotContain => !any_string(|user, value| user.contains(value)),
+            Comparator::StartWith => any_string(|user, value| user.starts_with(value)),
+            Comparator::NotStartWith => !any_string(|user, value| user.starts_with(value)),
+            Comparator::EndWith => any_string(|user, value| user.ends_with(value)),
+            Comparator::NotEndWith => !any_string(|user, value| user.ends_with(value)),
+            Comparator::Greater
+            | Comparator::GreaterEqual
+            | Comparator::Less
+            | Comparator::LessEqual => match user_value {
+                UserValue::Json(Value::Number(n)) => {
+                    let user_number = n.as_f64().unwrap_or(0.0);
+                    self.numbers.iter().any(|&(_, value)| {
+                        user_number
+                            .partial_cmp(&value)
+                            .is_some_and(|ordering| comparator.accepts(ordering))
+                    })
+                }
+                _ => false,
+            },
+            Comparator::Exist => true,
+            Comparator::NotExist | Comparator::Unknown => false,
+        }
+    }
+}
+
+impl ParsedVersion {
+    // Takes the first run of dot separated numbers, e.g. 1.2.3 from "v1.2.3-beta"
+    fn parse(value: &str) -> Option<ParsedVersion> {
+        let found = VERSION_PATTERN.find(value)?;
+        Some(ParsedVersion {
+            parts: found
+                .as_str()
+                .split('.')
+                .filter_map(|part| part.parse::<u32>().ok())
+                .collect(),
+            prefix: value[..found.start()].to_string(),
+            suffix: value[found.end()..].trim_start_matches('.').to_string(),
+        })
+    }
+
+    // Equal versions need the same parts, prefix and suffix, so "1.0" != "1.0.0"
+    fn equal(a: &Option<ParsedVersion>, b: &Option<ParsedVersion>) -> bool {
+        matches!((a, b), (Some(a), Some(b)) if a == b)
+    }
+
+    // Compares the numeric parts with missing trailing parts treated as zero. None when only
+    // one side has a version, so no ordering comparator passes.
+    fn compare(a: &Option<ParsedVersion>, b: &Option<ParsedVersion>) -> Option<Ordering> {
+        let empty: &[u32] = &[];
+        let a_parts = a.as_ref().map_or(empty, |version| version.parts.as_slice());
+        let b_parts = b.as_ref().map_or(empty, |version| version.parts.as_slice());
+        match (a_parts.is_empty(), b_parts.is_empty()) {
+            (true, true) => return Some(Ordering::Equal),
+            (false, false) => {}
+            _ => return None,
+        }
+        for i in 0..a_parts.len().max(b_parts.len()) {
+            let a_part = a_parts.get(i).copied().unwrap_or(0);
+            let b_part = b_parts.get(i).copied().unwrap_or(0);
+            if a_part != b_part {
+                return Some(a_part.cmp(&b_part));
+            }
+        }
+        Some(Ordering::Equal)
+    }
+}
+
+impl UserValue<'_> {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            UserValue::Str(s) => Some(s),
+            UserValue::Json(value) => value.as_str(),
+        }
+    }
+
+    fn is_empty_string(&self) -> bool {
+        self.as_str().is_some_and(str::is_empty)
+    }
+}
@@ -0,0 +1,201 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::ConfigBody;
+    use crate::filters::{AudienceOperator, Filter, NoIdAudience};
+    use crate::segmentation::compiled_filters::FilterCompiler;
+    use crate::user::PopulatedUser;
+    use crate::util::test_fixtures;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    fn create_user(user_id: &str) -> PopulatedUser {
+        PopulatedUser {
+            email: format!("{}@example.com", user_id),
+            language: "en".to_string(),
+            country: "CA".to_string(),
+            ip: "10.1.2.3".to_string(),
+            app_version: "2.4.0".to_string(),
+            custom_data: HashMap::from([("plan".to_string(), json!("enterprise"))]),
+            device_model: "Pixel".to_string(),
+            ..test_fixtures::create_user(user_id)
+        }
+    }
+
+    fn operator(value: Value) -> AudienceOperator {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn audiences(value: Value) -> HashMap<String, NoIdAudience> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn evaluate_compiled(
+        operator: &AudienceOperator,
+        audiences: &HashMap<String, NoIdAudience>,
+        user: &PopulatedUser,
+    ) -> bool {
+        FilterCompiler::new(audiences)
+            .compile_operator(operator)
+            .evaluate(user, &HashMap::new(), None)
+    }
+
+    #[test]
+    fn test_large_value_lists_match_like_small_ones() {
+        let user = create_user("user-42");
+        let mut values: Vec<Value> = (0..100).map(|i| json!(format!("user-{}", i))).collect();
+        values.push(json!(42));
+        for (comparator, expected) in [("=", true), ("!=", false)] {
+            let large = operator(json!({
+                "operator": "and",
+                "filters": [{"type": "user", "subType": "user_id", "comparator": comparator, "values": values}]
+            }));
+            let small = operator(json!({
+                "operator": "and",
+                "filters": [{"type": "user", "subType": "user_id", "comparator": comparator, "values": ["user-42"]}]
+            }));
+            assert_eq!(evaluate_compiled(&large, &HashMap::new(), &user), expected);
+            assert_eq!(evaluate_compiled(&small, &HashMap::new(), &user), expected);
+        }
+
+        let miss = create_user("user-420");
+        let large = operator(json!({
+            "operator": "and",
+            "filters": [{"type": "user", "subType": "user_id", "comparator": "=", "values": values}]
+        }));
+        assert!(!evaluate_compiled(&large, &HashMap::new(), &miss));
+    }
+
+    #[test]
+    fn test_numbers_keep_json_equality() {
+        let mut user = create_user("user");
+        user.custom_data.insert("seats".to_string(), json!(10));
+        let filter = |values: Value| {
+            operator(json!({
+                "operator": "and",
+                "filters": [{"type": "user", "subType": "customData", "comparator": "=", "values": values}]
+            }))
+        };
+        assert!(evaluate_compiled(
+            &filter(json!(["seats", 10])),
+            &HashMap::new(),
+            &user
+        ));
+        assert!(!evaluate_compiled(
+            &filter(json!(["seats", 10.0])),
+            &HashMap::new(),
+            &user
+        ));
+
+        let greater = operator(json!({
+            "operator": "and",
+            "filters": [{"type": "user", "subType": "customData", "comparator": ">", "values": ["seats", 9.5]}]
+        }));
+        assert!(evaluate_compiled(&greater, &HashMap::new(), &user));
+    }
+
+    #[test]
+    fn test_versions_and_ips_are_parsed_once() {
+        let user = create_user("user");
+        let version = |comparator: &str, values: Value| {
+            operator(json!({
+                "operator": "and",
+                "filters": [{"type": "user", "subType": "appVersion", "comparator": comparator, "values": values}]
+            }))
+        };
+        assert!(evaluate_compiled(
+            &version(">", json!(["2.3.9", "3"])),
+            &HashMap::new(),
+            &user
+        ));
+        assert!(evaluate_compiled(
+            &version(">=", json!(["2.4"])),
+            &HashMap::new(),
+            &user
+        ));
+        assert!(!evaluate_compiled(
+            &version("=", json!(["2.4"])),
+            &HashMap::new(),
+            &user
+        ));
+        assert!(evaluate_compiled(
+            &version("=", json!(["2.4.0"])),
+            &HashMap::new(),
+            &user
+        ));
+        assert!(!evaluate_compiled(
+            &version("<", json!(["beta"])),
+            &HashMap::new(),
+            &user
+        ));
+
+        let ip = operator(json!({
+            "operator": "and",
+            "filters": [{"type": "user", "subType": "ip", "comparator": "contain", "values": ["not-a-block", "10.0.0.0/8"]}]
+        }));
+        assert!(evaluate_compiled(&ip, &HashMap::new(), &user));
+    }
+
+    #[test]
+    fn test_audience_references_are_resolved() {
+        let audiences = audiences(json!({
+            "canada": {"filters": {"operator": "and", "filters": [
+                {"type": "user", "subType": "country", "comparator": "=", "values": ["CA"]}
+            ]}},
+            "enterprise": {"filters": {"operator": "and", "filters": [
+                {"type": "audienceMatch", "comparator": "=", "_audiences": ["canada"]},
+                {"type": "user", "subType": "customData", "comparator": "=", "values": ["plan", "enterprise"]}
+            ]}}
+        }));
+        let user = create_user("user");
+        let matches = operator(json!({
+            "operator": "and",
+            "filters": [{"type": "audienceMatch", "comparator": "=", "_audiences": ["missing", "enterprise"]}]
+        }));
+        let excludes = operator(json!({
+            "operator": "and",
+            "filters": [{"type": "audienceMatch", "comparator": "!=", "_audiences": ["canada"]}]
+        }));
+
+        let mut compiler = FilterCompiler::new(&audiences);
+        let matches_plan = compiler.compile_operator(&matches);
+        let excludes_plan = compiler.compile_operator(&excludes);
+        assert!(matches_plan.evaluate(&user, &HashMap::new(), None));
+        assert!(!excludes_plan.evaluate(&user, &HashMap::new(), None));
+    }
+
+    #[test]
+    fn test_cyclic_audiences_do_not_match() {
+        let audiences = audiences(json!({
+            "a": {"filters": {"operator": "or", "filters": [
+                {"type": "user", "subType": "country", "comparator": "=", "values": ["US"]},
+                {"type": "audienceMatch", "comparator": "=", "_audiences": ["b"]}
+            ]}},
+            "b": {"filters": {"operator": "and", "filters": [
+                {"type": "audienceMatch", "comparator": "=", "_audiences": ["a"]}
+            ]}}
+        }));
+        let filter: Filter = serde_json::from_value(
+            json!({"type": "audienceMatch", "comparator": "=", "_audiences": ["a"]}),
+        )
+        .unwrap();
+
+        let mut user = create_user("user");
+        assert!(!filter.evaluate(&audiences, &mut user, &HashMap::new()));
+        user.country = "US".to_string();
+        assert!(filter.evaluate(&audiences, &mut user, &HashMap::new()));
+    }
+
+    #[test]
+    fn test_config_compile_attaches_plans_to_targets() {
+        let full_config =
+            serde_json::from_str(include_str!("../../tests/resources/test_config.json")).unwrap();
+        let config = ConfigBody::from_full_config(full_config).unwrap();
+        assert!(config.features.iter().all(|feature| {
+            feature
+                .configuration
+                .targets
+                .iter()
+                .all(|target| target.compiled_audience.is_some())
+        }));
+    }
+}
@@ -2,6 +2,7 @@
 mod tests {
     use crate::constants;
     use crate::filters::*;
+    use crate::segmentation::optin::{InMemoryOptInStore, OptInContext, OptInStore};
     use std::collections::HashMap;
 
     use crate::config::platform_data::{self, PlatformData};
@@ -36,6 +37,7 @@ mod tests {
             name: "Test User".to_string(),
             language: "en".to_string(),
             country: "US".to_string(),
+            ip: String::new(),
             app_version: "1.2.3".to_string(),
             app_build: "456".to_string(),
             custom_data: {
@@ -65,6 +67,7 @@ mod tests {
             name: "Brooks".to_string(),
             language: "en".to_string(),
             country: "Canada".to_string(),
+            ip: String::new(),
             app_version: "2.0.2".to_string(),
             app_build: "100".to_string(),
             custom_data: HashMap::new(),
@@ -301,6 +304,7 @@ mod tests {
             name: "".to_string(),
             language: "".to_string(),
             country: "".to_string(),
+            ip: String::new(),
             app_version: "".to_string(),
             app_build: "".to_string(),
             custom_data: HashMap::new(),
@@ -856,6 +860,7 @@ mod tests {
             name: "Brooks".to_string(),
             language: "en".to_string(),
             country: "Canada".to_string(),
+            ip: String::new(),
             app_version: "2.0.2".to_string(),
             app_build: "100".to_string(),
             custom_data: {
@@ -924,6 +929,7 @@ mod tests {
             name: "Brooks".to_string(),
             language: "en".to_string(),
             country: "Canada".to_string(),
+            ip: String::new(),
             app_version: "2.0.2".to_string(),
             app_build: "100".to_string(),
             custom_data: HashMap::new(),
@@ -1042,6 +1048,7 @@ mod tests {
             name: "".to_string(),
             language: "".to_string(),
             country: "".to_string(),
+            ip: String::new(),
             app_version: "".to_string(),
             app_build: "".to_string(),
             custom_data: HashMap::new(),
@@ -1073,6 +1080,7 @@ mod tests {
             name: "".to_string(),
             language: "".to_string(),
             country: "CA".to_string(),
+            ip: String::new(),
             app_version: "".to_string(),
             app_build: "".to_string(),
             custom_data: HashMap::new(),
@@ -1142,6 +1150,7 @@ mod tests {
             name: "".to_string(),
             language: "".to_string(),
             country: "".to_string(),
+            ip: String::new(),
             app_version: "".to_string(),
             app_build: "".to_string(),
             custom_data: HashMap::new(),
@@ -1270,6 +1279,7 @@ mod tests {
             name: "".to_string(),
             language: "".to_string(),
             country: "".to_string(),
+            ip: String::new(),
             app_version: "".to_string(),
             app_build: "".to_string(),
             custom_data: HashMap::new(),
@@ -3318,4 +3328,233 @@ mod tests {
 
         assert!(filter.evaluate(&audiences, &mut user, &client_custom_data));
     }
+
+    fn ip_filter(comparator: &str, values: &[&str]) -> Filter {
+        Filter {
+            _type: constants::TYPE_USER.to_string(),
+            sub_type: Some(constants::SUB_TYPE_IP.to_string()),
+            comparator: Some(comparator.to_string()),
+            values: values
+                .iter()
+                .map(|v| serde_json::Value::String(v.to_string()))
+                .collect(),
+            filters: vec![],
+            operator: None,
+            _audiences: vec![],
+        }
+    }
+
+    fn evaluate_ip_filter(filter: &Filter, ip: &str) -> bool {
+        let mut user = create_test_user();
+        user.ip = ip.to_string();
+        let audiences: HashMap<String, NoIdAudience> = HashMap::new();
+        let client_custom_data: HashMap<String, serde_json::Value> = HashMap::new();
+        filter.evaluate(&audiences, &mut user, &client_custom_data)
+    }
+
+    #[test]
+    fn test_ip_filter_equal() {
+        let filter = ip_filter(constants::COMPARATOR_EQUAL, &["10.0.0.1", "2001:db8::1"]);
+        assert!(evaluate_ip_filter(&filter, "10.0.0.1"));
+        assert!(evaluate_ip_filter(&filter, "2001:0db8:0000::0001"));
+        assert!(evaluate_ip_filter(&filter, "::ffff:10.0.0.1"));
+        assert!(!evaluate_ip_filter(&filter, "10.0.0.2"));
+        assert!(!evaluate_ip_filter(&filter, "not-an-ip"));
+        assert!(!evaluate_ip_filter(&filter, ""));
+    }
+
+    #[test]
+    fn test_ip_filter_not_equal() {
+        let filter = ip_filter(constants::COMPARATOR_NOT_EQUAL, &["10.0.0.1"]);
+        assert!(!evaluate_ip_filter(&filter, "10.0.0.1"));
+        assert!(evaluate_ip_filter(&filter, "10.0.0.2"));
+        assert!(evaluate_ip_filter(&filter, "not-an-ip"));
+    }
+
+    #[test]
+    fn test_ip_filter_cidr_ipv4() {
+        let filter = ip_filter(
+            constants::COMPARATOR_CONTAIN,
+            &["192.168.0.0/16", "10.1.2.0/24"],
+        );
+        assert!(evaluate_ip_filter(&filter, "192.168.44.7"));
+        assert!(evaluate_ip_filter(&filter, "10.1.2.255"));
+        assert!(!evaluate_ip_filter(&filter, "10.1.3.0"));
+        assert!(!evaluate_ip_filter(&filter, "2001:db8::1"));
+
+        let everything = ip_filter(constants::COMPARATOR_CONTAIN, &["0.0.0.0/0"]);
+        assert!(evaluate_ip_filter(&everything, "8.8.8.8"));
+
+        let malformed = ip_filter(constants::COMPARATOR_CONTAIN, &["10.0.0.0/33", "bogus"]);
+        assert!(!evaluate_ip_filter(&malformed, "10.0.0.1"));
+    }
+
+    #[test]
+    fn test_ip_filter_cidr_ipv6() {
+        let filter = ip_filter(constants::COMPARATOR_CONTAIN, &["2001:db8::/32"]);
+        assert!(evaluate_ip_filter(&filter, "2001:db8:abcd::42"));
+        assert!(!evaluate_ip_filter(&filter, "2001:db9::1"));
+        assert!(!evaluate_ip_filter(&filter, "10.0.0.1"));
+
+        let not_filter = ip_filter(constants::COMPARATOR_NOT_CONTAIN, &["2001:db8::/32"]);
+        assert!(!evaluate_ip_filter(&not_filter, "2001:db8:abcd::42"));
+        assert!(evaluate_ip_filter(&not_filter, "2001:db9::1"));
+    }
+
+    #[test]
+    fn test_ip_filter_range() {
+        let audiences: HashMap<String, NoIdAudience> = HashMap::new();
+        let client_custom_data: HashMap<String, serde_json::Value> = HashMap::new();
+        let range = AudienceOperator {
+            operator: constants::OPERATOR_AND.to_string(),
+            filters: vec![
+                ip_filter(constants::COMPARATOR_GREATER_EQUAL, &["10.0.0.10"]),
+                ip_filter(constants::COMPARATOR_LESS, &["10.0.0.20"]),
+            ],
+        };
+
+        let mut user = create_test_user();
+        for (ip, expected) in [
+            ("10.0.0.9", false),
+            ("10.0.0.10", true),
+            ("10.0.0.19", true),
+            ("10.0.0.20", false),
+            ("::1", false),
+        ] {
+            user.ip = ip.to_string();
+            assert_eq!(
+                range.evaluate(&audiences, &mut user, &client_custom_data),
+                expected,
+                "unexpected result for {}",
+                ip
+            );
+        }
+
+        assert!(evaluate_ip_filter(
+            &ip_filter(constants::COMPARATOR_GREATER, &["fe80::1"]),
+            "fe80::2"
+        ));
+        assert!(evaluate_ip_filter(
+            &ip_filter(constants::COMPARATOR_LESS_EQUAL, &["fe80::1"]),
+            "fe80::1"
+        ));
+    }
+
+    #[test]
+    fn test_ip_filter_exists() {
+        let filter = ip_filter(constants::COMPARATOR_EXIST, &[]);
+        assert!(evaluate_ip_filter(&filter, "10.0.0.1"));
+        assert!(!evaluate_ip_filter(&filter, ""));
+    }
+
+    fn opt_in_filter() -> Filter {
+        Filter {
+            _type: constants::TYPE_OPT_IN.to_string(),
+            sub_type: None,
+            comparator: Some(constants::COMPARATOR_EQUAL.to_string()),
+            values: vec![],
+            filters: vec![],
+            operator: None,
+            _audiences: vec![],
+        }
+    }
+
+    fn evaluate_opt_in_filter(filter: &Filter, opt_in: Option<&OptInContext>) -> bool {
+        let mut user = create_test_user();
+        let audiences: HashMap<String, NoIdAudience> = HashMap::new();
+        let client_custom_data: HashMap<String, serde_json::Value> = HashMap::new();
+        filter.evaluate_with_opt_in(&audiences, &mut user, &client_custom_data, opt_in)
+    }
+
+    #[test]
+    fn test_optin_filter_passes_for_opted_in_user() {
+        let store = InMemoryOptInStore::new();
+        store.opt_in("test_user_123", "feature_a");
+        let opt_in = OptInContext {
+            store: &store,
+            feature_id: "feature_a",
+            enabled: true,
+        };
+        assert!(evaluate_opt_in_filter(&opt_in_filter(), Some(&opt_in)));
+    }
+
+    #[test]
+    fn test_optin_filter_fails_for_other_feature() {
+        let store = InMemoryOptInStore::new();
+        store.opt_in("test_user_123", "feature_b");
+        let opt_in = OptInContext {
+            store: &store,
+            feature_id: "feature_a",
+            enabled: true,
+        };
+        assert!(!evaluate_opt_in_filter(&opt_in_filter(), Some(&opt_in)));
+    }
+
+    #[test]
+    fn test_optin_filter_fails_when_project_opt_in_disabled() {
+        let store = InMemoryOptInStore::new();
+        store.opt_in("test_user_123", "feature_a");
+        let opt_in = OptInContext {
+            store: &store,
+            feature_id: "feature_a",
+            enabled: false,
+        };
+        assert!(!evaluate_opt_in_filter(&opt_in_filter(), Some(&opt_in)));
+        assert!(!evaluate_opt_in_filter(&opt_in_filter(), None));
+    }
+
+    #[test]
+    fn test_optin_filter_fails_after_opt_out() {
+        let store = InMemoryOptInStore::new();
+        store.opt_in("test_user_123", "feature_a");
+        store.opt_out("test_user_123", "feature_a");
+        let opt_in = OptInContext {
+            store: &store,
+            feature_id: "feature_a",
+            enabled: true,
+        };
+        assert!(!evaluate_opt_in_filter(&opt_in_filter(), Some(&opt_in)));
+    }
+
+    #[test]
+    fn test_optin_filter_in_nested_audience_match() {
+        let store = InMemoryOptInStore::new();
+        store.opt_in("test_user_123", "feature_a");
+        let opt_in = OptInContext {
+            store: &store,
+            feature_id: "feature_a",
+            enabled: true,
+        };
+        let mut audiences: HashMap<String, NoIdAudience> = HashMap::new();
+        audiences.insert(
+            "opted_in".to_string(),
+            NoIdAudience {
+                filters: AudienceOperator {
+                    operator: constants::OPERATOR_AND.to_string(),
+                    filters: vec![opt_in_filter()],
+                },
+            },
+        );
+        let operator = AudienceOperator {
+            operator: constants::OPERATOR_AND.to_string(),
+            filters: vec![Filter {
+                _type: constants::TYPE_AUDIENCE_MATCH.to_string(),
+                sub_type: None,
+                comparator: Some(constants::COMPARATOR_EQUAL.to_string()),
+                values: vec![],
+                filters: vec![],
+                operator: None,
+                _audiences: vec!["opted_in".to_string()],
+            }],
+        };
+        let mut user = create_test_user();
+        let client_custom_data: HashMap<String, serde_json::Value> = HashMap::new();
+        assert!(operator.evaluate_with_opt_in(
+            &audiences,
+            &mut user,
+            &client_custom_data,
+            Some(&opt_in)
+        ));
+        assert!(!operator.evaluate(&audiences, &mut user, &client_custom_data));
+    }
 }
@@ -0,0 +1,57 @@
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+// Parse an address, treating IPv4-mapped IPv6 addresses (::ffff:a.b.c.d) as IPv4
+pub(crate) fn parse_ip(value: &str) -> Option<IpAddr> {
+    value
+        .trim()
+        .parse::<IpAddr>()
+        .ok()
+        .map(|ip| ip.to_canonical())
+}
+
+// Parse a CIDR block such as "10.0.0.0/8" or "2001:db8::/32".
+// A bare address is treated as a single-host block.
+pub(crate) fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    let value = value.trim();
+    let (address, prefix) = match value.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (value, None),
+    };
+    let network = parse_ip(address)?;
+    let max_prefix = match network {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    };
+    let prefix_len = match prefix {
+        Some(prefix) => prefix.trim().parse::<u8>().ok()?,
+        None => max_prefix,
+    };
+    if prefix_len > max_prefix {
+        return None;
+    }
+    Some((network, prefix_len))
+}
+
+pub(crate) fn block_contains(network: IpAddr, prefix_len: u8, ip: &IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            (u32::from(network) & mask) == (u32::from(*ip) & mask)
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            (u128::from(network) & mask) == (u128::from(*ip) & mask)
+        }
+        _ => false,
+    }
+}
+
+// Numeric ordering of two addresses. Addresses from different families are not comparable.
+pub(crate) fn compare_ips(a: &IpAddr, b: &IpAddr) -> Option<Ordering> {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => Some(a.cmp(b)),
+        (IpAddr::V6(a), IpAddr::V6(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
@@ -1,7 +1,11 @@
+pub(crate) mod compiled_filters;
 pub(crate) mod filters;
+pub(crate) mod ipcompare;
+pub(crate) mod optin;
 pub(crate) mod target;
-pub(crate) mod versioncompare;
 
+#[cfg(test)]
+mod compiled_filters_tests;
 #[cfg(test)]
 mod filter_tests;
 
@@ -0,0 +1,84 @@
+use crate::client::client_manager;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// Records which features each user has opted into. Used by the `optIn` filter when the
+/// project has opt-in enabled.
+pub trait OptInStore: Send + Sync {
+    fn is_opted_in(&self, user_id: &str, feature_id: &str) -> bool;
+    fn opt_in(&self, user_id: &str, feature_id: &str);
+    fn opt_out(&self, user_id: &str, feature_id: &str);
+}
+
+/// Default `OptInStore` keeping opted-in feature IDs per user in memory.
+#[derive(Default)]
+pub struct InMemoryOptInStore {
+    opt_ins: RwLock<HashMap<String, HashSet<String>>>,
+}
+
+impl InMemoryOptInStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OptInStore for InMemoryOptInStore {
+    fn is_opted_in(&self, user_id: &str, feature_id: &str) -> bool {
+        let opt_ins = self
+            .opt_ins
+            .read()
+            .expect("Failed to acquire read lock on opt-ins: lock poisoned");
+        opt_ins
+            .get(user_id)
+            .is_some_and(|features| features.contains(feature_id))
+    }
+
+    fn opt_in(&self, user_id: &str, feature_id: &str) {
+        let mut opt_ins = self
+            .opt_ins
+            .write()
+            .expect("Failed to acquire write lock on opt-ins: lock poisoned");
+        opt_ins
+            .entry(user_id.to_string())
+            .or_default()
+            .insert(feature_id.to_string());
+    }
+
+    fn opt_out(&self, user_id: &str, feature_id: &str) {
+        let mut opt_ins = self
+            .opt_ins
+            .write()
+            .expect("Failed to acquire write lock on opt-ins: lock poisoned");
+        if let Some(features) = opt_ins.get_mut(user_id) {
+            features.remove(feature_id);
+            if features.is_empty() {
+                opt_ins.remove(user_id);
+            }
+        }
+    }
+}
+
+/// Get the opt-in store for an SDK key, an in-memory store if none was set. None when the SDK
+/// key has no client yet.
+pub fn get_opt_in_store(sdk_key: &str) -> Option<Arc<dyn OptInStore>> {
+    client_manager::get_client(sdk_key).map(|client| client.state.get_opt_in_store())
+}
+
+pub fn set_opt_in_store(sdk_key: &str, store: Arc<dyn OptInStore>) {
+    client_manager::get_or_create_client(sdk_key)
+        .state
+        .set_opt_in_store(store);
+}
+
+// What the optIn filter needs to know about the feature being evaluated
+pub(crate) struct OptInContext<'a> {
+    pub(crate) store: &'a dyn OptInStore,
+    pub(crate) feature_id: &'a str,
+    pub(crate) enabled: bool,
+}
+
+impl OptInContext<'_> {
+    pub(crate) fn is_user_opted_in(&self, user_id: &str) -> bool {
+        self.enabled && self.store.is_opted_in(user_id, self.feature_id)
+    }
+}
@@ -1,9 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{DevCycleError, FAILED_TO_DECIDE_VARIATION};
+use crate::bucketing::rollout_timeline::RolloutTimeline;
+use crate::errors::{self, DevCycleError};
 use crate::filters::{AudienceOperator, NoIdAudience};
 use crate::murmurhash::murmurhash;
+use crate::segmentation::compiled_filters::CompiledOperator;
+use std::borrow::Cow;
+use std::sync::Arc;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Target {
@@ -15,26 +19,60 @@ pub(crate) struct Target {
     pub(crate) distribution: Vec<TargetDistribution>,
     #[serde(rename = "bucketingKey", default = "default_bucketing_key")]
     pub(crate) bucketingkey: String,
+    // The audience lowered by ConfigBody::compile
+    #[serde(skip)]
+    pub(crate) compiled_audience: Option<Arc<CompiledOperator>>,
+    // The rollout's timeline, built by ConfigBody::compile
+    #[serde(skip)]
+    pub(crate) compiled_rollout: Option<Arc<RolloutTimeline>>,
 }
 
 impl Target {
+    // None without a rollout or with a malformed one. Rollouts ConfigBody::compile couldn't
+    // build a timeline for are rebuilt on every call.
+    pub(crate) fn rollout_timeline(&self) -> Option<Cow<'_, RolloutTimeline>> {
+        match (&self.compiled_rollout, &self.rollout) {
+            (Some(timeline), _) => Some(Cow::Borrowed(timeline.as_ref())),
+            (None, Some(rollout)) => RolloutTimeline::from_rollout(rollout).ok().map(Cow::Owned),
+            (None, None) => None,
+        }
+    }
+
+    // The share of users the rollout lets in at `now`, None without a rollout. A malformed
+    // rollout lets nobody in.
+    pub(crate) fn rollout_percentage(&self, now: DateTime<Utc>) -> Option<f64> {
+        self.rollout.as_ref()?;
+        Some(
+            self.rollout_timeline()
+                .map_or(0.0, |timeline| timeline.percentage_at(now)),
+        )
+    }
+
     pub(crate) fn decide_target_variation(
         self,
         bounded_hash: f64,
     ) -> Result<(String, bool), DevCycleError> {
-        let mut distribution_index: f64 = 0.0;
-        let previous_distribution_index: f64 = 0.0;
         let is_randomized = self.distribution.len() > 1;
-        for d in self.distribution {
-            distribution_index += d.percentage;
-            if bounded_hash >= previous_distribution_index
-                && (bounded_hash < distribution_index
-                    || (distribution_index == 1.0 && bounded_hash == 1.0))
-            {
-                return Ok((d.variation, is_randomized));
+        match self.find_distribution(bounded_hash) {
+            Some((d, _)) => Ok((d.variation.clone(), is_randomized)),
+            None => Err(errors::failed_to_decide_variation()),
+        }
+    }
+
+    // The distribution entry covering the hash, with the cumulative percentage it starts at
+    pub(crate) fn find_distribution(
+        &self,
+        bounded_hash: f64,
+    ) -> Option<(&TargetDistribution, f64)> {
+        let mut distribution_start: f64 = 0.0;
+        for d in &self.distribution {
+            let distribution_end = distribution_start + d.percentage;
+            if bounded_hash < distribution_end || (distribution_end == 1.0 && bounded_hash == 1.0) {
+                return Some((d, distribution_start));
             }
+            distribution_start = distribution_end;
         }
-        return Err(FAILED_TO_DECIDE_VARIATION);
+        None
     }
 }
 
@@ -19,6 +19,9 @@ pub struct User {
     pub language: String,
     // User's country in ISO 3166 alpha-2 format
     pub country: String,
+    // User's IP address (IPv4 or IPv6) used to target the user by network
+    #[serde(default)]
+    pub ip: String,
     // App Version of the running application
     pub app_version: String,
     // App Build number of the running application
@@ -56,6 +59,7 @@ impl User {
             custom_data: self.custom_data.clone(),
             language: self.language.clone(),
             country: self.country.clone(),
+            ip: self.ip.clone(),
             app_version: self.app_version.clone(),
             app_build: self.app_build.clone(),
             device_model: self.device_model.clone(),
@@ -84,6 +88,9 @@ pub struct PopulatedUser {
     pub language: String,
     // User's country in ISO 3166 alpha-2 format
     pub country: String,
+    // User's IP address (IPv4 or IPv6) used to target the user by network
+    #[serde(default)]
+    pub ip: String,
     // App Version of the running application
     #[serde(rename = "appVersion")]
     pub app_version: String,
@@ -145,6 +152,7 @@ impl PopulatedUser {
             custom_data: user.custom_data.clone(),
             language: user.language,
             country: user.country.clone(),
+            ip: user.ip.clone(),
             app_version: user.app_version.clone(),
             app_build: user.app_build.clone(),
             device_model: user.device_model.clone(),
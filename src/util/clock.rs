@@ -0,0 +1,56 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::RwLock;
+
+/// Source of the current time for rollout evaluation. Each evaluation reads the clock once, so
+/// every feature in a bucketed config is evaluated as of the same instant.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Default `Clock` reading the system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A `Clock` that stays at the time it was given until it is set or advanced.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        ManualClock {
+            now: RwLock::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self
+            .now
+            .write()
+            .expect("Failed to acquire write lock on clock: lock poisoned") = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self
+            .now
+            .write()
+            .expect("Failed to acquire write lock on clock: lock poisoned");
+        *now += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self
+            .now
+            .read()
+            .expect("Failed to acquire read lock on clock: lock poisoned")
+    }
+}
@@ -0,0 +1,21 @@
+#[cfg(test)]
+mod tests {
+    use crate::util::clock::{Clock, ManualClock};
+    use chrono::{DateTime, Duration, Utc};
+
+    #[test]
+    fn test_manual_clock_set_and_advance() {
+        let start: DateTime<Utc> = "2030-01-01T00:00:00Z".parse().unwrap();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::hours(36));
+        assert_eq!(
+            clock.now(),
+            "2030-01-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+}
@@ -48,8 +48,13 @@ pub const VARIABLE_TYPES_BOOL: &str = "Boolean";
 pub const ROLLOUT_TYPE_SCHEDULE: &str = "schedule";
 pub const ROLLOUT_TYPE_PERCENTAGE: &str = "percentage";
 pub const ROLLOUT_TYPE_DISCRETE: &str = "discrete";
+pub const ROLLOUT_TYPE_GRADUAL: &str = "gradual";
+pub const ROLLOUT_TYPE_STEPPED: &str = "stepped";
+pub const ROLLOUT_TYPE_LINEAR: &str = "linear";
 
 pub const DEFAULT_BUCKETING_VALUE: &str = "null";
 
+pub const WINNING_VARIATION_DETAILS: &str = "Winning Variation";
+
 pub const BASE_SEED: u32 = 1;
 pub const MAX_HASH_VALUE: u32 = 4294967295;
@@ -1,117 +1,278 @@
+use crate::config::config_lint::ConfigLintFinding;
 use crate::events::event::DefaultReason;
 use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 
-#[derive(Debug)]
-pub struct DevCycleError {
-    pub(crate) details: String,
+type ErrorSource = Arc<dyn Error + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub enum DevCycleError {
+    MissingConfig,
+    MissingVariable,
+    MissingFeature,
+    MissingTarget,
+    MissingVariation,
+    MissingVariableForVariation,
+    FailedToDecideVariation,
+    UserNotTargeted,
+    UserNotInRollout,
+    PrerequisiteNotMet,
+    InvalidVariableType,
+    VariableTypeMismatch,
+    MissingPlatformData {
+        sdk_key: String,
+    },
+    Parse {
+        message: String,
+        source: Option<ErrorSource>,
+    },
+    MissingField(String),
+    InvalidEvent(String),
+    EventQueueNotInitialized,
+    EventQueueFull(String),
+    EventFlushFailed(Vec<String>),
+    ConfigFetchFailed {
+        message: String,
+        source: Option<ErrorSource>,
+    },
+    // Rejected by `set_config_strict` or a malformed rollout, holds the error findings
+    InvalidConfig(Vec<ConfigLintFinding>),
+    ClientDropped,
+    Other {
+        message: String,
+        source: Option<ErrorSource>,
+    },
 }
 
 impl DevCycleError {
     pub fn new(msg: &str) -> DevCycleError {
-        DevCycleError {
-            details: msg.to_string(),
+        DevCycleError::Other {
+            message: msg.to_string(),
+            source: None,
+        }
+    }
+
+    // Wraps a lower level error, keeping it available through `Error::source`
+    pub fn with_source<E>(msg: &str, source: E) -> DevCycleError
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        DevCycleError::Other {
+            message: msg.to_string(),
+            source: Some(Arc::new(source)),
+        }
+    }
+
+    /// Stable identifier of the error kind, suitable for matching across languages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DevCycleError::MissingConfig => "MISSING_CONFIG",
+            DevCycleError::MissingVariable => "MISSING_VARIABLE",
+            DevCycleError::MissingFeature => "MISSING_FEATURE",
+            DevCycleError::MissingTarget => "MISSING_TARGET",
+            DevCycleError::MissingVariation => "MISSING_VARIATION",
+            DevCycleError::MissingVariableForVariation => "MISSING_VARIABLE_FOR_VARIATION",
+            DevCycleError::FailedToDecideVariation => "FAILED_TO_DECIDE_VARIATION",
+            DevCycleError::UserNotTargeted => "USER_NOT_TARGETED",
+            DevCycleError::UserNotInRollout => "USER_NOT_IN_ROLLOUT",
+            DevCycleError::PrerequisiteNotMet => "PREREQUISITE_NOT_MET",
+            DevCycleError::InvalidVariableType => "INVALID_VARIABLE_TYPE",
+            DevCycleError::VariableTypeMismatch => "VARIABLE_TYPE_MISMATCH",
+            DevCycleError::MissingPlatformData { .. } => "MISSING_PLATFORM_DATA",
+            DevCycleError::Parse { .. } => "PARSE_ERROR",
+            DevCycleError::MissingField(_) => "MISSING_FIELD",
+            DevCycleError::InvalidEvent(_) => "INVALID_EVENT",
+            DevCycleError::EventQueueNotInitialized => "EVENT_QUEUE_NOT_INITIALIZED",
+            DevCycleError::EventQueueFull(_) => "EVENT_QUEUE_FULL",
+            DevCycleError::EventFlushFailed(_) => "EVENT_FLUSH_FAILED",
+            DevCycleError::ConfigFetchFailed { .. } => "CONFIG_FETCH_FAILED",
+            DevCycleError::InvalidConfig(_) => "INVALID_CONFIG",
+            DevCycleError::ClientDropped => "CLIENT_DROPPED",
+            DevCycleError::Other { .. } => "UNKNOWN",
+        }
+    }
+
+    /// The reason reported when this error causes a variable to fall back to its default.
+    pub fn default_reason(&self) -> DefaultReason {
+        match self {
+            DevCycleError::MissingConfig => DefaultReason::MissingConfig,
+            DevCycleError::MissingVariable => DefaultReason::MissingVariable,
+            DevCycleError::MissingFeature => DefaultReason::MissingFeature,
+            DevCycleError::MissingVariation => DefaultReason::MissingVariation,
+            DevCycleError::MissingVariableForVariation => {
+                DefaultReason::MissingVariableForVariation
+            }
+            DevCycleError::UserNotTargeted => DefaultReason::UserNotTargeted,
+            DevCycleError::UserNotInRollout => DefaultReason::UserNotInRollout,
+            DevCycleError::PrerequisiteNotMet => DefaultReason::PrerequisiteNotMet,
+            DevCycleError::InvalidVariableType => DefaultReason::InvalidVariableType,
+            DevCycleError::VariableTypeMismatch => DefaultReason::VariableTypeMismatch,
+            DevCycleError::MissingTarget
+            | DevCycleError::FailedToDecideVariation
+            | DevCycleError::MissingPlatformData { .. }
+            | DevCycleError::Parse { .. }
+            | DevCycleError::MissingField(_)
+            | DevCycleError::EventQueueNotInitialized
+            | DevCycleError::ClientDropped => DefaultReason::Error,
+            DevCycleError::InvalidEvent(_)
+            | DevCycleError::EventQueueFull(_)
+            | DevCycleError::EventFlushFailed(_)
+            | DevCycleError::ConfigFetchFailed { .. }
+            | DevCycleError::InvalidConfig(_)
+            | DevCycleError::Other { .. } => DefaultReason::Unknown,
         }
     }
 }
 
 impl fmt::Display for DevCycleError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.details)
+        match self {
+            DevCycleError::MissingConfig => write!(f, "Config not found"),
+            DevCycleError::MissingVariable => write!(f, "Variable not found"),
+            DevCycleError::MissingFeature => write!(f, "Feature not found"),
+            DevCycleError::MissingTarget => write!(f, "Target not found"),
+            DevCycleError::MissingVariation => write!(f, "Variation not found"),
+            DevCycleError::MissingVariableForVariation => {
+                write!(f, "Variable not found for variation")
+            }
+            DevCycleError::FailedToDecideVariation => {
+                write!(f, "Failed to decide target variation")
+            }
+            DevCycleError::UserNotTargeted => write!(f, "User does not qualify for any targets"),
+            DevCycleError::UserNotInRollout => write!(f, "User does not qualify for rollouts"),
+            DevCycleError::PrerequisiteNotMet => {
+                write!(f, "User does not meet feature prerequisites")
+            }
+            DevCycleError::InvalidVariableType => write!(f, "Invalid variable type"),
+            DevCycleError::VariableTypeMismatch => write!(f, "Variable type mismatch"),
+            DevCycleError::MissingPlatformData { sdk_key } => write!(
+                f,
+                "Platform data not set for SDK key: {}. Call set_platform_data() first.",
+                sdk_key
+            ),
+            DevCycleError::Parse { message, .. } => write!(f, "Parse error: {}", message),
+            DevCycleError::MissingField(field) => write!(f, "Missing required field: {}", field),
+            DevCycleError::InvalidEvent(msg) => write!(f, "{}", msg),
+            DevCycleError::EventQueueNotInitialized => write!(f, "Event queue not initialized"),
+            DevCycleError::EventQueueFull(msg) => {
+                write!(f, "dropping event, queue is full: {}", msg)
+            }
+            DevCycleError::EventFlushFailed(errors) => {
+                write!(f, "failed to flush events: {}", errors.join(", "))
+            }
+            DevCycleError::ConfigFetchFailed { message, .. } => write!(f, "{}", message),
+            DevCycleError::InvalidConfig(findings) => {
+                let messages: Vec<String> = findings
+                    .iter()
+                    .map(|finding| format!("{}: {}", finding.path, finding.message))
+                    .collect();
+                write!(f, "invalid config: {}", messages.join(", "))
+            }
+            DevCycleError::ClientDropped => write!(f, "the owning client was dropped"),
+            DevCycleError::Other { message, .. } => write!(f, "{}", message),
+        }
     }
 }
 
 impl Error for DevCycleError {
-    fn description(&self) -> &str {
-        &self.details
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DevCycleError::Parse {
+                source: Some(source),
+                ..
+            }
+            | DevCycleError::ConfigFetchFailed {
+                source: Some(source),
+                ..
+            }
+            | DevCycleError::Other {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            _ => None,
+        }
     }
 }
 
-pub const FAILED_TO_DECIDE_VARIATION: DevCycleError = DevCycleError {
-    details: String::new(),
-};
-pub const FAILED_USER_DOES_NOT_QUALIFY_FOR_TARGETS: DevCycleError = DevCycleError {
-    details: String::new(),
-};
-pub const FAILED_USER_DOES_NOT_QUALIFY_FOR_ROLLOUTS: DevCycleError = DevCycleError {
-    details: String::new(),
-};
-pub const MISSING_VARIABLE: DevCycleError = DevCycleError {
-    details: String::new(),
-};
-pub const MISSING_VARIATION: DevCycleError = DevCycleError {
-    details: String::new(),
-};
+impl From<serde_json::Error> for DevCycleError {
+    fn from(err: serde_json::Error) -> Self {
+        DevCycleError::Parse {
+            message: err.to_string(),
+            source: Some(Arc::new(err)),
+        }
+    }
+}
 
 // Helper functions to create errors with proper messages
 pub(crate) fn failed_to_decide_variation() -> DevCycleError {
-    DevCycleError::new("Failed to decide target variation")
+    DevCycleError::FailedToDecideVariation
 }
 
 pub(crate) fn failed_user_does_not_qualify_for_targets() -> DevCycleError {
-    DevCycleError::new("User does not qualify for any targets")
+    DevCycleError::UserNotTargeted
 }
 
 pub(crate) fn failed_user_does_not_qualify_for_rollouts() -> DevCycleError {
-    DevCycleError::new("User does not qualify for rollouts")
+    DevCycleError::UserNotInRollout
+}
+
+pub(crate) fn failed_user_does_not_meet_prerequisites() -> DevCycleError {
+    DevCycleError::PrerequisiteNotMet
 }
 
 pub(crate) fn missing_variable() -> DevCycleError {
-    DevCycleError::new("Variable not found")
+    DevCycleError::MissingVariable
 }
 
 pub(crate) fn missing_variation() -> DevCycleError {
-    DevCycleError::new("Variation not found")
+    DevCycleError::MissingVariation
 }
 
 pub(crate) fn missing_config() -> DevCycleError {
-    DevCycleError::new("Config not found")
+    DevCycleError::MissingConfig
 }
 
 pub(crate) fn missing_feature() -> DevCycleError {
-    DevCycleError::new("Feature not found")
+    DevCycleError::MissingFeature
 }
 
-pub(crate) fn missing_variable_for_variation() -> DevCycleError {
-    DevCycleError::new("Variable not found for variation")
+pub(crate) fn missing_target() -> DevCycleError {
+    DevCycleError::MissingTarget
 }
 
-pub(crate) fn invalid_variable_type() -> DevCycleError {
-    DevCycleError::new("Invalid variable type")
+pub(crate) fn missing_variable_for_variation() -> DevCycleError {
+    DevCycleError::MissingVariableForVariation
 }
 
-pub(crate) fn variable_type_mismatch() -> DevCycleError {
-    DevCycleError::new("Variable type mismatch")
+pub(crate) fn invalid_variable_type() -> DevCycleError {
+    DevCycleError::InvalidVariableType
 }
 
-pub fn failed_to_set_client_custom_data() -> DevCycleError {
-    DevCycleError::new("Failed to set client custom data")
+pub(crate) fn missing_platform_data(sdk_key: &str) -> DevCycleError {
+    DevCycleError::MissingPlatformData {
+        sdk_key: sdk_key.to_string(),
+    }
 }
 
-pub fn parse_error(msg: String) -> DevCycleError {
-    DevCycleError::new(&format!("Parse error: {}", msg))
+pub(crate) fn invalid_event(msg: &str) -> DevCycleError {
+    DevCycleError::InvalidEvent(msg.to_string())
 }
 
-pub fn missing_field(field: String) -> DevCycleError {
-    DevCycleError::new(&format!("Missing required field: {}", field))
+pub(crate) fn event_queue_full(msg: String) -> DevCycleError {
+    DevCycleError::EventQueueFull(msg)
 }
 
-pub(crate) fn bucket_result_error_to_default_reason(err: &DevCycleError) -> DefaultReason {
-    match err.details.as_str() {
-        "Missing config" => DefaultReason::MissingConfig,
-        "Missing variable" => DefaultReason::MissingVariable,
-        "Missing feature" => DefaultReason::MissingFeature,
-        "Missing variation" => DefaultReason::MissingVariation,
-        "Missing variable for variation" => DefaultReason::MissingVariableForVariation,
-        "User does not qualify for rollouts" => DefaultReason::UserNotInRollout,
-        "User does not qualify for targets" => DefaultReason::UserNotTargeted,
-        "Invalid variable type" => DefaultReason::InvalidVariableType,
-        "Variable type mismatch" => DefaultReason::VariableTypeMismatch,
-        "" => DefaultReason::NotDefaulted,
-        _ => DefaultReason::Unknown,
+pub(crate) fn config_fetch_failed<E>(msg: &str, source: E) -> DevCycleError
+where
+    E: Error + Send + Sync + 'static,
+{
+    DevCycleError::ConfigFetchFailed {
+        message: msg.to_string(),
+        source: Some(Arc::new(source)),
     }
 }
 
 pub(crate) fn event_queue_not_initialized() -> DevCycleError {
-    return DevCycleError::new("Event queue not initialized");
+    DevCycleError::EventQueueNotInitialized
 }
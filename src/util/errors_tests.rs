@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::errors::{self, DevCycleError};
+    use crate::events::event::DefaultReason;
+    use std::error::Error;
+
+    #[test]
+    fn test_default_reason_mapping() {
+        let cases = [
+            (errors::missing_config(), DefaultReason::MissingConfig),
+            (errors::missing_variable(), DefaultReason::MissingVariable),
+            (errors::missing_feature(), DefaultReason::MissingFeature),
+            (errors::missing_variation(), DefaultReason::MissingVariation),
+            (
+                errors::missing_variable_for_variation(),
+                DefaultReason::MissingVariableForVariation,
+            ),
+            (
+                errors::failed_user_does_not_qualify_for_targets(),
+                DefaultReason::UserNotTargeted,
+            ),
+            (
+                errors::failed_user_does_not_qualify_for_rollouts(),
+                DefaultReason::UserNotInRollout,
+            ),
+            (
+                errors::failed_user_does_not_meet_prerequisites(),
+                DefaultReason::PrerequisiteNotMet,
+            ),
+            (
+                errors::invalid_variable_type(),
+                DefaultReason::InvalidVariableType,
+            ),
+            (
+                DevCycleError::VariableTypeMismatch,
+                DefaultReason::VariableTypeMismatch,
+            ),
+            (errors::event_queue_not_initialized(), DefaultReason::Error),
+            (DevCycleError::new("something else"), DefaultReason::Unknown),
+        ];
+        for (err, reason) in cases {
+            assert_eq!(err.default_reason(), reason, "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        assert_eq!(errors::missing_config().code(), "MISSING_CONFIG");
+        assert_eq!(
+            errors::failed_user_does_not_qualify_for_rollouts().code(),
+            "USER_NOT_IN_ROLLOUT"
+        );
+        assert_eq!(
+            errors::missing_platform_data("key").code(),
+            "MISSING_PLATFORM_DATA"
+        );
+        assert_eq!(DevCycleError::new("other").code(), "UNKNOWN");
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(errors::missing_config().to_string(), "Config not found");
+        assert_eq!(
+            errors::missing_platform_data("my-key").to_string(),
+            "Platform data not set for SDK key: my-key. Call set_platform_data() first."
+        );
+        assert_eq!(
+            DevCycleError::EventFlushFailed(vec!["a".to_string(), "b".to_string()]).to_string(),
+            "failed to flush events: a, b"
+        );
+    }
+
+    #[test]
+    fn test_source_is_chained() {
+        let json_err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let expected = json_err.to_string();
+        let err = DevCycleError::from(json_err);
+
+        assert_eq!(err.code(), "PARSE_ERROR");
+        assert_eq!(err.source().unwrap().to_string(), expected);
+
+        let io_err = std::io::Error::other("disk full");
+        let err = DevCycleError::with_source("failed to write", io_err);
+        assert_eq!(err.to_string(), "failed to write");
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+        assert!(errors::missing_config().source().is_none());
+
+        let err = errors::config_fetch_failed(
+            "failed to fetch config",
+            std::io::Error::other("connection refused"),
+        );
+        assert_eq!(err.to_string(), "failed to fetch config");
+        assert_eq!(err.source().unwrap().to_string(), "connection refused");
+    }
+}
@@ -1,7 +1,16 @@
+pub(crate) mod clock;
 pub(crate) mod constants;
 pub mod errors;
 pub(crate) mod murmurhash;
+#[cfg(test)]
+pub(crate) mod stub_server;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 
+#[cfg(test)]
+mod clock_tests;
+#[cfg(test)]
+mod errors_tests;
 #[cfg(test)]
 mod murmurhash_tests;
 
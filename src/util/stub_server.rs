@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// A request received by the stub server, header names are lower cased.
+#[derive(Clone)]
+pub(crate) struct StubRequest {
+    pub(crate) path: String,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl StubRequest {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    pub(crate) fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+}
+
+pub(crate) enum StubBody {
+    Full(String),
+    // Written chunk by chunk until the sender is dropped
+    Stream(mpsc::Receiver<String>),
+}
+
+pub(crate) struct StubResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: StubBody,
+}
+
+impl StubResponse {
+    pub(crate) fn new(status: u16) -> Self {
+        StubResponse {
+            status,
+            headers: Vec::new(),
+            body: StubBody::Full(String::new()),
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub(crate) fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = StubBody::Full(body.into());
+        self
+    }
+
+    pub(crate) fn stream(mut self, chunks: mpsc::Receiver<String>) -> Self {
+        self.body = StubBody::Stream(chunks);
+        self
+    }
+}
+
+// Minimal HTTP/1.1 server standing in for the config CDN and events API in tests. Every
+// connection is answered by `handler` and then closed. Returns the server's base URI.
+pub(crate) async fn start_stub_server<F>(handler: F) -> String
+where
+    F: Fn(&StubRequest) -> StubResponse + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_uri = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move { serve_connection(socket, handler.as_ref()).await });
+        }
+    });
+    base_uri
+}
+
+async fn serve_connection<F>(mut socket: TcpStream, handler: &F)
+where
+    F: Fn(&StubRequest) -> StubResponse,
+{
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let response = handler(&request);
+
+    let mut head = format!("HTTP/1.1 {} Stub\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    match response.body {
+        StubBody::Full(body) => {
+            head.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ));
+            let _ = socket.write_all(head.as_bytes()).await;
+        }
+        StubBody::Stream(mut chunks) => {
+            head.push_str("Connection: close\r\n\r\n");
+            if socket.write_all(head.as_bytes()).await.is_err() {
+                return;
+            }
+            while let Some(chunk) = chunks.recv().await {
+                if socket.write_all(chunk.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    let _ = socket.shutdown().await;
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<StubRequest> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let path = lines.next()?.split_whitespace().nth(1)?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_lowercase(), value.trim().to_string()))
+        })
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    Some(StubRequest {
+        path,
+        headers,
+        body: buffer[header_end..].to_vec(),
+    })
+}
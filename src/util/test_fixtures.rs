@@ -0,0 +1,67 @@
+use crate::client::DevCycleLocalBucketing;
+use crate::config::platform_data::PlatformData;
+use crate::config::{ConfigBody, FullConfig};
+use crate::user::PopulatedUser;
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Shared by the tests that start from tests/resources/test_config.json: a single "test"
+// feature whose only target splits everyone 50/50 between two variations.
+
+pub(crate) const TEST_CONFIG: &str = include_str!("../../tests/resources/test_config.json");
+pub(crate) const TEST_FEATURE_KEY: &str = "test";
+pub(crate) const TEST_TARGET_ID: &str = "621642332ea68943c8833c4d";
+pub(crate) const VARIATION_ON: &str = "6216422850294da359385e8f";
+pub(crate) const VARIATION_OFF: &str = "6216422850294da359385e90";
+
+pub(crate) fn test_config_json() -> Value {
+    serde_json::from_str(TEST_CONFIG).unwrap()
+}
+
+pub(crate) fn full_config(json: Value) -> FullConfig {
+    serde_json::from_value(json).unwrap()
+}
+
+pub(crate) fn config_body(json: Value) -> ConfigBody {
+    ConfigBody::from_full_config(full_config(json)).unwrap()
+}
+
+// A new client with `json` set as its config
+pub(crate) fn client_with_config(sdk_key: &str, json: Value) -> DevCycleLocalBucketing {
+    let client = DevCycleLocalBucketing::new(sdk_key);
+    client.set_config(config_body(json)).unwrap();
+    client
+}
+
+pub(crate) fn test_platform_data() -> PlatformData {
+    PlatformData {
+        sdk_type: "server".to_string(),
+        sdk_version: "1.0.0".to_string(),
+        platform_version: "1.0.0".to_string(),
+        device_model: "test-device".to_string(),
+        platform: "test".to_string(),
+        hostname: "localhost".to_string(),
+    }
+}
+
+// A user with only an ID, other fields can be set with struct update syntax
+pub(crate) fn create_user(user_id: &str) -> PopulatedUser {
+    PopulatedUser {
+        user_id: user_id.to_string(),
+        email: String::new(),
+        name: String::new(),
+        language: String::new(),
+        country: String::new(),
+        ip: String::new(),
+        app_version: String::new(),
+        app_build: String::new(),
+        custom_data: HashMap::new(),
+        private_custom_data: HashMap::new(),
+        device_model: String::new(),
+        last_seen_date: Utc::now(),
+        platform_data: Arc::new(test_platform_data()),
+        created_date: Utc::now(),
+    }
+}
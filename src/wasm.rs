@@ -4,6 +4,7 @@
 use crate::bucketing::VariableForUserResult;
 use crate::config::platform_data::PlatformData;
 use crate::events::EventQueueOptions;
+use crate::events::event::CustomEvent;
 use crate::user::{PopulatedUser, User};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -111,6 +112,7 @@ impl WasmEventQueueOptions {
 impl From<WasmEventQueueOptions> for EventQueueOptions {
     fn from(wasm_opts: WasmEventQueueOptions) -> Self {
         EventQueueOptions {
+            event_sink: None,
             flush_events_interval: Duration::from_millis(wasm_opts.flush_events_interval_ms),
             disable_automatic_event_logging: wasm_opts.disable_automatic_event_logging,
             disable_custom_event_logging: wasm_opts.disable_custom_event_logging,
@@ -152,10 +154,18 @@ pub fn set_config_data(sdk_key: String, config_json: String) -> Result<(), JsVal
     Ok(())
 }
 
+/// Set config data for SDK key from a serialized `ConfigBodyProto` message
+#[cfg(feature = "protobuf")]
+#[wasm_bindgen]
+pub fn set_config_data_from_protobuf(sdk_key: String, config_bytes: &[u8]) -> Result<(), JsValue> {
+    crate::set_config_from_protobuf_bytes(&sdk_key, config_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Error creating config body: {}", e)))
+}
+
 /// Check if config data exists for SDK key
 #[wasm_bindgen]
 pub fn has_config_data(sdk_key: String) -> bool {
-    crate::configmanager::has_config(&sdk_key)
+    crate::configmanager::get_config(&sdk_key).is_some()
 }
 
 /// Set client custom data for SDK key from JSON string
@@ -174,7 +184,7 @@ pub fn set_client_custom_data(
 
 /// Get variable value for user (JSON input/output), returns stringified SDKVariable or null
 #[wasm_bindgen]
-pub async fn variable_for_user(
+pub fn variable_for_user(
     sdk_key: String,
     user_json_str: String,
     variable_key: String,
@@ -188,12 +198,8 @@ pub async fn variable_for_user(
     let populated_user = user.get_populated_user(&sdk_key);
 
     // Get client_custom_data from global storage (or use empty if not set)
-    let client_custom_data = crate::config::client_custom_data::CLIENT_CUSTOM_DATA
-        .read()
-        .unwrap()
-        .get(&sdk_key)
-        .cloned()
-        .unwrap_or_else(HashMap::new);
+    let client_custom_data =
+        crate::config::client_custom_data::get_client_custom_data(sdk_key.clone());
 
     unsafe {
         let result = crate::bucketing::variable_for_user(
@@ -202,8 +208,7 @@ pub async fn variable_for_user(
             &variable_key,
             &variable_type,
             client_custom_data,
-        )
-        .await;
+        );
 
         match result {
             Ok(VariableForUserResult {
@@ -214,6 +219,8 @@ pub async fn variable_for_user(
                 feature_id,
                 variation_id,
                 eval_reason,
+                eval_details,
+                target_id,
                 default_reason: _default_reason,
             }) => {
                 // Check if variable type matches expected type (matching AS behavior)
@@ -233,8 +240,8 @@ pub async fn variable_for_user(
                     feature: Some(feature_id),
                     eval_reason: WasmEvalReason {
                         reason: format!("{:?}", eval_reason),
-                        details: String::new(),
-                        target_id: String::new(),
+                        details: eval_details.unwrap_or_default(),
+                        target_id: target_id.unwrap_or_default(),
                     },
                 };
 
@@ -266,13 +273,50 @@ pub async fn init_event_queue(
     unsafe {
         crate::init_event_queue(&sdk_key, event_options)
             .await
+            .map(|_| ())
             .map_err(|e| JsValue::from_str(&format!("Error initializing event queue: {:?}", e)))
     }
 }
 
+/// Process and publish queued events, returns the number of events reported. Nothing
+/// processes the queue in the background under WASM, so call this on the flush interval
+/// or evaluations and `track` start failing once the queue is full.
+#[wasm_bindgen]
+pub async fn flush_events(sdk_key: String) -> Result<f64, JsValue> {
+    crate::flush_events(&sdk_key)
+        .await
+        .map(|reported| reported as f64)
+        .map_err(|e| JsValue::from_str(&format!("Error flushing events: {:?}", e)))
+}
+
+/// Stop the event queue, publishing everything queued in a final flush
+#[wasm_bindgen]
+pub async fn close(sdk_key: String) -> Result<(), JsValue> {
+    crate::close(&sdk_key)
+        .await
+        .map_err(|e| JsValue::from_str(&format!("Error closing event queue: {:?}", e)))
+}
+
+/// Track a custom event for a user (JSON input), returns false if custom event logging is disabled
+/// The event is published by the next `flush_events` or `close`
+#[wasm_bindgen]
+pub fn track(
+    sdk_key: String,
+    user_json_str: String,
+    event_json_str: String,
+) -> Result<bool, JsValue> {
+    let user: User = serde_json::from_str(&user_json_str)
+        .map_err(|e| JsValue::from_str(&format!("Invalid user JSON: {:?}", e)))?;
+    let custom_event: CustomEvent = serde_json::from_str(&event_json_str)
+        .map_err(|e| JsValue::from_str(&format!("Invalid event JSON: {:?}", e)))?;
+
+    crate::track(&sdk_key, user, custom_event)
+        .map_err(|e| JsValue::from_str(&format!("Error tracking event: {:?}", e)))
+}
+
 /// Generate bucketed config from user (JSON input/output)
 #[wasm_bindgen]
-pub async fn generate_bucketed_config_from_user(
+pub fn generate_bucketed_config_from_user(
     sdk_key: String,
     user_json: JsValue,
 ) -> Result<JsValue, JsValue> {
@@ -280,11 +324,9 @@ pub async fn generate_bucketed_config_from_user(
         .map_err(|e| JsValue::from_str(&format!("Invalid user JSON: {:?}", e)))?;
 
     unsafe {
-        let config = crate::generate_bucketed_config_from_user(&sdk_key, user)
-            .await
-            .map_err(|e| {
-                JsValue::from_str(&format!("Error generating bucketed config: {:?}", e))
-            })?;
+        let config = crate::generate_bucketed_config_from_user(&sdk_key, user).map_err(|e| {
+            JsValue::from_str(&format!("Error generating bucketed config: {:?}", e))
+        })?;
 
         serde_wasm_bindgen::to_value(&config)
             .map_err(|e| JsValue::from_str(&format!("Error serializing config: {:?}", e)))
@@ -293,7 +335,7 @@ pub async fn generate_bucketed_config_from_user(
 
 /// Generate bucketed config from populated user (JSON input/output)
 #[wasm_bindgen]
-pub async fn generate_bucketed_config(
+pub fn generate_bucketed_config(
     sdk_key: String,
     populated_user_json: JsValue,
     client_custom_data_json: JsValue,
@@ -310,13 +352,69 @@ pub async fn generate_bucketed_config(
         };
 
     let config = crate::generate_bucketed_config(&sdk_key, populated_user, client_custom_data)
-        .await
         .map_err(|e| JsValue::from_str(&format!("Error generating bucketed config: {:?}", e)))?;
 
     serde_wasm_bindgen::to_value(&config)
         .map_err(|e| JsValue::from_str(&format!("Error serializing config: {:?}", e)))
 }
 
+/// Generate bucketed config from populated user with a trace of how every feature was evaluated
+#[wasm_bindgen]
+pub fn explain_bucketed_config(
+    sdk_key: String,
+    populated_user_json: JsValue,
+    client_custom_data_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let populated_user: PopulatedUser = serde_wasm_bindgen::from_value(populated_user_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid populated user JSON: {:?}", e)))?;
+
+    let client_custom_data: HashMap<String, serde_json::Value> =
+        if client_custom_data_json.is_undefined() || client_custom_data_json.is_null() {
+            HashMap::new()
+        } else {
+            serde_wasm_bindgen::from_value(client_custom_data_json)
+                .map_err(|e| JsValue::from_str(&format!("Invalid client custom data: {:?}", e)))?
+        };
+
+    let explanation = crate::explain_bucketed_config(&sdk_key, populated_user, client_custom_data)
+        .map_err(|e| JsValue::from_str(&format!("Error explaining bucketed config: {:?}", e)))?;
+
+    serde_wasm_bindgen::to_value(&explanation)
+        .map_err(|e| JsValue::from_str(&format!("Error serializing explanation: {:?}", e)))
+}
+
+/// Evaluate a variable for user (JSON input) with a trace of how its feature was evaluated,
+/// without queueing events
+#[wasm_bindgen]
+pub fn explain_variable_for_user(
+    sdk_key: String,
+    user_json_str: String,
+    variable_key: String,
+    variable_type: String,
+) -> Result<JsValue, JsValue> {
+    let user: User = serde_json::from_str(&user_json_str)
+        .map_err(|e| JsValue::from_str(&format!("Invalid user JSON: {:?}", e)))?;
+    let populated_user = user.get_populated_user(&sdk_key);
+
+    let explanation =
+        crate::explain_variable_for_user(&sdk_key, populated_user, &variable_key, &variable_type)
+            .map_err(|e| JsValue::from_str(&format!("Error explaining variable: {:?}", e)))?;
+
+    serde_wasm_bindgen::to_value(&explanation)
+        .map_err(|e| JsValue::from_str(&format!("Error serializing explanation: {:?}", e)))
+}
+
+/// Run a conformance vector file (see tests/conformance) against this library's bucketing.
+/// A report with failures is still returned; files that can't be run are an error.
+#[wasm_bindgen]
+pub fn run_conformance_vectors(vector_json_str: String) -> Result<JsValue, JsValue> {
+    let report = crate::run_conformance_vectors(&vector_json_str)
+        .map_err(|e| JsValue::from_str(&format!("Error running conformance vectors: {:?}", e)))?;
+
+    serde_wasm_bindgen::to_value(&report)
+        .map_err(|e| JsValue::from_str(&format!("Error serializing conformance report: {:?}", e)))
+}
+
 /// Get library version
 #[wasm_bindgen]
 pub fn version() -> String {
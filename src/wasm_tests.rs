@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::platform_data::PlatformData;
+    use crate::events::EventQueueOptions;
+    use crate::events::event_sink::InMemoryEventSink;
+    use crate::user::User;
+    use crate::wasm;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn user_json(user_id: &str) -> String {
+        serde_json::to_string(&User {
+            user_id: user_id.to_string(),
+            email: String::new(),
+            name: String::new(),
+            language: String::new(),
+            country: String::new(),
+            ip: String::new(),
+            app_version: String::new(),
+            app_build: String::new(),
+            custom_data: HashMap::new(),
+            private_custom_data: HashMap::new(),
+            device_model: String::new(),
+            last_seen_date: chrono::Utc::now(),
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tracked_events_are_published_by_flush_events() {
+        let sdk_key = "test_wasm_flush_events";
+        crate::set_platform_data(
+            sdk_key,
+            PlatformData {
+                platform: "wasm".to_string(),
+                platform_version: "1.0.0".to_string(),
+                sdk_type: "server".to_string(),
+                sdk_version: "1.0.0".to_string(),
+                hostname: "localhost".to_string(),
+                device_model: "test".to_string(),
+            },
+        );
+        wasm::set_config_data(
+            sdk_key.to_string(),
+            include_str!("../tests/resources/test_config.json").to_string(),
+        )
+        .unwrap();
+        let sink = Arc::new(InMemoryEventSink::new());
+        let handle = crate::init_event_queue(
+            sdk_key,
+            EventQueueOptions {
+                event_sink: Some(sink.clone()),
+                flush_events_interval: Duration::from_secs(3600),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        // WASM builds never start the background task
+        handle.shutdown();
+
+        for i in 0..3 {
+            let tracked = wasm::track(
+                sdk_key.to_string(),
+                user_json(&format!("wasm-user-{}", i)),
+                r#"{"type": "purchase"}"#.to_string(),
+            )
+            .unwrap();
+            assert!(tracked);
+        }
+        assert!(sink.batches().is_empty());
+
+        assert_eq!(wasm::flush_events(sdk_key.to_string()).await.unwrap(), 3.0);
+        assert_eq!(sink.event_count(), 3);
+
+        wasm::track(
+            sdk_key.to_string(),
+            user_json("wasm-user-3"),
+            r#"{"type": "purchase"}"#.to_string(),
+        )
+        .unwrap();
+        wasm::close(sdk_key.to_string()).await.unwrap();
+        assert_eq!(sink.event_count(), 4);
+    }
+}
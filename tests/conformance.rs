@@ -0,0 +1,40 @@
+use devcycle_bucketing_rs::run_conformance_vectors;
+use std::fs;
+use std::path::Path;
+
+// Runs every vector file in tests/conformance, the same files the other SDKs run
+#[test]
+fn test_conformance_vectors() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("Failed to read conformance vector directory")
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    assert!(
+        !paths.is_empty(),
+        "No conformance vectors in {}",
+        dir.display()
+    );
+
+    for path in paths {
+        let vectors = fs::read_to_string(&path).expect("Failed to read conformance vectors");
+        let report = run_conformance_vectors(&vectors)
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert!(
+            report.passed(),
+            "{} failed:\n{}",
+            path.display(),
+            serde_json::to_string_pretty(&report.failures).unwrap()
+        );
+        assert!(
+            report.hashes + report.cases > 0,
+            "{} is empty",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,167 @@
+#!/usr/bin/env python3
+"""Writes tests/conformance/reference_split.json without running this crate.
+
+The hashes and variations come from a standalone MurmurHash3 (x86, 32 bit) and the
+bucketing steps as the JS and Go SDKs implement them:
+
+- the target hash is murmurhash(target ID, seed 1)
+- the rollout hash is murmurhash(bucketing value + "_rollout", target hash) / (2^32 - 1)
+- the bucketing hash is murmurhash(bucketing value, target hash) / (2^32 - 1)
+- a target's distribution, sorted by variation ID in descending order, is walked until
+  the running total of percentages passes the bucketing hash
+
+The murmurhash is checked against published MurmurHash3 test values before anything is
+written. Run from the repository root:
+
+    python3 tests/conformance/reference_vectors.py
+"""
+
+import json
+import struct
+from pathlib import Path
+
+BASE_SEED = 1
+MAX_HASH_VALUE = 0xFFFFFFFF
+CONFORMANCE_DIR = Path(__file__).resolve().parent
+
+# (input, seed, hash) from the MurmurHash3 reference implementation
+MURMURHASH_TEST_VALUES = [
+    ("", 0, 0x00000000),
+    ("", 1, 0x514E28B7),
+    ("", 0xFFFFFFFF, 0x81F16F39),
+    ("Hello, world!", 1234, 0xFAF6CDB3),
+    ("The quick brown fox jumps over the lazy dog", 0x9747B28C, 0x2FA826CD),
+]
+
+
+def murmurhash(text, seed):
+    data = text.encode("utf-8")
+    c1, c2 = 0xCC9E2D51, 0x1B873593
+    h = seed & 0xFFFFFFFF
+    rotl = lambda x, r: ((x << r) | (x >> (32 - r))) & 0xFFFFFFFF
+
+    block_end = len(data) - len(data) % 4
+    for i in range(0, block_end, 4):
+        (k,) = struct.unpack_from("<I", data, i)
+        k = rotl((k * c1) & 0xFFFFFFFF, 15)
+        h ^= (k * c2) & 0xFFFFFFFF
+        h = (rotl(h, 13) * 5 + 0xE6546B64) & 0xFFFFFFFF
+
+    k = 0
+    for i, byte in enumerate(data[block_end:]):
+        k |= byte << (8 * i)
+    if k:
+        k = rotl((k * c1) & 0xFFFFFFFF, 15)
+        h ^= (k * c2) & 0xFFFFFFFF
+
+    h ^= len(data)
+    h ^= h >> 16
+    h = (h * 0x85EBCA6B) & 0xFFFFFFFF
+    h ^= h >> 13
+    h = (h * 0xC2B2AE35) & 0xFFFFFFFF
+    h ^= h >> 16
+    return h
+
+
+def bounded_hashes(bucketing_value, target_id):
+    target_hash = murmurhash(target_id, BASE_SEED)
+    return (
+        murmurhash(bucketing_value + "_rollout", target_hash) / MAX_HASH_VALUE,
+        murmurhash(bucketing_value, target_hash) / MAX_HASH_VALUE,
+    )
+
+
+def bucketing_value(bucketing_key, user):
+    if bucketing_key in ("", "user_id"):
+        return user["userId"]
+    value = {**user.get("customData", {}), **user.get("privateCustomData", {})}.get(bucketing_key)
+    if value is None:
+        return "null"
+    # Only integer values are used, which every SDK prints the same way
+    return str(value)
+
+
+def decide_variation(target, bucketing_hash):
+    total = 0.0
+    for entry in sorted(target["distribution"], key=lambda d: d["_variation"], reverse=True):
+        total += entry["percentage"]
+        if bucketing_hash < total or (total == 1.0 and bucketing_hash == 1.0):
+            return entry["_variation"]
+    raise ValueError("distribution does not cover {}".format(bucketing_hash))
+
+
+def main():
+    for text, seed, expected in MURMURHASH_TEST_VALUES:
+        actual = murmurhash(text, seed)
+        assert actual == expected, "murmurhash({!r}, {}) = {:#x}".format(text, seed, actual)
+
+    # The 50/50 split config of split.json
+    config = json.loads((CONFORMANCE_DIR / "split.json").read_text())["config"]
+    feature = config["features"][0]
+    target = feature["configuration"]["targets"][0]
+    variables = {variable["_id"]: variable for variable in config["variables"]}
+
+    hashes = []
+    for bucketing_key, user, target_id in [
+        ("user_id", {"userId": "reference-user-1"}, target["_id"]),
+        ("user_id", {"userId": "reference-user-2"}, target["_id"]),
+        ("user_id", {"userId": "ünïcödé-user"}, target["_id"]),
+        ("user_id", {"userId": "reference-user-1"}, "61536f3bc838a705c105eb62"),
+        ("accountId", {"userId": "reference-user-3", "customData": {"accountId": 1234}}, target["_id"]),
+        ("accountId", {"userId": "reference-user-4", "privateCustomData": {"accountId": 7}}, target["_id"]),
+        ("accountId", {"userId": "reference-user-5"}, target["_id"]),
+    ]:
+        value = bucketing_value(bucketing_key, user)
+        rollout_hash, bucketing_hash = bounded_hashes(value, target_id)
+        hashes.append({
+            "bucketingKey": bucketing_key,
+            "user": user,
+            "targetId": target_id,
+            "bucketingValue": value,
+            "rolloutHash": rollout_hash,
+            "bucketingHash": bucketing_hash,
+        })
+
+    cases = []
+    for user_id in ["reference-user-{}".format(i) for i in range(1, 9)]:
+        _, bucketing_hash = bounded_hashes(user_id, target["_id"])
+        variation_id = decide_variation(target, bucketing_hash)
+        variation = next(v for v in feature["variations"] if v["_id"] == variation_id)
+        cases.append({
+            "name": user_id,
+            "user": {"userId": user_id},
+            "time": "2030-01-01T00:00:00Z",
+            "expected": {
+                "features": {
+                    feature["key"]: {
+                        "variationId": variation_id,
+                        "variationKey": variation["key"],
+                        "evalReason": "SPLIT",
+                    }
+                },
+                "variables": {
+                    variables[v["_var"]]["key"]: {
+                        "type": variables[v["_var"]]["type"],
+                        "value": v["value"],
+                        "evalReason": "SPLIT",
+                    }
+                    for v in variation["variables"]
+                },
+            },
+        })
+
+    vectors = {
+        "version": 1,
+        "description": "The split.json config with hashes and variations derived by "
+        "tests/conformance/reference_vectors.py, independently of this crate",
+        "config": config,
+        "hashes": hashes,
+        "cases": cases,
+    }
+    path = CONFORMANCE_DIR / "reference_split.json"
+    path.write_text(json.dumps(vectors, indent=2, ensure_ascii=False) + "\n")
+    print("Wrote {}".format(path))
+
+
+if __name__ == "__main__":
+    main()